@@ -0,0 +1,699 @@
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::codec::{read_u16, read_u32, write_u16, write_u32};
+use crate::text::{latin1_decode, latin1_encode, split_null, validate_keyword};
+use anyhow::{anyhow, Result};
+use std::fmt::{Display, Formatter};
+use std::str::FromStr;
+
+/// Background color (`bKGD`). The layout depends on the image color type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bkgd {
+    PaletteIndex(u8),
+    Gray(u16),
+    Rgb(u16, u16, u16),
+}
+
+impl Bkgd {
+    pub const CHUNK_TYPE: &'static str = "bKGD";
+
+    pub fn from_chunk(chunk: &Chunk, color_type: u8) -> Result<Self> {
+        check_type(chunk, Bkgd::CHUNK_TYPE)?;
+        let data = chunk.data();
+        let expected = match color_type {
+            3 => 1,
+            0 | 4 => 2,
+            2 | 6 => 6,
+            _ => return Err(anyhow!("Invalid bKGD Chunk : Color Type {}", color_type)),
+        };
+        if data.len() != expected {
+            return Err(anyhow!(
+                "Invalid bKGD Chunk : Length {} , Should Be {}",
+                data.len(),
+                expected
+            ));
+        }
+        Ok(match color_type {
+            3 => Bkgd::PaletteIndex(data[0]),
+            0 | 4 => Bkgd::Gray(read_u16(data, 0)?),
+            _ => Bkgd::Rgb(read_u16(data, 0)?, read_u16(data, 2)?, read_u16(data, 4)?),
+        })
+    }
+    /// Checks that samples fit in `bit_depth` and palette indexes are in range.
+    pub fn validate(&self, bit_depth: u8, palette_len: Option<usize>) -> Result<()> {
+        let max = sample_max(bit_depth);
+        match *self {
+            Bkgd::PaletteIndex(i) => match palette_len {
+                Some(len) if (i as usize) < len => Ok(()),
+                Some(len) => Err(anyhow!(
+                    "Invalid bKGD Chunk : Palette Index {} Out Of Range {}",
+                    i,
+                    len
+                )),
+                None => Err(anyhow!("Invalid bKGD Chunk : Palette Index Without PLTE")),
+            },
+            Bkgd::Gray(g) if g > max => Err(anyhow!(
+                "Invalid bKGD Chunk : Gray {} Exceeds Bit Depth {}",
+                g,
+                bit_depth
+            )),
+            Bkgd::Rgb(r, g, b) if r > max || g > max || b > max => Err(anyhow!(
+                "Invalid bKGD Chunk : Color ({}, {}, {}) Exceeds Bit Depth {}",
+                r,
+                g,
+                b,
+                bit_depth
+            )),
+            _ => Ok(()),
+        }
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        match *self {
+            Bkgd::PaletteIndex(i) => data.push(i),
+            Bkgd::Gray(g) => write_u16(&mut data, g),
+            Bkgd::Rgb(r, g, b) => {
+                for v in [r, g, b] {
+                    write_u16(&mut data, v);
+                }
+            }
+        }
+        new_chunk(Bkgd::CHUNK_TYPE, data)
+    }
+}
+
+/// Significant bits (`sBIT`): one entry per channel of the color type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Sbit {
+    pub color_type: u8,
+    pub bits: Vec<u8>,
+}
+
+impl Sbit {
+    pub const CHUNK_TYPE: &'static str = "sBIT";
+
+    pub fn from_chunk(chunk: &Chunk, color_type: u8) -> Result<Self> {
+        check_type(chunk, Sbit::CHUNK_TYPE)?;
+        let expected = match color_type {
+            0 => 1,
+            4 => 2,
+            2 | 3 => 3,
+            6 => 4,
+            _ => return Err(anyhow!("Invalid sBIT Chunk : Color Type {}", color_type)),
+        };
+        if chunk.data().len() != expected {
+            return Err(anyhow!(
+                "Invalid sBIT Chunk : Length {} , Should Be {}",
+                chunk.data().len(),
+                expected
+            ));
+        }
+        Ok(Sbit {
+            color_type,
+            bits: chunk.data().to_vec(),
+        })
+    }
+    /// Each entry must be between 1 and the sample depth (8 for palette images).
+    pub fn validate(&self, bit_depth: u8) -> Result<()> {
+        let depth = if self.color_type == 3 { 8 } else { bit_depth };
+        match self.bits.iter().find(|b| **b == 0 || **b > depth) {
+            Some(b) => Err(anyhow!(
+                "Invalid sBIT Chunk : Significant Bits {} Out Of Range 1..={}",
+                b,
+                depth
+            )),
+            None => Ok(()),
+        }
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk(Sbit::CHUNK_TYPE, self.bits.clone())
+    }
+}
+
+/// Palette histogram (`hIST`): approximate usage frequency of each PLTE entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hist {
+    pub frequencies: Vec<u16>,
+}
+
+impl Hist {
+    pub const CHUNK_TYPE: &'static str = "hIST";
+
+    pub fn validate(&self, palette_len: usize) -> Result<()> {
+        if self.frequencies.len() == palette_len {
+            Ok(())
+        } else {
+            Err(anyhow!(
+                "Invalid hIST Chunk : {} Entries , PLTE Has {}",
+                self.frequencies.len(),
+                palette_len
+            ))
+        }
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        for f in &self.frequencies {
+            write_u16(&mut data, *f);
+        }
+        new_chunk(Hist::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Hist {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Hist::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() % 2 != 0 {
+            return Err(anyhow!("Invalid hIST Chunk : Odd Length {}", data.len()));
+        }
+        Ok(Hist {
+            frequencies: (0..data.len())
+                .step_by(2)
+                .map(|i| read_u16(data, i))
+                .collect::<Result<_>>()?,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpltEntry {
+    pub red: u16,
+    pub green: u16,
+    pub blue: u16,
+    pub alpha: u16,
+    pub frequency: u16,
+}
+
+/// Suggested palette (`sPLT`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Splt {
+    pub name: String,
+    pub sample_depth: u8,
+    pub entries: Vec<SpltEntry>,
+}
+
+impl Splt {
+    pub const CHUNK_TYPE: &'static str = "sPLT";
+
+    /// Fails if the sample depth is not 8 or 16, or an entry has a color or
+    /// alpha value that does not fit in a depth of 8.
+    pub fn to_chunk(&self) -> Result<Chunk> {
+        if !matches!(self.sample_depth, 8 | 16) {
+            return Err(anyhow!(
+                "Invalid sPLT Chunk : Sample Depth {}",
+                self.sample_depth
+            ));
+        }
+        let mut data = latin1_encode(&self.name);
+        data.push(0);
+        data.push(self.sample_depth);
+        for e in &self.entries {
+            for v in [e.red, e.green, e.blue, e.alpha] {
+                if self.sample_depth == 16 {
+                    write_u16(&mut data, v);
+                } else {
+                    let v = u8::try_from(v)
+                        .map_err(|_| anyhow!("Invalid sPLT Entry : Value Exceeds Depth"))?;
+                    data.push(v);
+                }
+            }
+            write_u16(&mut data, e.frequency);
+        }
+        Ok(new_chunk(Splt::CHUNK_TYPE, data))
+    }
+}
+
+impl TryFrom<&Chunk> for Splt {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Splt::CHUNK_TYPE)?;
+        let (name, rest) = split_null(chunk.data())
+            .ok_or_else(|| anyhow!("Invalid sPLT Chunk : Missing Palette Name Terminator"))?;
+        let name = latin1_decode(name);
+        validate_keyword(&name)?;
+        let (&sample_depth, rest) = rest
+            .split_first()
+            .ok_or_else(|| anyhow!("Invalid sPLT Chunk : Missing Sample Depth"))?;
+        let entry_len = match sample_depth {
+            8 => 6,
+            16 => 10,
+            d => return Err(anyhow!("Invalid sPLT Chunk : Sample Depth {}", d)),
+        };
+        if rest.len() % entry_len != 0 {
+            return Err(anyhow!(
+                "Invalid sPLT Chunk : Entry Data Length {} Not A Multiple Of {}",
+                rest.len(),
+                entry_len
+            ));
+        }
+        let entries = rest
+            .chunks(entry_len)
+            .map(|e| {
+                Ok(if sample_depth == 8 {
+                    SpltEntry {
+                        red: e[0] as u16,
+                        green: e[1] as u16,
+                        blue: e[2] as u16,
+                        alpha: e[3] as u16,
+                        frequency: read_u16(e, 4)?,
+                    }
+                } else {
+                    SpltEntry {
+                        red: read_u16(e, 0)?,
+                        green: read_u16(e, 2)?,
+                        blue: read_u16(e, 4)?,
+                        alpha: read_u16(e, 6)?,
+                        frequency: read_u16(e, 8)?,
+                    }
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Splt {
+            name,
+            sample_depth,
+            entries,
+        })
+    }
+}
+
+/// Transparency (`tRNS`): a single transparent color for gray/RGB images,
+/// or one alpha value per leading palette entry for indexed images.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Trns {
+    Gray(u16),
+    Rgb(u16, u16, u16),
+    Palette(Vec<u8>),
+}
+
+impl Trns {
+    pub const CHUNK_TYPE: &'static str = "tRNS";
+
+    pub fn from_chunk(chunk: &Chunk, color_type: u8) -> Result<Self> {
+        check_type(chunk, Trns::CHUNK_TYPE)?;
+        let data = chunk.data();
+        match color_type {
+            0 if data.len() == 2 => Ok(Trns::Gray(read_u16(data, 0)?)),
+            2 if data.len() == 6 => Ok(Trns::Rgb(
+                read_u16(data, 0)?,
+                read_u16(data, 2)?,
+                read_u16(data, 4)?,
+            )),
+            3 => Ok(Trns::Palette(data.to_vec())),
+            0 | 2 => Err(anyhow!(
+                "Invalid tRNS Chunk : Length {} For Color Type {}",
+                data.len(),
+                color_type
+            )),
+            _ => Err(anyhow!(
+                "Invalid tRNS Chunk : Not Allowed For Color Type {}",
+                color_type
+            )),
+        }
+    }
+    /// Checks sample ranges against `bit_depth` and that an indexed tRNS has
+    /// no more entries than the palette.
+    pub fn validate(&self, bit_depth: u8, palette_len: Option<usize>) -> Result<()> {
+        let max = sample_max(bit_depth);
+        match self {
+            Trns::Palette(alphas) => match palette_len {
+                Some(len) if alphas.len() <= len => Ok(()),
+                Some(len) => Err(anyhow!(
+                    "Invalid tRNS Chunk : {} Entries , PLTE Has {}",
+                    alphas.len(),
+                    len
+                )),
+                None => Err(anyhow!("Invalid tRNS Chunk : Palette Alpha Without PLTE")),
+            },
+            Trns::Gray(g) if *g > max => Err(anyhow!(
+                "Invalid tRNS Chunk : Gray {} Exceeds Bit Depth {}",
+                g,
+                bit_depth
+            )),
+            Trns::Rgb(r, g, b) if *r > max || *g > max || *b > max => Err(anyhow!(
+                "Invalid tRNS Chunk : Color ({}, {}, {}) Exceeds Bit Depth {}",
+                r,
+                g,
+                b,
+                bit_depth
+            )),
+            _ => Ok(()),
+        }
+    }
+    /// Alpha of a palette entry; entries past the end of tRNS are opaque.
+    pub fn palette_alpha(&self, index: usize) -> Option<u8> {
+        match self {
+            Trns::Palette(alphas) => Some(alphas.get(index).copied().unwrap_or(255)),
+            _ => None,
+        }
+    }
+    /// Sets the alpha of a palette entry, growing the table with opaque
+    /// entries as needed.
+    pub fn set_palette_alpha(&mut self, index: usize, alpha: u8) -> Result<()> {
+        match self {
+            Trns::Palette(alphas) => {
+                if alphas.len() <= index {
+                    alphas.resize(index + 1, 255);
+                }
+                alphas[index] = alpha;
+                Ok(())
+            }
+            _ => Err(anyhow!(
+                "Invalid tRNS Edit : Not A Palette Transparency Chunk"
+            )),
+        }
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        match self {
+            Trns::Gray(g) => write_u16(&mut data, *g),
+            Trns::Rgb(r, g, b) => {
+                for v in [r, g, b] {
+                    write_u16(&mut data, *v);
+                }
+            }
+            Trns::Palette(alphas) => data.extend_from_slice(alphas),
+        }
+        new_chunk(Trns::CHUNK_TYPE, data)
+    }
+}
+
+/// Physical pixel dimensions (`pHYs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Phys {
+    pub pixels_per_unit_x: u32,
+    pub pixels_per_unit_y: u32,
+    /// 0 = unknown (aspect ratio only), 1 = meter.
+    pub unit: u8,
+}
+
+impl Phys {
+    pub const CHUNK_TYPE: &'static str = "pHYs";
+
+    /// Horizontal and vertical dots per inch, when the unit is the meter.
+    pub fn dpi(&self) -> Option<(f64, f64)> {
+        if self.unit == 1 {
+            Some((
+                self.pixels_per_unit_x as f64 * 0.0254,
+                self.pixels_per_unit_y as f64 * 0.0254,
+            ))
+        } else {
+            None
+        }
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        write_u32(&mut data, self.pixels_per_unit_x);
+        write_u32(&mut data, self.pixels_per_unit_y);
+        data.push(self.unit);
+        new_chunk(Phys::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Phys {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Phys::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() != 9 {
+            return Err(anyhow!(
+                "Invalid pHYs Chunk : Length {} , Should Be 9",
+                data.len()
+            ));
+        }
+        if data[8] > 1 {
+            return Err(anyhow!("Invalid pHYs Chunk : Unit {}", data[8]));
+        }
+        Ok(Phys {
+            pixels_per_unit_x: read_u32(data, 0)?,
+            pixels_per_unit_y: read_u32(data, 4)?,
+            unit: data[8],
+        })
+    }
+}
+
+/// Last modification time (`tIME`), always UTC.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Time {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl Time {
+    pub const CHUNK_TYPE: &'static str = "tIME";
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        write_u16(&mut data, self.year);
+        data.extend_from_slice(&[self.month, self.day, self.hour, self.minute, self.second]);
+        new_chunk(Time::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Time {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Time::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() != 7 {
+            return Err(anyhow!(
+                "Invalid tIME Chunk : Length {} , Should Be 7",
+                data.len()
+            ));
+        }
+        let time = Time {
+            year: read_u16(data, 0)?,
+            month: data[2],
+            day: data[3],
+            hour: data[4],
+            minute: data[5],
+            second: data[6],
+        };
+        // A second of 60 is allowed for leap seconds.
+        if !(1..=12).contains(&time.month)
+            || !(1..=31).contains(&time.day)
+            || time.hour > 23
+            || time.minute > 59
+            || time.second > 60
+        {
+            return Err(anyhow!(
+                "Invalid tIME Chunk : Out Of Range Field In {}",
+                time
+            ));
+        }
+        Ok(time)
+    }
+}
+
+impl Display for Time {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+pub(crate) fn check_type(chunk: &Chunk, expected: &str) -> Result<()> {
+    if chunk.chunk_type().to_string() == expected {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Invalid {} Chunk : Found Chunk Type {}",
+            expected,
+            chunk.chunk_type()
+        ))
+    }
+}
+
+pub(crate) fn new_chunk(chunk_type: &str, data: Vec<u8>) -> Chunk {
+    Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data)
+}
+
+fn sample_max(bit_depth: u8) -> u16 {
+    if bit_depth >= 16 {
+        u16::MAX
+    } else {
+        (1u16 << bit_depth) - 1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        new_chunk(chunk_type, data.to_vec())
+    }
+
+    #[test]
+    fn test_bkgd_by_color_type() {
+        let c = chunk("bKGD", &[3]);
+        assert_eq!(Bkgd::from_chunk(&c, 3).unwrap(), Bkgd::PaletteIndex(3));
+
+        let c = chunk("bKGD", &[0, 200]);
+        assert_eq!(Bkgd::from_chunk(&c, 0).unwrap(), Bkgd::Gray(200));
+
+        let c = chunk("bKGD", &[0, 1, 0, 2, 0, 3]);
+        let bkgd = Bkgd::from_chunk(&c, 6).unwrap();
+        assert_eq!(bkgd, Bkgd::Rgb(1, 2, 3));
+        assert_eq!(bkgd.to_chunk().data(), c.data());
+    }
+
+    #[test]
+    fn test_bkgd_wrong_length() {
+        let c = chunk("bKGD", &[0, 200]);
+        assert!(Bkgd::from_chunk(&c, 2).is_err());
+    }
+
+    #[test]
+    fn test_bkgd_validate() {
+        assert!(Bkgd::Gray(255).validate(8, None).is_ok());
+        assert!(Bkgd::Gray(256).validate(8, None).is_err());
+        assert!(Bkgd::PaletteIndex(4).validate(8, Some(4)).is_err());
+        assert!(Bkgd::PaletteIndex(4).validate(8, None).is_err());
+    }
+
+    #[test]
+    fn test_sbit() {
+        let c = chunk("sBIT", &[5, 6, 5]);
+        let sbit = Sbit::from_chunk(&c, 2).unwrap();
+        assert_eq!(sbit.bits, vec![5, 6, 5]);
+        assert!(sbit.validate(8).is_ok());
+        assert!(sbit.validate(4).is_err());
+        assert!(Sbit::from_chunk(&c, 6).is_err());
+    }
+
+    #[test]
+    fn test_hist() {
+        let c = chunk("hIST", &[0, 1, 1, 0]);
+        let hist = Hist::try_from(&c).unwrap();
+        assert_eq!(hist.frequencies, vec![1, 256]);
+        assert!(hist.validate(2).is_ok());
+        assert!(hist.validate(3).is_err());
+        assert_eq!(hist.to_chunk().data(), c.data());
+
+        assert!(Hist::try_from(&chunk("hIST", &[0, 1, 1])).is_err());
+    }
+
+    #[test]
+    fn test_splt_round_trip() {
+        let splt = Splt {
+            name: "web safe".to_string(),
+            sample_depth: 8,
+            entries: vec![SpltEntry {
+                red: 255,
+                green: 0,
+                blue: 51,
+                alpha: 255,
+                frequency: 7,
+            }],
+        };
+        let c = splt.to_chunk().unwrap();
+        assert_eq!(c.length(), 9 + 1 + 6);
+        assert_eq!(Splt::try_from(&c).unwrap(), splt);
+
+        let mut wide = Splt {
+            sample_depth: 16,
+            ..splt
+        };
+        wide.entries[0].red = 1000;
+        assert_eq!(Splt::try_from(&wide.to_chunk().unwrap()).unwrap(), wide);
+
+        wide.sample_depth = 8;
+        let err = wide.to_chunk().err().unwrap();
+        assert_eq!(err.to_string(), "Invalid sPLT Entry : Value Exceeds Depth");
+        wide.sample_depth = 4;
+        assert!(wide.to_chunk().is_err());
+    }
+
+    #[test]
+    fn test_splt_invalid() {
+        assert!(Splt::try_from(&chunk("sPLT", b"name\0\x07")).is_err());
+        assert!(Splt::try_from(&chunk("sPLT", b"name\0\x08\x01\x02")).is_err());
+        assert!(Splt::try_from(&chunk("sPLT", b"name")).is_err());
+    }
+
+    #[test]
+    fn test_trns_by_color_type() {
+        let c = chunk("tRNS", &[0, 7]);
+        assert_eq!(Trns::from_chunk(&c, 0).unwrap(), Trns::Gray(7));
+
+        let c = chunk("tRNS", &[0, 1, 0, 2, 0, 3]);
+        let trns = Trns::from_chunk(&c, 2).unwrap();
+        assert_eq!(trns, Trns::Rgb(1, 2, 3));
+        assert_eq!(trns.to_chunk().data(), c.data());
+
+        let c = chunk("tRNS", &[0, 128]);
+        assert_eq!(
+            Trns::from_chunk(&c, 3).unwrap(),
+            Trns::Palette(vec![0, 128])
+        );
+    }
+
+    #[test]
+    fn test_trns_invalid_color_type() {
+        let c = chunk("tRNS", &[0, 7]);
+        assert!(Trns::from_chunk(&c, 4).is_err());
+        assert!(Trns::from_chunk(&c, 6).is_err());
+        assert!(Trns::from_chunk(&c, 2).is_err());
+    }
+
+    #[test]
+    fn test_trns_validate() {
+        let trns = Trns::Palette(vec![0, 0, 0]);
+        assert!(trns.validate(8, Some(3)).is_ok());
+        assert!(trns.validate(8, Some(2)).is_err());
+        assert!(trns.validate(8, None).is_err());
+        assert!(Trns::Gray(4).validate(2, None).is_err());
+        assert!(Trns::Rgb(1, 2, 3).validate(2, None).is_ok());
+    }
+
+    #[test]
+    fn test_trns_palette_alpha() {
+        let mut trns = Trns::Palette(vec![0]);
+        assert_eq!(trns.palette_alpha(0), Some(0));
+        assert_eq!(trns.palette_alpha(5), Some(255));
+
+        trns.set_palette_alpha(2, 64).unwrap();
+        assert_eq!(trns, Trns::Palette(vec![0, 255, 64]));
+
+        assert!(Trns::Gray(0).set_palette_alpha(0, 0).is_err());
+        assert_eq!(Trns::Gray(0).palette_alpha(0), None);
+    }
+
+    #[test]
+    fn test_time() {
+        let c = chunk("tIME", &[7, 230, 2, 12, 13, 5, 59]);
+        let time = Time::try_from(&c).unwrap();
+        assert_eq!(time.to_string(), "2022-02-12T13:05:59Z");
+        assert_eq!(time.to_chunk().data(), c.data());
+
+        assert!(Time::try_from(&chunk("tIME", &[7, 230, 13, 12, 13, 5, 59])).is_err());
+        assert!(Time::try_from(&chunk("tIME", &[7, 230, 2, 12, 13, 5])).is_err());
+    }
+
+    #[test]
+    fn test_phys() {
+        let c = chunk("pHYs", &[0, 0, 14, 195, 0, 0, 14, 195, 1]);
+        let phys = Phys::try_from(&c).unwrap();
+        assert_eq!(phys.pixels_per_unit_x, 3779);
+        let (x, y) = phys.dpi().unwrap();
+        assert_eq!(x.round(), 96.0);
+        assert_eq!(y.round(), 96.0);
+        assert_eq!(phys.to_chunk().data(), c.data());
+
+        let c = chunk("pHYs", &[0, 0, 0, 1, 0, 0, 0, 2, 0]);
+        assert_eq!(Phys::try_from(&c).unwrap().dpi(), None);
+        assert!(Phys::try_from(&chunk("pHYs", &[0, 0, 0, 1, 0, 0, 0, 2, 2])).is_err());
+    }
+}
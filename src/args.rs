@@ -0,0 +1,1751 @@
+use crate::schema::Output;
+use clap::{App, AppSettings, Arg, ArgMatches, ErrorKind};
+use pngme::corpus::Damage;
+use pngme::filter::Filter;
+use pngme::png::ParseOptions;
+use pngme::units::Units;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A parsed command line.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PngMeArgs {
+    Encode(EncodeArgs),
+    Decode(DecodeArgs),
+    Remove(RemoveArgs),
+    Print(PrintArgs),
+    Scan(ScanArgs),
+    Report(ReportArgs),
+    Verify(VerifyArgs),
+    Repair(RepairArgs),
+    Salvage(SalvageArgs),
+    Doctor(DoctorArgs),
+    Info(InfoArgs),
+    Diff(DiffArgs),
+    Strip(StripArgs),
+    Resplit(ResplitArgs),
+    Extract(ExtractArgs),
+    Carve(CarveArgs),
+    Inject(InjectArgs),
+    List(ListArgs),
+    Timeline(TimelineArgs),
+    Schema(SchemaArgs),
+    BenchCorpus(BenchCorpusArgs),
+}
+
+/// Options given before or after the subcommand that apply to all of them.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GlobalArgs {
+    /// Fail any attempt to write, rename or remove a file.
+    pub read_only: bool,
+    /// Give up once this much time has passed.
+    pub timeout: Option<Duration>,
+    /// How every command parses its input; each command has its own
+    /// default if `None`.
+    pub parse: Option<ParseOptions>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeArgs {
+    pub file: PathBuf,
+    pub chunk_type: String,
+    pub payload: Payload,
+    /// Frame the payload as international text; raw chunk data if `None`.
+    pub itxt: Option<ITxtArgs>,
+    /// Record who added the chunk and why; no record if `None`.
+    pub provenance: Option<ProvenanceArgs>,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+/// The `iTXt` fields set with `encode --keyword`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ITxtArgs {
+    pub keyword: String,
+    /// Empty if not given.
+    pub language_tag: String,
+    /// Empty if not given.
+    pub translated_keyword: String,
+}
+
+/// The provenance record set with `--reason` on `encode` and `inject`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceArgs {
+    /// Empty if not given.
+    pub operator: String,
+    pub reason: String,
+}
+
+/// Where `encode` takes the chunk data from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Payload {
+    /// A message given on the command line.
+    Message(String),
+    /// The bytes of a file, read as is.
+    File(PathBuf),
+    /// Everything on standard input, selected with `--input-file -`.
+    Stdin,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeArgs {
+    pub file: PathBuf,
+    pub chunk_type: String,
+    pub json: bool,
+    /// Write the raw chunk data here instead of printing it.
+    pub output_file: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoveArgs {
+    pub file: PathBuf,
+    /// `None` if chunks are selected with `filter` instead.
+    pub chunk_type: Option<String>,
+    /// Remove every matching chunk, not just the first.
+    pub all: bool,
+    /// Remove the chunk preceded by this many of the same type.
+    pub index: Option<usize>,
+    /// Remove every chunk matching this `--where` expression.
+    pub filter: Option<String>,
+    /// Allow removing critical chunks.
+    pub force: bool,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PrintArgs {
+    pub file: PathBuf,
+    pub json: bool,
+    /// Parse only this many chunks and ignore the rest of the file.
+    pub head: Option<usize>,
+    /// Decode the chunk data into previews instead of showing raw bytes.
+    pub decode: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScanArgs {
+    pub dir: PathBuf,
+    /// Chunk type to look for; any private chunk if `None`.
+    pub chunk_type: Option<String>,
+    pub json: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReportArgs {
+    pub dir: PathBuf,
+    pub json: bool,
+    /// Print a standalone HTML page instead of text.
+    pub html: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyArgs {
+    pub file: PathBuf,
+    pub json: bool,
+    /// Move the file into this directory if it fails.
+    pub quarantine: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairArgs {
+    pub file: PathBuf,
+    /// Also fix length fields that do not lead to the next chunk.
+    pub fix_lengths: bool,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SalvageArgs {
+    pub file: PathBuf,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DoctorArgs {
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InfoArgs {
+    pub file: PathBuf,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiffArgs {
+    pub old: PathBuf,
+    pub new: PathBuf,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StripArgs {
+    pub file: PathBuf,
+    /// Ancillary chunk types to leave in place.
+    pub keep: Vec<String>,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ResplitArgs {
+    pub file: PathBuf,
+    /// Largest IDAT chunk to write; a single IDAT if `None`.
+    pub idat_size: Option<usize>,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExtractArgs {
+    pub file: PathBuf,
+    /// Directory receiving one file per chunk and `manifest.json`.
+    pub out_dir: PathBuf,
+    /// Only extract chunks matching this `--where` expression.
+    pub filter: Option<String>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CarveArgs {
+    /// Any file that may hold embedded PNGs.
+    pub file: PathBuf,
+    pub out_dir: PathBuf,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InjectArgs {
+    pub file: PathBuf,
+    pub chunk_type: String,
+    /// File whose bytes become the chunk data.
+    pub data: PathBuf,
+    /// Chunk index to insert at; before IEND if `None`.
+    pub position: Option<usize>,
+    /// Record who added the chunk and why; no record if `None`.
+    pub provenance: Option<ProvenanceArgs>,
+    /// Where to write the result; the input file is rewritten if `None`.
+    pub output: Option<PathBuf>,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ListArgs {
+    pub file: PathBuf,
+    /// List the chunks matching this `--where` expression instead of the
+    /// non-standard ones.
+    pub filter: Option<String>,
+    /// How sizes are shown; `--bytes` selects exact numbers.
+    pub units: Units,
+    pub json: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineArgs {
+    pub files: Vec<PathBuf>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaArgs {
+    /// The command whose `--json` schema to print; all of them if `None`.
+    pub output: Option<Output>,
+}
+
+/// `bench-corpus generate`: synthetic files for benchmarks and robustness
+/// tests.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BenchCorpusArgs {
+    pub out_dir: PathBuf,
+    /// Files per damage pattern, with consecutive seeds.
+    pub count: usize,
+    /// IDAT chunks per file.
+    pub chunks: usize,
+    /// Data size of each IDAT chunk.
+    pub chunk_size: usize,
+    /// Only this damage pattern; every pattern if `None`.
+    pub damage: Option<Damage>,
+    pub seed: u64,
+    /// Report what would change without writing anything.
+    pub dry_run: bool,
+}
+
+pub fn app() -> App<'static> {
+    App::new("pngme")
+        .version(env!("CARGO_PKG_VERSION"))
+        .about("Hide and find messages in PNG chunks")
+        .setting(AppSettings::SubcommandRequiredElseHelp)
+        .arg(
+            Arg::new("dry_run")
+                .long("dry-run")
+                .global(true)
+                .help("Report what would change without writing any file"),
+        )
+        .arg(
+            Arg::new("bytes")
+                .long("bytes")
+                .global(true)
+                .help("Show sizes as exact byte counts instead of KiB, MiB, ..."),
+        )
+        .arg(
+            Arg::new("read_only")
+                .long("read-only")
+                .global(true)
+                .help("Fail instead of writing, renaming or removing any file"),
+        )
+        .arg(
+            Arg::new("timeout")
+                .long("timeout")
+                .global(true)
+                .takes_value(true)
+                .value_name("SECONDS")
+                .validator(|v| v.parse::<u64>())
+                .help(
+                    "Give up after this many seconds; checked before each file and each chunk \
+                     read (not supported by verify, repair, salvage, doctor and schema)",
+                ),
+        )
+        .arg(
+            Arg::new("parse_mode")
+                .long("parse-mode")
+                .global(true)
+                .takes_value(true)
+                .value_name("MODE")
+                .possible_values(["strict", "normal", "permissive"])
+                .help("Parse inputs this way instead of each command's own default"),
+        )
+        .arg(
+            Arg::new("allow_unknown_critical")
+                .long("allow-unknown-critical")
+                .global(true)
+                .requires("parse_mode")
+                .help("Keep critical chunk types that are not registered, with --parse-mode"),
+        )
+        .subcommand(
+            App::new("encode")
+                .about("Add a chunk holding a message or file before IEND")
+                .arg(path_arg("file").required(true).help("PNG file to read"))
+                .arg(
+                    Arg::new("chunk_type")
+                        .required(true)
+                        .help("Four-letter chunk type, e.g. ruSt"),
+                )
+                .arg(
+                    Arg::new("message")
+                        .required_unless_present("input_file")
+                        .help("Message to store"),
+                )
+                .arg(
+                    path_arg("input_file")
+                        .long("input-file")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .conflicts_with("message")
+                        .help("Store the bytes of this file instead, or of stdin if -"),
+                )
+                .arg(
+                    Arg::new("keyword")
+                        .long("keyword")
+                        .takes_value(true)
+                        .value_name("KEYWORD")
+                        .help("Store the payload as iTXt text under this keyword"),
+                )
+                .arg(
+                    Arg::new("lang")
+                        .long("lang")
+                        .takes_value(true)
+                        .value_name("TAG")
+                        .requires("keyword")
+                        .help("Language tag of the iTXt text, e.g. de-DE"),
+                )
+                .arg(
+                    Arg::new("translated_keyword")
+                        .long("translated-keyword")
+                        .takes_value(true)
+                        .value_name("TEXT")
+                        .requires("keyword")
+                        .help("Keyword translated into the language of the text"),
+                )
+                .arg(reason_arg())
+                .arg(operator_arg())
+                .arg(
+                    path_arg("output_file")
+                        .conflicts_with("output")
+                        .help("Same as --output"),
+                )
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("decode")
+                .about("Print the message of every chunk of the given type")
+                .arg(path_arg("file").required(true).help("PNG file to read"))
+                .arg(
+                    Arg::new("chunk_type")
+                        .required(true)
+                        .help("Four-letter chunk type, e.g. ruSt"),
+                )
+                .arg(json_arg())
+                .arg(
+                    path_arg("output_file")
+                        .long("output-file")
+                        .takes_value(true)
+                        .value_name("PATH")
+                        .conflicts_with("json")
+                        .help("Write the raw data of the chunks to this file"),
+                ),
+        )
+        .subcommand(
+            App::new("remove")
+                .about("Remove the first chunk of the given type")
+                .arg(path_arg("file").required(true).help("PNG file to rewrite"))
+                .arg(
+                    Arg::new("chunk_type")
+                        .required_unless_present("where")
+                        .help("Four-letter chunk type, e.g. ruSt"),
+                )
+                .arg(
+                    where_arg()
+                        .conflicts_with_all(&["chunk_type", "all", "index"])
+                        .help("Remove every chunk matching this expression instead"),
+                )
+                .arg(
+                    Arg::new("all")
+                        .long("all")
+                        .help("Remove every chunk of the type"),
+                )
+                .arg(
+                    Arg::new("index")
+                        .long("index")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|v| v.parse::<usize>())
+                        .conflicts_with("all")
+                        .help("Remove the chunk of the type at this index, counting from 0"),
+                )
+                .arg(
+                    Arg::new("force")
+                        .long("force")
+                        .help("Allow removing critical chunks"),
+                )
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("print")
+                .about("List every chunk with its length, CRC, flags and a preview")
+                .long_about(
+                    "List every chunk with its length, CRC, flags and a preview. \
+                     FLAGS shows A (ancillary), P (private), R (reserved bit set) \
+                     and S (safe to copy), or - where the property is absent. \
+                     A CRC followed by ! does not match the chunk.",
+                )
+                .arg(path_arg("file").required(true).help("PNG file to read"))
+                .arg(json_arg())
+                .arg(
+                    Arg::new("head")
+                        .long("head")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|v| v.parse::<usize>())
+                        .help("Parse only the first N chunks and ignore the rest of the file"),
+                )
+                .arg(
+                    Arg::new("decode")
+                        .long("decode")
+                        .help("Decode known chunks into readable previews"),
+                ),
+        )
+        .subcommand(
+            App::new("scan")
+                .about("Find PNG files under a directory holding a chunk type")
+                .arg(
+                    path_arg("dir")
+                        .required(true)
+                        .help("Directory to search recursively"),
+                )
+                .arg(
+                    Arg::new("chunk_type")
+                        .help("Four-letter chunk type; any private chunk if omitted"),
+                )
+                .arg(json_arg()),
+        )
+        .subcommand(
+            App::new("report")
+                .about("Summarize chunk types, failures and suspicious files under a directory")
+                .arg(
+                    path_arg("dir")
+                        .required(true)
+                        .help("Directory to search recursively"),
+                )
+                .arg(json_arg())
+                .arg(
+                    Arg::new("html")
+                        .long("html")
+                        .conflicts_with("json")
+                        .help("Print an HTML page instead of text"),
+                ),
+        )
+        .subcommand(
+            App::new("verify")
+                .about("Check the signature, every chunk CRC and the IEND chunk")
+                .arg(path_arg("file").required(true).help("PNG file to check"))
+                .arg(json_arg())
+                .arg(
+                    path_arg("quarantine")
+                        .long("quarantine")
+                        .takes_value(true)
+                        .value_name("DIR")
+                        .help("Move the file into this directory if it fails"),
+                ),
+        )
+        .subcommand(
+            App::new("repair")
+                .about("Recompute wrong chunk CRCs")
+                .arg(path_arg("file").required(true).help("PNG file to repair"))
+                .arg(
+                    Arg::new("fix_lengths")
+                        .long("fix-lengths")
+                        .help("Also fix length fields using the next known chunk type"),
+                )
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("salvage")
+                .about("Rebuild a viewable PNG from the intact chunks of a damaged one")
+                .arg(path_arg("file").required(true).help("PNG file to salvage"))
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("doctor")
+                .about("Diagnose a PNG file and suggest the commands that fix it")
+                .long_about(
+                    "Diagnose a PNG file and suggest the commands that fix it. Checks \
+                     the framing, every CRC, data after IEND, unknown critical chunks, \
+                     non-standard chunks and embedded files, and lists the problems \
+                     most urgent first. Fails if any of them is an error.",
+                )
+                .arg(path_arg("file").required(true).help("PNG file to diagnose")),
+        )
+        .subcommand(
+            App::new("info")
+                .about("Summarize the image header and chunks")
+                .arg(path_arg("file").required(true).help("PNG file to read")),
+        )
+        .subcommand(
+            App::new("diff")
+                .about("List chunks added, removed or modified between two PNGs")
+                .arg(path_arg("old").required(true).help("Original PNG file"))
+                .arg(path_arg("new").required(true).help("Changed PNG file")),
+        )
+        .subcommand(
+            App::new("strip")
+                .about("Remove every ancillary chunk")
+                .arg(path_arg("file").required(true).help("PNG file to rewrite"))
+                .arg(
+                    Arg::new("keep")
+                        .long("keep")
+                        .takes_value(true)
+                        .multiple_occurrences(true)
+                        .use_delimiter(true)
+                        .value_name("TYPES")
+                        .help("Comma-separated chunk types to keep, e.g. tEXt,iCCP"),
+                )
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("resplit")
+                .about("Rewrite the image data into IDAT chunks of a chosen size")
+                .arg(path_arg("file").required(true).help("PNG file to rewrite"))
+                .arg(
+                    Arg::new("idat_size")
+                        .long("idat-size")
+                        .takes_value(true)
+                        .value_name("BYTES")
+                        .validator(|v| v.parse::<usize>())
+                        .help("Largest IDAT chunk to write; one IDAT if omitted"),
+                )
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("extract")
+                .about("Write the data of every chunk to its own file")
+                .arg(path_arg("file").required(true).help("PNG file to read"))
+                .arg(
+                    path_arg("out_dir")
+                        .long("out-dir")
+                        .takes_value(true)
+                        .required(true)
+                        .value_name("DIR")
+                        .help("Directory to write NN_TYPE.bin files and manifest.json to"),
+                )
+                .arg(where_arg().help("Only extract chunks matching this expression")),
+        )
+        .subcommand(
+            App::new("carve")
+                .about("Copy every complete PNG embedded in any file to its own file")
+                .arg(
+                    path_arg("file")
+                        .required(true)
+                        .help("File to search, e.g. a memory dump or disk image"),
+                )
+                .arg(
+                    path_arg("out_dir")
+                        .long("out-dir")
+                        .takes_value(true)
+                        .required(true)
+                        .value_name("DIR")
+                        .help("Directory to write the carved PNGs and report.txt to"),
+                ),
+        )
+        .subcommand(
+            App::new("list")
+                .about("List chunks that are not registered public PNG chunks")
+                .arg(path_arg("file").required(true).help("PNG file to read"))
+                .arg(where_arg().help("List the chunks matching this expression instead"))
+                .arg(json_arg()),
+        )
+        .subcommand(
+            App::new("timeline")
+                .about("List the dates recorded in the metadata of PNG files, oldest first")
+                .arg(
+                    path_arg("files")
+                        .required(true)
+                        .multiple_values(true)
+                        .value_name("FILES")
+                        .help("PNG files to read"),
+                ),
+        )
+        .subcommand(
+            App::new("inject")
+                .about("Insert a chunk holding the bytes of a file")
+                .arg(path_arg("file").required(true).help("PNG file to rewrite"))
+                .arg(
+                    Arg::new("chunk_type")
+                        .required(true)
+                        .help("Four-letter chunk type, e.g. ruSt"),
+                )
+                .arg(
+                    path_arg("data")
+                        .long("data")
+                        .takes_value(true)
+                        .required(true)
+                        .value_name("PATH")
+                        .help("File holding the chunk data"),
+                )
+                .arg(
+                    Arg::new("position")
+                        .long("position")
+                        .takes_value(true)
+                        .value_name("N")
+                        .validator(|v| v.parse::<usize>())
+                        .help("Chunk index to insert at instead of before IEND"),
+                )
+                .arg(reason_arg())
+                .arg(operator_arg())
+                .arg(output_arg()),
+        )
+        .subcommand(
+            App::new("schema")
+                .about("Print the JSON Schema of the --json output of a command")
+                .long_about(
+                    "Print the JSON Schema of the --json output of a command, or of \
+                     every command with one. Each document carries schema_version; \
+                     within a version fields are only added, never removed or retyped.",
+                )
+                .arg(
+                    Arg::new("output")
+                        .possible_values(Output::ALL.map(Output::name))
+                        .help("Command whose output to describe; all of them if omitted"),
+                ),
+        )
+        .subcommand(
+            App::new("bench-corpus")
+                .about("Development tools for benchmark inputs")
+                .setting(AppSettings::SubcommandRequiredElseHelp)
+                .subcommand(
+                    App::new("generate")
+                        .about("Write reproducible synthetic PNGs, intact and damaged")
+                        .arg(
+                            path_arg("out_dir")
+                                .long("out-dir")
+                                .takes_value(true)
+                                .required(true)
+                                .value_name("DIR")
+                                .help("Directory to write the files to"),
+                        )
+                        .arg(number_arg("count", "1").help("Files per damage pattern"))
+                        .arg(number_arg("chunks", "4").help("IDAT chunks per file"))
+                        .arg(
+                            number_arg("chunk_size", "1024")
+                                .long("chunk-size")
+                                .help("Data bytes per IDAT chunk"),
+                        )
+                        .arg(
+                            Arg::new("damage")
+                                .long("damage")
+                                .takes_value(true)
+                                .value_name("KIND")
+                                .possible_values(Damage::ALL.map(Damage::name))
+                                .help("Only this damage pattern instead of all of them"),
+                        )
+                        .arg(number_arg("seed", "1").help("Seed of the first file")),
+                ),
+        )
+}
+
+/// `--NAME N`, a non-negative integer with a default.
+fn number_arg(name: &'static str, default: &'static str) -> Arg<'static> {
+    Arg::new(name)
+        .long(name)
+        .takes_value(true)
+        .value_name("N")
+        .default_value(default)
+        .validator(|v| v.parse::<u64>())
+}
+
+/// `-o/--output`, taken by every command that changes the file.
+fn output_arg() -> Arg<'static> {
+    path_arg("output")
+        .short('o')
+        .long("output")
+        .takes_value(true)
+        .value_name("PATH")
+        .help("Write the result here and leave the input file untouched")
+}
+
+/// `--where EXPR`, a [`Filter`] expression selecting chunks, e.g.
+/// `type ~ "tE.t" && len > 1024 && !critical`.
+/// `--reason`, which records a provenance entry for the added chunk.
+fn reason_arg() -> Arg<'static> {
+    Arg::new("reason")
+        .long("reason")
+        .takes_value(true)
+        .value_name("TEXT")
+        .help("Record in a prOV chunk that pngme added the chunk, and why")
+}
+
+fn operator_arg() -> Arg<'static> {
+    Arg::new("operator")
+        .long("operator")
+        .takes_value(true)
+        .value_name("NAME")
+        .requires("reason")
+        .help("Who added the chunk, for the --reason record")
+}
+
+fn where_arg() -> Arg<'static> {
+    Arg::new("where")
+        .long("where")
+        .takes_value(true)
+        .value_name("EXPR")
+        .validator(|v| v.parse::<Filter>())
+}
+
+/// `--json`, for commands that can print machine-readable output.
+fn json_arg() -> Arg<'static> {
+    Arg::new("json")
+        .long("json")
+        .help("Print JSON instead of text")
+}
+
+/// An argument holding a file name, which need not be valid UTF-8.
+fn path_arg(name: &'static str) -> Arg<'static> {
+    Arg::new(name).allow_invalid_utf8(true)
+}
+
+/// Parses the process arguments, printing help and exiting on error.
+pub fn parse() -> (GlobalArgs, PngMeArgs) {
+    parse_from(std::env::args_os()).unwrap_or_else(|e| e.exit())
+}
+
+pub fn parse_from<I, T>(args: I) -> clap::Result<(GlobalArgs, PngMeArgs)>
+where
+    I: IntoIterator<Item = T>,
+    T: Into<OsString> + Clone,
+{
+    let matches = app().try_get_matches_from(args)?;
+    let global = global_from_matches(&matches);
+    match matches.subcommand_name() {
+        Some(name) if global.timeout.is_some() && NO_TIMEOUT.contains(&name) => {
+            return Err(app().error(
+                ErrorKind::ArgumentConflict,
+                format!("--timeout is not supported by {}", name),
+            ));
+        }
+        _ => {}
+    }
+    Ok((global, from_matches(&matches)))
+}
+
+/// Subcommands that never check the cancellation token, so a `--timeout`
+/// given to them would silently do nothing.
+const NO_TIMEOUT: [&str; 5] = ["verify", "repair", "salvage", "doctor", "schema"];
+
+/// Reads the global options from the innermost subcommand's matches, where
+/// clap puts them wherever they were given on the command line.
+fn global_from_matches(matches: &ArgMatches) -> GlobalArgs {
+    let mut m = matches;
+    while let Some((_, sub)) = m.subcommand() {
+        m = sub;
+    }
+    GlobalArgs {
+        read_only: m.is_present("read_only"),
+        timeout: string(m, "timeout").map(|v| Duration::from_secs(v.parse().unwrap())),
+        parse: m.value_of("parse_mode").map(|mode| ParseOptions {
+            allow_unknown_critical: m.is_present("allow_unknown_critical")
+                || parse_options(mode).allow_unknown_critical,
+            ..parse_options(mode)
+        }),
+    }
+}
+
+/// The [`ParseOptions`] behind each `--parse-mode` value.
+fn parse_options(mode: &str) -> ParseOptions {
+    match mode {
+        "strict" => ParseOptions::strict(),
+        "permissive" => ParseOptions::permissive(),
+        _ => ParseOptions::default(),
+    }
+}
+
+fn from_matches(matches: &ArgMatches) -> PngMeArgs {
+    match matches.subcommand() {
+        Some(("encode", m)) => PngMeArgs::Encode(EncodeArgs {
+            file: path(m, "file").unwrap(),
+            chunk_type: string(m, "chunk_type").unwrap(),
+            payload: match path(m, "input_file") {
+                Some(file) if file.as_os_str() == "-" => Payload::Stdin,
+                Some(file) => Payload::File(file),
+                None => Payload::Message(string(m, "message").unwrap()),
+            },
+            itxt: string(m, "keyword").map(|keyword| ITxtArgs {
+                keyword,
+                language_tag: string(m, "lang").unwrap_or_default(),
+                translated_keyword: string(m, "translated_keyword").unwrap_or_default(),
+            }),
+            provenance: provenance(m),
+            output: path(m, "output").or_else(|| path(m, "output_file")),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("decode", m)) => PngMeArgs::Decode(DecodeArgs {
+            file: path(m, "file").unwrap(),
+            chunk_type: string(m, "chunk_type").unwrap(),
+            json: m.is_present("json"),
+            output_file: path(m, "output_file"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("remove", m)) => PngMeArgs::Remove(RemoveArgs {
+            file: path(m, "file").unwrap(),
+            chunk_type: string(m, "chunk_type"),
+            all: m.is_present("all"),
+            index: string(m, "index").map(|v| v.parse().unwrap()),
+            filter: string(m, "where"),
+            force: m.is_present("force"),
+            output: path(m, "output"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("scan", m)) => PngMeArgs::Scan(ScanArgs {
+            dir: path(m, "dir").unwrap(),
+            chunk_type: string(m, "chunk_type"),
+            json: m.is_present("json"),
+            units: units(m),
+        }),
+        Some(("report", m)) => PngMeArgs::Report(ReportArgs {
+            dir: path(m, "dir").unwrap(),
+            json: m.is_present("json"),
+            html: m.is_present("html"),
+        }),
+        Some(("verify", m)) => PngMeArgs::Verify(VerifyArgs {
+            file: path(m, "file").unwrap(),
+            json: m.is_present("json"),
+            quarantine: path(m, "quarantine"),
+            dry_run: m.is_present("dry_run"),
+        }),
+        Some(("repair", m)) => PngMeArgs::Repair(RepairArgs {
+            file: path(m, "file").unwrap(),
+            fix_lengths: m.is_present("fix_lengths"),
+            output: path(m, "output"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("salvage", m)) => PngMeArgs::Salvage(SalvageArgs {
+            file: path(m, "file").unwrap(),
+            output: path(m, "output"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("doctor", m)) => PngMeArgs::Doctor(DoctorArgs {
+            file: path(m, "file").unwrap(),
+        }),
+        Some(("info", m)) => PngMeArgs::Info(InfoArgs {
+            file: path(m, "file").unwrap(),
+            units: units(m),
+        }),
+        Some(("diff", m)) => PngMeArgs::Diff(DiffArgs {
+            old: path(m, "old").unwrap(),
+            new: path(m, "new").unwrap(),
+            units: units(m),
+        }),
+        Some(("strip", m)) => PngMeArgs::Strip(StripArgs {
+            file: path(m, "file").unwrap(),
+            keep: m
+                .values_of("keep")
+                .map(|v| v.map(str::to_string).collect())
+                .unwrap_or_default(),
+            output: path(m, "output"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("resplit", m)) => PngMeArgs::Resplit(ResplitArgs {
+            file: path(m, "file").unwrap(),
+            idat_size: string(m, "idat_size").map(|v| v.parse().unwrap()),
+            output: path(m, "output"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("extract", m)) => PngMeArgs::Extract(ExtractArgs {
+            file: path(m, "file").unwrap(),
+            out_dir: path(m, "out_dir").unwrap(),
+            filter: string(m, "where"),
+            dry_run: m.is_present("dry_run"),
+        }),
+        Some(("carve", m)) => PngMeArgs::Carve(CarveArgs {
+            file: path(m, "file").unwrap(),
+            out_dir: path(m, "out_dir").unwrap(),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("inject", m)) => PngMeArgs::Inject(InjectArgs {
+            file: path(m, "file").unwrap(),
+            chunk_type: string(m, "chunk_type").unwrap(),
+            data: path(m, "data").unwrap(),
+            position: string(m, "position").map(|v| v.parse().unwrap()),
+            provenance: provenance(m),
+            output: path(m, "output"),
+            dry_run: m.is_present("dry_run"),
+            units: units(m),
+        }),
+        Some(("list", m)) => PngMeArgs::List(ListArgs {
+            file: path(m, "file").unwrap(),
+            filter: string(m, "where"),
+            units: units(m),
+            json: m.is_present("json"),
+        }),
+        Some(("schema", m)) => PngMeArgs::Schema(SchemaArgs {
+            output: m.value_of("output").and_then(Output::from_name),
+        }),
+        Some(("timeline", m)) => PngMeArgs::Timeline(TimelineArgs {
+            files: m
+                .values_of_os("files")
+                .unwrap()
+                .map(PathBuf::from)
+                .collect(),
+        }),
+        Some(("print", m)) => PngMeArgs::Print(PrintArgs {
+            file: path(m, "file").unwrap(),
+            json: m.is_present("json"),
+            head: string(m, "head").map(|v| v.parse().unwrap()),
+            decode: m.is_present("decode"),
+        }),
+        Some(("bench-corpus", m)) => {
+            let (_, m) = m.subcommand().expect("a subcommand is required");
+            PngMeArgs::BenchCorpus(BenchCorpusArgs {
+                out_dir: path(m, "out_dir").unwrap(),
+                count: string(m, "count").unwrap().parse().unwrap(),
+                chunks: string(m, "chunks").unwrap().parse().unwrap(),
+                chunk_size: string(m, "chunk_size").unwrap().parse().unwrap(),
+                damage: m.value_of("damage").and_then(Damage::from_name),
+                seed: string(m, "seed").unwrap().parse().unwrap(),
+                dry_run: m.is_present("dry_run"),
+            })
+        }
+        _ => unreachable!("a subcommand is required"),
+    }
+}
+
+fn units(matches: &ArgMatches) -> Units {
+    if matches.is_present("bytes") {
+        Units::Exact
+    } else {
+        Units::Human
+    }
+}
+
+fn provenance(matches: &ArgMatches) -> Option<ProvenanceArgs> {
+    string(matches, "reason").map(|reason| ProvenanceArgs {
+        operator: string(matches, "operator").unwrap_or_default(),
+        reason,
+    })
+}
+
+fn string(matches: &ArgMatches, name: &str) -> Option<String> {
+    matches.value_of(name).map(str::to_string)
+}
+
+fn path(matches: &ArgMatches, name: &str) -> Option<PathBuf> {
+    matches.value_of_os(name).map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse_command<I, T>(args: I) -> clap::Result<PngMeArgs>
+    where
+        I: IntoIterator<Item = T>,
+        T: Into<OsString> + Clone,
+    {
+        parse_from(args).map(|(_, command)| command)
+    }
+
+    #[test]
+    fn test_parse_encode() {
+        let args = parse_command(["pngme", "encode", "a.png", "ruSt", "hello"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Encode(EncodeArgs {
+                file: PathBuf::from("a.png"),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+
+        let args =
+            parse_command(["pngme", "encode", "a.png", "ruSt", "--input-file", "s.bin"]).unwrap();
+        match args {
+            PngMeArgs::Encode(encode) => {
+                assert_eq!(encode.payload, Payload::File(PathBuf::from("s.bin")))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        let args =
+            parse_command(["pngme", "encode", "a.png", "ruSt", "--input-file", "-"]).unwrap();
+        match args {
+            PngMeArgs::Encode(encode) => assert_eq!(encode.payload, Payload::Stdin),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command(["pngme", "encode", "a.png", "ruSt"]).is_err());
+        assert!(parse_command([
+            "pngme",
+            "encode",
+            "a.png",
+            "ruSt",
+            "hi",
+            "--input-file",
+            "s.bin"
+        ])
+        .is_err());
+
+        let args = parse_command(["pngme", "encode", "a.png", "ruSt", "hello", "b.png"]).unwrap();
+        match args {
+            PngMeArgs::Encode(encode) => assert_eq!(encode.output, Some(PathBuf::from("b.png"))),
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let args =
+            parse_command(["pngme", "encode", "-o", "b.png", "a.png", "ruSt", "hi"]).unwrap();
+        match args {
+            PngMeArgs::Encode(encode) => assert_eq!(encode.output, Some(PathBuf::from("b.png"))),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command([
+            "pngme", "encode", "a.png", "ruSt", "hi", "b.png", "--output", "c.png"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_encode_itxt() {
+        let args = parse_command([
+            "pngme",
+            "encode",
+            "a.png",
+            "iTXt",
+            "Grüße",
+            "--keyword",
+            "Title",
+            "--lang",
+            "de-DE",
+            "--translated-keyword",
+            "Titel",
+        ])
+        .unwrap();
+        match args {
+            PngMeArgs::Encode(encode) => assert_eq!(
+                encode.itxt,
+                Some(ITxtArgs {
+                    keyword: "Title".to_string(),
+                    language_tag: "de-DE".to_string(),
+                    translated_keyword: "Titel".to_string(),
+                })
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command(["pngme", "encode", "a.png", "iTXt", "hi", "--lang", "en"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_decode() {
+        let args = parse_command(["pngme", "decode", "a.png", "ruSt"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Decode(DecodeArgs {
+                file: PathBuf::from("a.png"),
+                chunk_type: "ruSt".to_string(),
+                json: false,
+                output_file: None,
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+        let args = parse_command(["pngme", "decode", "--json", "a.png", "ruSt"]).unwrap();
+        match args {
+            PngMeArgs::Decode(decode) => assert!(decode.json),
+            other => panic!("unexpected {:?}", other),
+        }
+        let args =
+            parse_command(["pngme", "decode", "a.png", "ruSt", "--output-file", "p.bin"]).unwrap();
+        match args {
+            PngMeArgs::Decode(decode) => {
+                assert_eq!(decode.output_file, Some(PathBuf::from("p.bin")))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command([
+            "pngme",
+            "decode",
+            "a.png",
+            "ruSt",
+            "--json",
+            "--output-file",
+            "p.bin"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_remove() {
+        let args = parse_command(["pngme", "remove", "a.png", "ruSt"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Remove(RemoveArgs {
+                file: PathBuf::from("a.png"),
+                chunk_type: Some("ruSt".to_string()),
+                all: false,
+                index: None,
+                filter: None,
+                force: false,
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+
+        let args = parse_command([
+            "pngme", "remove", "--all", "a.png", "IDAT", "--force", "--output", "b.png",
+        ])
+        .unwrap();
+        match args {
+            PngMeArgs::Remove(remove) => {
+                assert!(remove.all && remove.force);
+                assert_eq!(remove.output, Some(PathBuf::from("b.png")));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+
+        let args = parse_command(["pngme", "remove", "a.png", "ruSt", "--index", "1"]).unwrap();
+        match args {
+            PngMeArgs::Remove(remove) => assert_eq!(remove.index, Some(1)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command(["pngme", "remove", "a.png", "ruSt", "--index", "x"]).is_err());
+        assert!(
+            parse_command(["pngme", "remove", "a.png", "ruSt", "--index", "1", "--all"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_where() {
+        let args = parse_command(["pngme", "remove", "a.png", "--where", "len > 10"]).unwrap();
+        match args {
+            PngMeArgs::Remove(remove) => {
+                assert_eq!(remove.chunk_type, None);
+                assert_eq!(remove.filter, Some("len > 10".to_string()));
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command(["pngme", "remove", "a.png"]).is_err());
+        assert!(
+            parse_command(["pngme", "remove", "a.png", "ruSt", "--where", "len > 10"]).is_err()
+        );
+        assert!(parse_command(["pngme", "remove", "a.png", "--where", "len >"]).is_err());
+
+        let args = parse_command(["pngme", "list", "a.png", "--where", "!critical"]).unwrap();
+        match args {
+            PngMeArgs::List(list) => assert_eq!(list.filter, Some("!critical".to_string())),
+            other => panic!("unexpected {:?}", other),
+        }
+        let args = parse_command([
+            "pngme",
+            "extract",
+            "a.png",
+            "--out-dir",
+            "d/",
+            "--where",
+            "type == \"IDAT\"",
+        ])
+        .unwrap();
+        match args {
+            PngMeArgs::Extract(extract) => {
+                assert_eq!(extract.filter, Some("type == \"IDAT\"".to_string()))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_print() {
+        let args = parse_command(["pngme", "print", "a.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Print(PrintArgs {
+                file: PathBuf::from("a.png"),
+                json: false,
+                head: None,
+                decode: false,
+            })
+        );
+        match parse_command(["pngme", "print", "a.png", "--head", "3"]).unwrap() {
+            PngMeArgs::Print(print) => assert_eq!(print.head, Some(3)),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command(["pngme", "print", "a.png", "--head", "-1"]).is_err());
+        match parse_command(["pngme", "print", "a.png", "--decode"]).unwrap() {
+            PngMeArgs::Print(print) => assert!(print.decode),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_scan() {
+        let args = parse_command(["pngme", "scan", "images"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Scan(ScanArgs {
+                dir: PathBuf::from("images"),
+                chunk_type: None,
+                json: false,
+                units: Units::Human,
+            })
+        );
+        let args = parse_command(["pngme", "scan", "images", "ruSt"]).unwrap();
+        match args {
+            PngMeArgs::Scan(scan) => assert_eq!(scan.chunk_type.as_deref(), Some("ruSt")),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_report() {
+        let args = parse_command(["pngme", "report", "images", "--html"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Report(ReportArgs {
+                dir: PathBuf::from("images"),
+                json: false,
+                html: true,
+            })
+        );
+        assert!(parse_command(["pngme", "report", "images", "--html", "--json"]).is_err());
+        assert!(parse_command(["pngme", "report"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_timeline() {
+        let args = parse_command(["pngme", "timeline", "a.png", "b.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Timeline(TimelineArgs {
+                files: vec![PathBuf::from("a.png"), PathBuf::from("b.png")],
+            })
+        );
+        assert!(parse_command(["pngme", "timeline"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_verify() {
+        let args = parse_command(["pngme", "verify", "--json", "a.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Verify(VerifyArgs {
+                file: PathBuf::from("a.png"),
+                json: true,
+                quarantine: None,
+                dry_run: false,
+            })
+        );
+        let args = parse_command(["pngme", "verify", "a.png", "--quarantine", "bad/"]).unwrap();
+        match args {
+            PngMeArgs::Verify(verify) => {
+                assert_eq!(verify.quarantine, Some(PathBuf::from("bad/")))
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_repair() {
+        let args =
+            parse_command(["pngme", "repair", "a.png", "--fix-lengths", "-o", "b.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Repair(RepairArgs {
+                file: PathBuf::from("a.png"),
+                fix_lengths: true,
+                output: Some(PathBuf::from("b.png")),
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_salvage() {
+        let args = parse_command(["pngme", "salvage", "a.png", "-o", "b.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Salvage(SalvageArgs {
+                file: PathBuf::from("a.png"),
+                output: Some(PathBuf::from("b.png")),
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_doctor() {
+        let args = parse_command(["pngme", "doctor", "a.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Doctor(DoctorArgs {
+                file: PathBuf::from("a.png"),
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_info() {
+        let args = parse_command(["pngme", "info", "a.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Info(InfoArgs {
+                file: PathBuf::from("a.png"),
+                units: Units::Human,
+            })
+        );
+        match parse_command(["pngme", "--bytes", "info", "a.png"]).unwrap() {
+            PngMeArgs::Info(info) => assert_eq!(info.units, Units::Exact),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_diff() {
+        let args = parse_command(["pngme", "diff", "a.png", "b.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Diff(DiffArgs {
+                old: PathBuf::from("a.png"),
+                new: PathBuf::from("b.png"),
+                units: Units::Human,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_strip() {
+        let args = parse_command(["pngme", "strip", "a.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Strip(StripArgs {
+                file: PathBuf::from("a.png"),
+                keep: Vec::new(),
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+        let args = parse_command([
+            "pngme",
+            "strip",
+            "a.png",
+            "--keep",
+            "tEXt,iCCP",
+            "--keep",
+            "gAMA",
+            "-o",
+            "b.png",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Strip(StripArgs {
+                file: PathBuf::from("a.png"),
+                keep: vec!["tEXt".to_string(), "iCCP".to_string(), "gAMA".to_string()],
+                output: Some(PathBuf::from("b.png")),
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_resplit() {
+        let args = parse_command(["pngme", "resplit", "a.png", "--idat-size", "8192"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Resplit(ResplitArgs {
+                file: PathBuf::from("a.png"),
+                idat_size: Some(8192),
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+        assert!(parse_command(["pngme", "resplit", "a.png", "--idat-size", "big"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_extract() {
+        let args = parse_command(["pngme", "extract", "a.png", "--out-dir", "d/"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Extract(ExtractArgs {
+                file: PathBuf::from("a.png"),
+                out_dir: PathBuf::from("d/"),
+                filter: None,
+                dry_run: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_carve() {
+        let args = parse_command(["pngme", "carve", "dump.bin", "--out-dir", "d/"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Carve(CarveArgs {
+                file: PathBuf::from("dump.bin"),
+                out_dir: PathBuf::from("d/"),
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+        match parse_command([
+            "pngme",
+            "carve",
+            "--dry-run",
+            "--bytes",
+            "x",
+            "--out-dir",
+            "d/",
+        ]) {
+            Ok(PngMeArgs::Carve(carve)) => assert_eq!(carve.units, Units::Exact),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_inject() {
+        let args = parse_command(["pngme", "inject", "a.png", "ruSt", "--data", "p.bin"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::Inject(InjectArgs {
+                file: PathBuf::from("a.png"),
+                chunk_type: "ruSt".to_string(),
+                data: PathBuf::from("p.bin"),
+                position: None,
+                provenance: None,
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            })
+        );
+        let args = parse_command([
+            "pngme",
+            "inject",
+            "a.png",
+            "ruSt",
+            "--data",
+            "p.bin",
+            "--position",
+            "1",
+        ])
+        .unwrap();
+        assert!(matches!(
+            args,
+            PngMeArgs::Inject(InjectArgs {
+                position: Some(1),
+                ..
+            })
+        ));
+        let args = parse_command([
+            "pngme",
+            "inject",
+            "a.png",
+            "ruSt",
+            "--data",
+            "p.bin",
+            "--reason",
+            "test",
+            "--operator",
+            "me",
+        ])
+        .unwrap();
+        match args {
+            PngMeArgs::Inject(inject) => assert_eq!(
+                inject.provenance,
+                Some(ProvenanceArgs {
+                    operator: "me".to_string(),
+                    reason: "test".to_string(),
+                })
+            ),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert!(parse_command([
+            "pngme",
+            "inject",
+            "a.png",
+            "ruSt",
+            "--data",
+            "p.bin",
+            "--operator",
+            "me"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_list() {
+        let args = parse_command(["pngme", "list", "a.png"]).unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::List(ListArgs {
+                file: PathBuf::from("a.png"),
+                filter: None,
+                units: Units::Human,
+                json: false,
+            })
+        );
+    }
+
+    #[test]
+    fn test_parse_schema() {
+        assert_eq!(
+            parse_command(["pngme", "schema", "scan"]).unwrap(),
+            PngMeArgs::Schema(SchemaArgs {
+                output: Some(Output::Scan)
+            })
+        );
+        assert_eq!(
+            parse_command(["pngme", "schema"]).unwrap(),
+            PngMeArgs::Schema(SchemaArgs { output: None })
+        );
+        assert!(parse_command(["pngme", "schema", "decode"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_bench_corpus() {
+        let args = parse_command([
+            "pngme",
+            "bench-corpus",
+            "generate",
+            "--out-dir",
+            "corpus",
+            "--chunk-size",
+            "64",
+            "--damage",
+            "bad-crc",
+        ])
+        .unwrap();
+        assert_eq!(
+            args,
+            PngMeArgs::BenchCorpus(BenchCorpusArgs {
+                out_dir: PathBuf::from("corpus"),
+                count: 1,
+                chunks: 4,
+                chunk_size: 64,
+                damage: Some(Damage::BadCrc),
+                seed: 1,
+                dry_run: false,
+            })
+        );
+        let (global, _) = parse_from([
+            "pngme",
+            "bench-corpus",
+            "generate",
+            "--out-dir",
+            "corpus",
+            "--read-only",
+        ])
+        .unwrap();
+        assert!(global.read_only);
+        assert!(parse_command(["pngme", "bench-corpus", "generate"]).is_err());
+        assert!(parse_command([
+            "pngme",
+            "bench-corpus",
+            "generate",
+            "--out-dir",
+            "c",
+            "--damage",
+            "melted"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_parse_dry_run() {
+        for args in [
+            ["pngme", "--dry-run", "strip", "a.png"],
+            ["pngme", "strip", "a.png", "--dry-run"],
+        ] {
+            match parse_command(args).unwrap() {
+                PngMeArgs::Strip(strip) => assert!(strip.dry_run),
+                other => panic!("unexpected {:?}", other),
+            }
+        }
+        match parse_command(["pngme", "strip", "a.png"]).unwrap() {
+            PngMeArgs::Strip(strip) => assert!(!strip.dry_run),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_read_only() {
+        for args in [
+            ["pngme", "--read-only", "strip", "a.png"],
+            ["pngme", "strip", "a.png", "--read-only"],
+        ] {
+            let (global, _) = parse_from(args).unwrap();
+            assert!(global.read_only);
+        }
+        let (global, _) = parse_from(["pngme", "print", "a.png"]).unwrap();
+        assert_eq!(global, GlobalArgs::default());
+    }
+
+    #[test]
+    fn test_parse_mode() {
+        let (global, _) = parse_from(["pngme", "--parse-mode", "strict", "info", "a.png"]).unwrap();
+        assert_eq!(global.parse, Some(ParseOptions::strict()));
+        let (global, _) = parse_from([
+            "pngme",
+            "info",
+            "a.png",
+            "--parse-mode",
+            "strict",
+            "--allow-unknown-critical",
+        ])
+        .unwrap();
+        let parse = global.parse.unwrap();
+        assert!(parse.allow_unknown_critical);
+        assert!(parse.strict_crc);
+        let (global, _) =
+            parse_from(["pngme", "print", "a.png", "--parse-mode", "normal"]).unwrap();
+        assert_eq!(global.parse, Some(ParseOptions::default()));
+        assert!(parse_from(["pngme", "--parse-mode", "lax", "info", "a.png"]).is_err());
+        assert!(parse_from(["pngme", "--allow-unknown-critical", "info", "a.png"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_timeout() {
+        let (global, _) = parse_from(["pngme", "scan", "dir", "--timeout", "30"]).unwrap();
+        assert_eq!(global.timeout, Some(Duration::from_secs(30)));
+        assert!(parse_from(["pngme", "--timeout", "soon", "scan", "dir"]).is_err());
+        let (global, _) =
+            parse_from(["pngme", "--timeout", "5", "encode", "a.png", "ruSt", "hi"]).unwrap();
+        assert_eq!(global.timeout, Some(Duration::from_secs(5)));
+        let err = parse_from(["pngme", "verify", "a.png", "--timeout", "5"]).unwrap_err();
+        assert_eq!(err.kind, ErrorKind::ArgumentConflict);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert!(parse_command(["pngme"]).is_err());
+        assert!(parse_command(["pngme", "encode", "a.png", "ruSt"]).is_err());
+        assert!(parse_command(["pngme", "decode", "a.png"]).is_err());
+        assert!(parse_command(["pngme", "print"]).is_err());
+        assert!(parse_command(["pngme", "scan"]).is_err());
+        assert!(parse_command(["pngme", "diff", "a.png"]).is_err());
+        assert!(parse_command(["pngme", "extract", "a.png"]).is_err());
+        assert!(parse_command(["pngme", "inject", "a.png", "ruSt"]).is_err());
+        assert!(parse_command([
+            "pngme",
+            "inject",
+            "a.png",
+            "ruSt",
+            "--data",
+            "p.bin",
+            "--position",
+            "-1"
+        ])
+        .is_err());
+        assert!(parse_command(["pngme", "bogus"]).is_err());
+    }
+
+    #[test]
+    fn test_app_is_consistent() {
+        app().debug_assert();
+    }
+}
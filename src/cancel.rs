@@ -0,0 +1,75 @@
+use std::fmt::{Display, Formatter};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Cooperative cancellation flag shared between a long-running operation and
+/// whoever may want to stop it. Clones share the same flag. Operations check
+/// it between chunks and fail with [`Cancelled`] once it is set or its
+/// deadline has passed.
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    _cancelled: Arc<AtomicBool>,
+    _deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        CancellationToken::default()
+    }
+    /// A token that cancels itself `timeout` from now.
+    pub fn with_timeout(timeout: Duration) -> Self {
+        CancellationToken {
+            _cancelled: Arc::default(),
+            _deadline: Some(Instant::now() + timeout),
+        }
+    }
+    pub fn cancel(&self) {
+        self._cancelled.store(true, Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self._cancelled.load(Ordering::Relaxed)
+            || self._deadline.is_some_and(|d| Instant::now() >= d)
+    }
+    /// Fails with [`Cancelled`] if the token has been cancelled.
+    pub fn check(&self) -> Result<(), Cancelled> {
+        if self.is_cancelled() {
+            Err(Cancelled)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Error returned by operations stopped through a [`CancellationToken`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cancelled;
+
+impl Display for Cancelled {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Invalid Operation : Cancelled")
+    }
+}
+
+impl std::error::Error for Cancelled {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancel_is_shared() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(clone.check().is_ok());
+        token.cancel();
+        assert!(clone.is_cancelled());
+        assert_eq!(clone.check(), Err(Cancelled));
+    }
+
+    #[test]
+    fn test_timeout() {
+        assert!(CancellationToken::with_timeout(Duration::ZERO).is_cancelled());
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(3600)).is_cancelled());
+    }
+}
@@ -0,0 +1,120 @@
+use crate::cancel::CancellationToken;
+use crate::png::Png;
+use crate::polyglot::{find, png_stream_end};
+use crate::vfs::Vfs;
+use anyhow::Result;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+/// Finds every complete PNG stream in an arbitrary blob (memory dump, pcap,
+/// disk image) by locating signatures and following the chunk framing to
+/// IEND. Signatures that do not lead to a complete stream are skipped.
+/// Once `token` is cancelled the search stops and fails with
+/// [`Cancelled`](crate::cancel::Cancelled).
+pub fn carve(value: &[u8], token: &CancellationToken) -> Result<Vec<Range<usize>>> {
+    let mut found = Vec::new();
+    let mut pos = 0;
+    while let Some(start) = find(value, &Png::STANDARD_HEADER, pos) {
+        token.check()?;
+        match png_stream_end(&value[start..]) {
+            Some(len) => {
+                found.push(start..start + len);
+                pos = start + len;
+            }
+            None => pos = start + 1,
+        }
+    }
+    Ok(found)
+}
+
+/// Writes each carved PNG to `dir` as `carved_NNNN_OFFSET.png` together with
+/// a `report.txt` listing index, offset and length, and returns the paths of
+/// the carved files. Fails with [`Cancelled`](crate::cancel::Cancelled)
+/// before writing anything once `token` is cancelled.
+pub fn carve_to_dir(
+    vfs: &dyn Vfs,
+    value: &[u8],
+    dir: &Path,
+    token: &CancellationToken,
+) -> Result<Vec<PathBuf>> {
+    let found = carve(value, token)?;
+    vfs.create_dir_all(dir)?;
+    let mut report = String::from("index\toffset\tlength\tfile\n");
+    let mut paths = Vec::new();
+    for (i, range) in found.into_iter().enumerate() {
+        let name = format!("carved_{:04}_{:08x}.png", i, range.start);
+        let path = dir.join(&name);
+        vfs.write(&path, &value[range.clone()])?;
+        report.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            i,
+            range.start,
+            range.len(),
+            name
+        ));
+        paths.push(path);
+    }
+    vfs.write(&dir.join("report.txt"), report.as_bytes())?;
+    Ok(paths)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::vfs::MemFs;
+    use std::str::FromStr;
+
+    fn minimal_png(data: &str) -> Vec<u8> {
+        let chunks = vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(
+                ChunkType::from_str("IDAT").unwrap(),
+                data.as_bytes().to_vec(),
+            ),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ];
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    fn blob() -> (Vec<u8>, Vec<Range<usize>>) {
+        let mut bytes = b"leading garbage".to_vec();
+        let first = minimal_png("first");
+        let second = minimal_png("second");
+        let a = bytes.len()..bytes.len() + first.len();
+        bytes.extend(first);
+        bytes.extend(&Png::STANDARD_HEADER);
+        bytes.extend(b"truncated stream");
+        let b = bytes.len()..bytes.len() + second.len();
+        bytes.extend(second);
+        bytes.extend(b"tail");
+        (bytes, vec![a, b])
+    }
+
+    #[test]
+    fn test_carve() {
+        let (bytes, expected) = blob();
+        let token = CancellationToken::new();
+        assert_eq!(carve(&bytes, &token).unwrap(), expected);
+        assert!(carve(b"no images here", &token).unwrap().is_empty());
+        token.cancel();
+        let err = carve(&bytes, &token).err().unwrap();
+        assert!(err.is::<crate::cancel::Cancelled>());
+    }
+
+    #[test]
+    fn test_carve_to_dir() {
+        let (bytes, expected) = blob();
+        let vfs = MemFs::new();
+        let dir = Path::new("carved");
+
+        let paths = carve_to_dir(&vfs, &bytes, dir, &CancellationToken::new()).unwrap();
+        assert_eq!(paths.len(), 2);
+        let carved = vfs.read(&paths[1]).unwrap();
+        assert_eq!(carved, &bytes[expected[1].clone()]);
+        assert!(Png::try_from(carved.as_ref()).is_ok());
+        let report = String::from_utf8(vfs.read(&dir.join("report.txt")).unwrap()).unwrap();
+        assert_eq!(report.lines().count(), 3);
+    }
+}
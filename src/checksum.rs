@@ -0,0 +1,54 @@
+use crc::{Algorithm, CRC_32_ISO_HDLC};
+
+/// The checksum stored after each chunk, computed over the chunk type and
+/// data. PNG uses [`PNG_CRC`]; other formats built on the same chunk grammar
+/// can supply their own.
+pub trait Checksum {
+    fn checksum(&self, chunk_type: &[u8; 4], data: &[u8]) -> u32;
+}
+
+/// A 32-bit CRC with any of the polynomials known to the `crc` crate.
+pub struct Crc32 {
+    _crc: crc::Crc<u32>,
+}
+
+impl Crc32 {
+    pub const fn new(algorithm: &'static Algorithm<u32>) -> Self {
+        Crc32 {
+            _crc: crc::Crc::<u32>::new(algorithm),
+        }
+    }
+}
+
+impl Checksum for Crc32 {
+    fn checksum(&self, chunk_type: &[u8; 4], data: &[u8]) -> u32 {
+        let mut digest = self._crc.digest();
+        digest.update(chunk_type);
+        digest.update(data);
+        digest.finalize()
+    }
+}
+
+/// The CRC-32 the PNG specification uses for every chunk.
+pub const PNG_CRC: Crc32 = Crc32::new(&CRC_32_ISO_HDLC);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crc::CRC_32_BZIP2;
+
+    #[test]
+    fn test_png_crc() {
+        assert_eq!(PNG_CRC.checksum(b"IEND", &[]), 0xae426082);
+        assert_eq!(
+            PNG_CRC.checksum(b"RuSt", b"This is where your secret message will be!"),
+            2882656334
+        );
+    }
+
+    #[test]
+    fn test_other_algorithm() {
+        let bzip2 = Crc32::new(&CRC_32_BZIP2);
+        assert_ne!(bzip2.checksum(b"IEND", &[]), PNG_CRC.checksum(b"IEND", &[]));
+    }
+}
@@ -1,9 +1,11 @@
+use crate::checksum::{Checksum, PNG_CRC};
 use crate::chunk_type::ChunkType;
+use crate::codec::read_u32;
 use anyhow::{anyhow, Result};
-use crc::{CRC_32_CKSUM, CRC_32_ISO_HDLC};
 use std::fmt::{Display, Formatter};
 use std::string::FromUtf8Error;
 
+#[derive(Clone)]
 pub struct Chunk {
     _length: u32,
     _type: ChunkType,
@@ -12,18 +14,73 @@ pub struct Chunk {
 }
 
 impl Chunk {
+    /// Builds a chunk around `_data`, which is stored byte for byte and need
+    /// not be text.
     pub fn new(_type: ChunkType, _data: Vec<u8>) -> Self {
-        let mut c = Chunk {
+        Chunk::new_with_checksum(_type, _data, &PNG_CRC)
+    }
+    /// Like [`Chunk::new`], with `checksum` in place of the PNG CRC.
+    pub fn new_with_checksum(_type: ChunkType, _data: Vec<u8>, checksum: &dyn Checksum) -> Self {
+        Chunk {
             _length: _data.len() as u32,
-            _crc: 0,
+            _crc: checksum.checksum(&_type.bytes(), &_data),
             _type,
             _data,
-        };
-        let rest = c.as_bytes();
-        let (_, rest) = rest.split_at(4);
-        let (rest, _) = rest.split_at(rest.len() - 4);
-        c._crc = crc::Crc::<u32>::new(&CRC_32_ISO_HDLC).checksum(rest);
-        c
+        }
+    }
+    /// Parses a chunk whose stored checksum was made with `checksum` rather
+    /// than the PNG CRC.
+    pub fn try_from_with_checksum(value: &[u8], checksum: &dyn Checksum) -> Result<Self> {
+        if value.len() < 12 {
+            Err(anyhow!(
+                "Invalid Chunk : Too Short , {} Bytes Left",
+                value.len()
+            ))
+        } else {
+            let length = read_u32(value, 0)?;
+            let rest = &value[4..];
+            if length as usize + 8 > rest.len() {
+                return Err(anyhow!(
+                    "Invalid Chunk Length {} : Too Short , {} Bytes Left",
+                    length,
+                    value.len()
+                ));
+            }
+            let (rest, _) = rest.split_at(length as usize + 8);
+            let (rest, crc) = rest.split_at(length as usize + 4);
+            let crc = read_u32(crc, 0)?;
+            let (chunk_type, data) = rest.split_at(4);
+            let chunk_type = <[u8; 4]>::try_from(chunk_type)?;
+
+            let _crc = checksum.checksum(&chunk_type, data);
+            if crc != _crc {
+                Err(anyhow!(
+                    "Invalid Chunk {} Length {} : Wrong CRC {} , Should Be {}",
+                    String::from_utf8_lossy(&chunk_type),
+                    length,
+                    crc,
+                    _crc
+                ))
+            } else {
+                Ok(Chunk {
+                    _length: length,
+                    _type: ChunkType::try_from(chunk_type).unwrap(),
+                    _data: data.to_vec(),
+                    _crc,
+                })
+            }
+        }
+    }
+    /// Builds a chunk from exactly the given fields, without checking that
+    /// `length` matches the data or recomputing the CRC. Meant for tests and
+    /// fuzzers that need malformed chunks; `as_bytes` writes the fields as is.
+    pub fn new_raw(length: u32, chunk_type: ChunkType, data: Vec<u8>, crc: u32) -> Self {
+        Chunk {
+            _length: length,
+            _type: chunk_type,
+            _data: data,
+            _crc: crc,
+        }
     }
     pub fn length(&self) -> u32 {
         self._length
@@ -40,6 +97,34 @@ impl Chunk {
     pub fn data_as_string(&self) -> std::result::Result<String, FromUtf8Error> {
         String::from_utf8(self._data.clone())
     }
+    /// Shannon entropy of the chunk data in bits per byte (0.0 to 8.0).
+    pub fn entropy(&self) -> f64 {
+        if self._data.is_empty() {
+            return 0.0;
+        }
+        let mut counts = [0usize; 256];
+        for b in &self._data {
+            counts[*b as usize] += 1;
+        }
+        let len = self._data.len() as f64;
+        counts
+            .iter()
+            .filter(|c| **c > 0)
+            .map(|c| {
+                let p = *c as f64 / len;
+                -p * p.log2()
+            })
+            .sum()
+    }
+    /// Estimated compressed/original size ratio, from the order-0 entropy.
+    /// Values near 1.0 mean the data is already compressed or encrypted.
+    pub fn compress_ratio_estimate(&self) -> f64 {
+        if self._data.is_empty() {
+            1.0
+        } else {
+            self.entropy() / 8.0
+        }
+    }
     pub fn as_bytes(&self) -> Vec<u8> {
         self._length
             .to_be_bytes()
@@ -56,58 +141,20 @@ impl TryFrom<&[u8]> for Chunk {
     type Error = anyhow::Error;
 
     fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
-        if value.len() < 12 {
-            Err(anyhow!("Invalid Chunk String {:?} : Too Short", value))
-        } else {
-            let (len, rest) = value.split_at(4);
-            if bytes_to_u32(len) > rest.len() as u32 {
-                return Err(anyhow!("Invalid Chunk String {:?} : Too Short", value));
-            }
-            let (rest, _) = rest.split_at(bytes_to_u32(len) as usize + 8);
-            let (rest, crc) = rest.split_at(bytes_to_u32(len) as usize + 4);
-
-            let _crc = crc::Crc::<u32>::new(&CRC_32_ISO_HDLC).checksum(rest);
-            if bytes_to_u32(crc) != _crc {
-                Err(anyhow!(
-                    "Invalid Chunk String {:?} : Wrong CRC {} , Should Be {}",
-                    value,
-                    bytes_to_u32(crc),
-                    _crc
-                ))
-            } else {
-                let (chunk_type, data) = rest.split_at(4);
-
-                Ok(Chunk {
-                    _length: bytes_to_u32(len),
-                    _type: ChunkType::try_from(<[u8; 4]>::try_from(chunk_type)?).unwrap(),
-                    _data: data.to_vec(),
-                    _crc,
-                })
-            }
-        }
+        Chunk::try_from_with_checksum(value, &PNG_CRC)
     }
 }
 
 impl Display for Chunk {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-        write!(f, "s")
+        write!(
+            f,
+            "{} ({} bytes, crc {:#010x})",
+            self._type, self._length, self._crc
+        )
     }
 }
 
-fn bytes_to_u32(value: &[u8]) -> u32 {
-    ((value[0] as u32) << 24)
-        + ((value[1] as u32) << 16)
-        + ((value[2] as u32) << 8)
-        + ((value[3] as u32) << 0)
-}
-fn u32_to_bytes(value: u32) -> [u8; 4] {
-    [
-        (value & 0xFF000000) as u8,
-        (value & 0x00FF0000) as u8,
-        (value & 0x0000FF00) as u8,
-        (value & 0x000000FF) as u8,
-    ]
-}
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -185,6 +232,7 @@ mod tests {
         assert_eq!(chunk.crc(), 2882656334);
     }
     #[test]
+    #[allow(unused_variables, clippy::iter_cloned_collect)]
     fn test_valid_chunk_from_bytes_str() {
         let data_length: u32 = 42;
         let chunk_type = "RuSt".as_bytes();
@@ -225,6 +273,94 @@ mod tests {
         assert!(chunk.is_err());
     }
 
+    #[test]
+    fn test_invalid_chunk_error_is_short() {
+        let message_bytes = "This is where your secret message will be!".as_bytes();
+        let mut bytes =
+            Chunk::new(ChunkType::from_str("RuSt").unwrap(), message_bytes.to_vec()).as_bytes();
+        let crc_start = bytes.len() - 4;
+        bytes[crc_start..].copy_from_slice(&2882656333u32.to_be_bytes());
+        assert_eq!(
+            Chunk::try_from(bytes.as_ref()).err().unwrap().to_string(),
+            "Invalid Chunk RuSt Length 42 : Wrong CRC 2882656333 , Should Be 2882656334"
+        );
+
+        let mut bytes =
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![7; 100_000]).as_bytes();
+        let last = bytes.len() - 1;
+        bytes[last] ^= 1;
+        let err = Chunk::try_from(bytes.as_ref()).err().unwrap().to_string();
+        assert!(err.len() < 100, "{}", err);
+        let err = Chunk::try_from(&bytes[..50_000]).err().unwrap().to_string();
+        assert_eq!(
+            err,
+            "Invalid Chunk Length 100000 : Too Short , 50000 Bytes Left"
+        );
+    }
+
+    #[test]
+    fn test_truncated_chunk_from_bytes() {
+        let chunk_data: Vec<u8> = 10u32
+            .to_be_bytes()
+            .iter()
+            .chain("RuSt".as_bytes().iter())
+            .chain([0u8; 8].iter())
+            .copied()
+            .collect();
+
+        let chunk = Chunk::try_from(chunk_data.as_ref());
+
+        assert!(chunk.is_err());
+    }
+
+    #[test]
+    fn test_new_raw_chunk() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let chunk = Chunk::new_raw(99, chunk_type, b"data".to_vec(), 1);
+        assert_eq!(chunk.length(), 99);
+        assert_eq!(chunk.crc(), 1);
+
+        let bytes = chunk.as_bytes();
+        assert_eq!(&bytes[..4], &99u32.to_be_bytes());
+        assert_eq!(&bytes[bytes.len() - 4..], &1u32.to_be_bytes());
+        assert!(Chunk::try_from(bytes.as_ref()).is_err());
+    }
+
+    #[test]
+    fn test_chunk_entropy() {
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let uniform = Chunk::new(chunk_type, vec![7; 64]);
+        assert_eq!(uniform.entropy(), 0.0);
+        assert_eq!(uniform.compress_ratio_estimate(), 0.0);
+
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let all_bytes = Chunk::new(chunk_type, (0..=255).collect());
+        assert_eq!(all_bytes.entropy(), 8.0);
+        assert_eq!(all_bytes.compress_ratio_estimate(), 1.0);
+
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let two_symbols = Chunk::new(chunk_type, b"abababab".to_vec());
+        assert_eq!(two_symbols.entropy(), 1.0);
+
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let empty = Chunk::new(chunk_type, Vec::new());
+        assert_eq!(empty.entropy(), 0.0);
+        assert_eq!(empty.compress_ratio_estimate(), 1.0);
+    }
+
+    #[test]
+    fn test_chunk_with_checksum() {
+        let bzip2 = crate::checksum::Crc32::new(&crc::CRC_32_BZIP2);
+        let chunk_type = ChunkType::from_str("RuSt").unwrap();
+        let chunk = Chunk::new_with_checksum(chunk_type, b"data".to_vec(), &bzip2);
+        let bytes = chunk.as_bytes();
+
+        assert!(Chunk::try_from(bytes.as_ref()).is_err());
+        let parsed = Chunk::try_from_with_checksum(&bytes, &bzip2).unwrap();
+        assert_eq!(parsed.crc(), chunk.crc());
+        assert_eq!(parsed.data(), b"data");
+    }
+
     #[test]
     pub fn test_chunk_trait_impls() {
         let data_length: u32 = 42;
@@ -243,6 +379,7 @@ mod tests {
 
         let chunk: Chunk = TryFrom::try_from(chunk_data.as_ref()).unwrap();
 
-        let _chunk_string = format!("{}", chunk);
+        let chunk_string = format!("{}", chunk);
+        assert_eq!(chunk_string, "RuSt (42 bytes, crc 0xabd1d84e)");
     }
 }
@@ -1,9 +1,20 @@
-use anyhow::{anyhow, Result};
+use anyhow::Result;
+use bitflags::bitflags;
 use core::fmt;
 use std::fmt::{Debug, Formatter};
 use std::str::FromStr;
 
-#[derive(Debug)]
+bitflags! {
+    /// The four property bits carried by the case of each chunk type letter.
+    pub struct ChunkProperties: u8 {
+        const ANCILLARY = 0b0001;
+        const PRIVATE = 0b0010;
+        const RESERVED = 0b0100;
+        const SAFE_TO_COPY = 0b1000;
+    }
+}
+
+#[derive(Debug, Clone)]
 pub struct ChunkType {
     _data: [u8; 4],
 }
@@ -31,6 +42,38 @@ impl ChunkType {
     pub fn is_safe_to_copy(&self) -> bool {
         self._data[3] & 32 != 0
     }
+    pub fn properties(&self) -> ChunkProperties {
+        let mut p = ChunkProperties::empty();
+        p.set(ChunkProperties::ANCILLARY, !self.is_critical());
+        p.set(ChunkProperties::PRIVATE, !self.is_public());
+        p.set(ChunkProperties::RESERVED, !self.is_reserved_bit_valid());
+        p.set(ChunkProperties::SAFE_TO_COPY, self.is_safe_to_copy());
+        p
+    }
+    pub fn with_properties(self, properties: ChunkProperties) -> Self {
+        self.with_bit(0, properties.contains(ChunkProperties::ANCILLARY))
+            .with_bit(1, properties.contains(ChunkProperties::PRIVATE))
+            .with_bit(2, properties.contains(ChunkProperties::RESERVED))
+            .with_bit(3, properties.contains(ChunkProperties::SAFE_TO_COPY))
+    }
+    pub fn with_ancillary(self, ancillary: bool) -> Self {
+        self.with_bit(0, ancillary)
+    }
+    pub fn with_private(self, private: bool) -> Self {
+        self.with_bit(1, private)
+    }
+    pub fn with_safe_to_copy(self, safe_to_copy: bool) -> Self {
+        self.with_bit(3, safe_to_copy)
+    }
+    /// Sets (lowercase) or clears (uppercase) the property bit of one letter.
+    fn with_bit(mut self, index: usize, set: bool) -> Self {
+        if set {
+            self._data[index] |= 32;
+        } else {
+            self._data[index] &= !32;
+        }
+        self
+    }
 }
 
 impl TryFrom<[u8; 4]> for ChunkType {
@@ -42,35 +85,75 @@ impl TryFrom<[u8; 4]> for ChunkType {
 }
 
 impl FromStr for ChunkType {
-    type Err = anyhow::Error;
+    type Err = ChunkTypeError;
 
+    /// Requires exactly four ASCII letters; see [`ChunkType::from_str_lenient`]
+    /// for reading types out of damaged files.
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() < 4 {
-            Err(anyhow!("Invalid Chunk Type String {} : Too Short", s))
-        } else {
-            if s.as_bytes()[0].is_ascii_alphabetic()
-                && s.as_bytes()[1].is_ascii_alphabetic()
-                && s.as_bytes()[2].is_ascii_alphabetic()
-                && s.as_bytes()[3].is_ascii_alphabetic()
-            {
-                Ok(ChunkType {
-                    _data: [
-                        s.as_bytes()[0],
-                        s.as_bytes()[1],
-                        s.as_bytes()[2],
-                        s.as_bytes()[3],
-                    ],
-                })
-            } else {
-                Err(anyhow!(
-                    "Invalid Chunk Type String {} : Invalid Character",
-                    s
-                ))
-            }
+        if !s.is_ascii() {
+            return Err(ChunkTypeError::NonAscii(s.to_string()));
+        }
+        match s.len() {
+            0..=3 => return Err(ChunkTypeError::TooShort(s.to_string())),
+            4 => {}
+            _ => return Err(ChunkTypeError::TooLong(s.to_string())),
+        }
+        if let Some(index) = s.bytes().position(|b| !b.is_ascii_alphabetic()) {
+            return Err(ChunkTypeError::InvalidCharacter {
+                value: s.to_string(),
+                index,
+            });
+        }
+        let b = s.as_bytes();
+        Ok(ChunkType {
+            _data: [b[0], b[1], b[2], b[3]],
+        })
+    }
+}
+
+impl ChunkType {
+    /// Takes the first four bytes of `s` as they are, whatever they contain,
+    /// and ignores the rest. Only fails if `s` is shorter than four bytes.
+    pub fn from_str_lenient(s: &str) -> Result<Self, ChunkTypeError> {
+        match s.as_bytes() {
+            [a, b, c, d, ..] => Ok(ChunkType {
+                _data: [*a, *b, *c, *d],
+            }),
+            _ => Err(ChunkTypeError::TooShort(s.to_string())),
+        }
+    }
+}
+
+/// Why [`ChunkType::from_str`] rejected a string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkTypeError {
+    TooShort(String),
+    TooLong(String),
+    NonAscii(String),
+    /// The byte at `index` is not an ASCII letter.
+    InvalidCharacter {
+        value: String,
+        index: usize,
+    },
+}
+
+impl fmt::Display for ChunkTypeError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkTypeError::TooShort(s) => write!(f, "Invalid Chunk Type String {} : Too Short", s),
+            ChunkTypeError::TooLong(s) => write!(f, "Invalid Chunk Type String {} : Too Long", s),
+            ChunkTypeError::NonAscii(s) => write!(f, "Invalid Chunk Type String {} : Not ASCII", s),
+            ChunkTypeError::InvalidCharacter { value, index } => write!(
+                f,
+                "Invalid Chunk Type String {} : Invalid Character At {}",
+                value, index
+            ),
         }
     }
 }
 
+impl std::error::Error for ChunkTypeError {}
+
 impl fmt::Display for ChunkType {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         write!(
@@ -114,6 +197,38 @@ mod tests {
         assert_eq!(expected, actual);
     }
 
+    #[test]
+    pub fn test_chunk_type_from_str_errors() {
+        assert_eq!(
+            ChunkType::from_str("Rus").unwrap_err(),
+            ChunkTypeError::TooShort("Rus".to_string())
+        );
+        assert_eq!(
+            ChunkType::from_str("RuSty").unwrap_err(),
+            ChunkTypeError::TooLong("RuSty".to_string())
+        );
+        assert_eq!(
+            ChunkType::from_str("Ru\u{e9}").unwrap_err(),
+            ChunkTypeError::NonAscii("Ru\u{e9}".to_string())
+        );
+        assert_eq!(
+            ChunkType::from_str("Ru1t").unwrap_err(),
+            ChunkTypeError::InvalidCharacter {
+                value: "Ru1t".to_string(),
+                index: 2
+            }
+        );
+    }
+
+    #[test]
+    pub fn test_chunk_type_from_str_lenient() {
+        let chunk = ChunkType::from_str_lenient("RuSty").unwrap();
+        assert_eq!(&chunk.to_string(), "RuSt");
+        let chunk = ChunkType::from_str_lenient("Ru1t").unwrap();
+        assert_eq!(chunk.bytes(), *b"Ru1t");
+        assert!(ChunkType::from_str_lenient("Ru").is_err());
+    }
+
     #[test]
     pub fn test_chunk_type_is_critical() {
         let chunk = ChunkType::from_str("RuSt").unwrap();
@@ -183,6 +298,45 @@ mod tests {
         assert_eq!(&chunk.to_string(), "RuSt");
     }
 
+    #[test]
+    pub fn test_chunk_type_properties() {
+        let chunk = ChunkType::from_str("ruSt").unwrap();
+        assert_eq!(
+            chunk.properties(),
+            ChunkProperties::ANCILLARY | ChunkProperties::PRIVATE | ChunkProperties::SAFE_TO_COPY
+        );
+
+        let chunk = ChunkType::from_str("IHDR").unwrap();
+        assert!(chunk.properties().is_empty());
+    }
+
+    #[test]
+    pub fn test_chunk_type_with_property_bits() {
+        let chunk = ChunkType::from_str("RUST")
+            .unwrap()
+            .with_ancillary(true)
+            .with_private(true)
+            .with_safe_to_copy(true);
+        assert_eq!(&chunk.to_string(), "ruSt");
+        assert!(!chunk.is_critical());
+        assert!(!chunk.is_public());
+        assert!(chunk.is_reserved_bit_valid());
+        assert!(chunk.is_safe_to_copy());
+
+        let chunk = chunk.with_ancillary(false).with_safe_to_copy(false);
+        assert_eq!(&chunk.to_string(), "RuST");
+    }
+
+    #[test]
+    pub fn test_chunk_type_with_properties() {
+        let chunk = ChunkType::from_str("abcd")
+            .unwrap()
+            .with_properties(ChunkProperties::PRIVATE);
+        assert_eq!(&chunk.to_string(), "AbCD");
+        assert_eq!(chunk.properties(), ChunkProperties::PRIVATE);
+        assert!(chunk.is_valid());
+    }
+
     #[test]
     pub fn test_chunk_type_trait_impls() {
         let chunk_type_1: ChunkType = TryFrom::try_from([82, 117, 83, 116]).unwrap();
@@ -0,0 +1,98 @@
+use anyhow::{anyhow, Result};
+
+/// Reads `N` bytes at `pos`, failing instead of panicking when out of bounds.
+fn read_array<const N: usize>(data: &[u8], pos: usize) -> Result<[u8; N]> {
+    pos.checked_add(N)
+        .and_then(|end| data.get(pos..end))
+        .map(|bytes| bytes.try_into().unwrap())
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid Read Of {} Bytes At {} : Only {} Available",
+                N,
+                pos,
+                data.len()
+            )
+        })
+}
+
+pub fn read_u8(data: &[u8], pos: usize) -> Result<u8> {
+    Ok(read_array::<1>(data, pos)?[0])
+}
+
+pub fn read_u16(data: &[u8], pos: usize) -> Result<u16> {
+    Ok(u16::from_be_bytes(read_array(data, pos)?))
+}
+
+pub fn read_u32(data: &[u8], pos: usize) -> Result<u32> {
+    Ok(u32::from_be_bytes(read_array(data, pos)?))
+}
+
+pub fn read_i32(data: &[u8], pos: usize) -> Result<i32> {
+    Ok(i32::from_be_bytes(read_array(data, pos)?))
+}
+
+pub fn read_u64(data: &[u8], pos: usize) -> Result<u64> {
+    Ok(u64::from_be_bytes(read_array(data, pos)?))
+}
+
+pub fn write_u8(out: &mut Vec<u8>, value: u8) {
+    out.push(value);
+}
+
+pub fn write_u16(out: &mut Vec<u8>, value: u16) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub fn write_u32(out: &mut Vec<u8>, value: u32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub fn write_i32(out: &mut Vec<u8>, value: i32) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+pub fn write_u64(out: &mut Vec<u8>, value: u64) {
+    out.extend_from_slice(&value.to_be_bytes());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_values() {
+        let data = [0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0, 0xff];
+        assert_eq!(read_u8(&data, 8).unwrap(), 0xff);
+        assert_eq!(read_u16(&data, 1).unwrap(), 0x3456);
+        assert_eq!(read_u32(&data, 0).unwrap(), 0x12345678);
+        assert_eq!(read_u32(&data, 5).unwrap(), 0xbcdef0ff);
+        assert_eq!(read_i32(&[0xff, 0xff, 0xff, 0xfe], 0).unwrap(), -2);
+        assert_eq!(read_u64(&data, 1).unwrap(), 0x3456789abcdef0ff);
+    }
+
+    #[test]
+    fn test_read_out_of_bounds() {
+        let data = [1, 2, 3, 4];
+        assert!(read_u8(&data, 4).is_err());
+        assert!(read_u16(&data, 3).is_err());
+        assert!(read_u32(&data, 1).is_err());
+        assert!(read_u32(&data, usize::MAX).is_err());
+        assert!(read_u64(&data, 0).is_err());
+    }
+
+    #[test]
+    fn test_write_round_trip() {
+        let mut out = Vec::new();
+        write_u8(&mut out, 7);
+        write_u16(&mut out, 0xbeef);
+        write_u32(&mut out, 0xdeadbeef);
+        write_i32(&mut out, -5);
+        write_u64(&mut out, u64::MAX - 1);
+        assert_eq!(out.len(), 19);
+        assert_eq!(read_u8(&out, 0).unwrap(), 7);
+        assert_eq!(read_u16(&out, 1).unwrap(), 0xbeef);
+        assert_eq!(read_u32(&out, 3).unwrap(), 0xdeadbeef);
+        assert_eq!(read_i32(&out, 7).unwrap(), -5);
+        assert_eq!(read_u64(&out, 11).unwrap(), u64::MAX - 1);
+    }
+}
@@ -0,0 +1,3114 @@
+use crate::args::{
+    BenchCorpusArgs, CarveArgs, DecodeArgs, DiffArgs, DoctorArgs, EncodeArgs, ExtractArgs,
+    ITxtArgs, InfoArgs, InjectArgs, ListArgs, Payload, PrintArgs, ProvenanceArgs, RemoveArgs,
+    RepairArgs, ReportArgs, ResplitArgs, SalvageArgs, ScanArgs, SchemaArgs, StripArgs,
+    TimelineArgs, VerifyArgs,
+};
+use crate::json::{base64, Json};
+use crate::schema::{self, Output};
+use crate::Result;
+use pngme::cancel::CancellationToken;
+use pngme::chunk::Chunk;
+use pngme::chunk_type::{ChunkProperties, ChunkType};
+use pngme::corpus::{self, CorpusOptions, Damage};
+use pngme::doctor::{diagnose, Finding, Remedy, Severity};
+use pngme::events::Event;
+use pngme::filter::Filter;
+use pngme::png::{ParseOptions, Png, Warning};
+use pngme::preview;
+use pngme::provenance::{self, Provenance};
+use pngme::registry;
+use pngme::repair::{Fix, RepairOptions};
+use pngme::report::{corpus_report, CorpusReport};
+use pngme::scan::{scan_dir, ScanEntry, ScanOutcome, ScanTarget};
+use pngme::text::ITxt;
+use pngme::units::{self, Units};
+use pngme::verify::{full_check, FullCheck};
+use pngme::vfs::Vfs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Adds a chunk holding the message, or the bytes of the input file or
+/// stdin, before IEND and saves the result to the output path, or back over
+/// the input. With `--keyword` the payload is stored as iTXt text.
+pub fn encode(
+    vfs: &dyn Vfs,
+    args: &EncodeArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let chunk_type = ChunkType::from_str(&args.chunk_type)?;
+    if !chunk_type.is_valid() {
+        return Err(format!("Invalid Chunk Type {} : Reserved Bit Set", chunk_type).into());
+    }
+    let data = read_payload(vfs, &args.payload)?;
+    let chunk = match &args.itxt {
+        Some(itxt) => itxt_chunk(&chunk_type, itxt, data)?,
+        None => Chunk::new(chunk_type, data),
+    };
+    let mut png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    let original = png.clone();
+    png.insert_before_end(chunk.clone());
+    annotate(&mut png, &chunk, args.provenance.as_ref())?;
+    save(
+        vfs,
+        &original,
+        &png,
+        &args.file,
+        args.output.as_deref(),
+        args.dry_run,
+        args.units,
+    )
+}
+
+/// Frames UTF-8 `text` as an iTXt chunk with the `--keyword`, `--lang` and
+/// `--translated-keyword` fields.
+fn itxt_chunk(chunk_type: &ChunkType, args: &ITxtArgs, text: Vec<u8>) -> Result<Chunk> {
+    if chunk_type.to_string() != ITxt::CHUNK_TYPE {
+        return Err(format!(
+            "Invalid Chunk Type {} : --keyword Needs {}",
+            chunk_type,
+            ITxt::CHUNK_TYPE
+        )
+        .into());
+    }
+    let text =
+        String::from_utf8(text).map_err(|e| format!("Invalid iTXt Text : {}", e.utf8_error()))?;
+    let itxt = ITxt::new(&args.keyword, &text)?
+        .with_language(&args.language_tag, &args.translated_keyword)?;
+    Ok(itxt.to_chunk())
+}
+
+fn read_payload(vfs: &dyn Vfs, payload: &Payload) -> Result<Vec<u8>> {
+    match payload {
+        Payload::Message(message) => Ok(message.as_bytes().to_vec()),
+        Payload::File(file) => Ok(vfs
+            .read(file)
+            .map_err(|e| format!("Invalid Input File {} : {}", file.display(), e))?),
+        Payload::Stdin => {
+            let mut data = Vec::new();
+            io::stdin().read_to_end(&mut data)?;
+            Ok(data)
+        }
+    }
+}
+
+/// Adds a chunk holding the bytes of the data file, before IEND or at the
+/// requested index, and saves the result to the output path, or back over
+/// the input.
+pub fn inject(
+    vfs: &dyn Vfs,
+    args: &InjectArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let chunk_type = ChunkType::from_str(&args.chunk_type)?;
+    if !chunk_type.is_valid() {
+        return Err(format!("Invalid Chunk Type {} : Reserved Bit Set", chunk_type).into());
+    }
+    let data = vfs
+        .read(&args.data)
+        .map_err(|e| format!("Invalid Data File {} : {}", args.data.display(), e))?;
+    let mut png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    let original = png.clone();
+    let chunk = Chunk::new(chunk_type, data);
+    match args.position {
+        Some(position) => png.insert_chunk_at(position, chunk.clone())?,
+        None => png.insert_before_end(chunk.clone()),
+    }
+    annotate(&mut png, &chunk, args.provenance.as_ref())?;
+    save(
+        vfs,
+        &original,
+        &png,
+        &args.file,
+        args.output.as_deref(),
+        args.dry_run,
+        args.units,
+    )
+}
+
+/// Records the `--reason` of a newly added `chunk` in the prOV chunk of
+/// `png`, if one was given.
+fn annotate(png: &mut Png, chunk: &Chunk, args: Option<&ProvenanceArgs>) -> Result<()> {
+    if let Some(args) = args {
+        provenance::annotate(png, chunk, Provenance::new(&args.operator, &args.reason))?;
+    }
+    Ok(())
+}
+
+/// Prints the message of every chunk of the requested type, one per line,
+/// or with `--json` every such chunk with its data in base64. With
+/// `--output-file` the raw data of those chunks, in file order, is written
+/// to that file instead.
+pub fn decode(
+    vfs: &dyn Vfs,
+    args: &DecodeArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    if let Some(output_file) = &args.output_file {
+        if same_file(vfs, &args.file, output_file) {
+            return Err(format!(
+                "Invalid Output File {} : Same As The Input",
+                output_file.display()
+            )
+            .into());
+        }
+        let payload = payload(&png, &args.chunk_type)?;
+        if args.dry_run {
+            println!(
+                "would write {} to {}",
+                units::size(payload.len() as u64, args.units),
+                output_file.display()
+            );
+            return Ok(());
+        }
+        vfs.write(output_file, &payload)
+            .map_err(|e| format!("Invalid Output File {} : {}", output_file.display(), e))?;
+    } else if args.json {
+        println!("{}", decode_json(&png, &args.file, &args.chunk_type)?);
+    } else {
+        for message in messages(&png, &args.chunk_type)? {
+            println!("{}", message);
+        }
+    }
+    Ok(())
+}
+
+/// Removes the first chunk of the requested type, the one at `--index`, all
+/// of them, or every chunk matching `--where`, and saves the result to the
+/// output path, or back over the input. Critical chunks are only removed
+/// with `force`.
+pub fn remove(
+    vfs: &dyn Vfs,
+    args: &RemoveArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let filter = chunk_filter(args.filter.as_deref())?;
+    if let Some(chunk_type) = &args.chunk_type {
+        let chunk_type = ChunkType::from_str(chunk_type)?;
+        if chunk_type.is_critical() && !args.force {
+            return Err(format!(
+                "Invalid Remove Chunk Type {} : Critical Chunk, Use --force",
+                chunk_type
+            )
+            .into());
+        }
+    }
+    let mut png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    let original = png.clone();
+    match (&filter, &args.chunk_type) {
+        (Some(filter), _) => {
+            let expr = args.filter.as_deref().unwrap_or_default();
+            if !args.force {
+                if let Some(chunk) = png
+                    .chunks()
+                    .find(|c| c.chunk_type().is_critical() && filter.matches(c))
+                {
+                    return Err(format!(
+                        "Invalid Remove Filter {} : Matches Critical Chunk {}, Use --force",
+                        expr,
+                        chunk.chunk_type()
+                    )
+                    .into());
+                }
+            }
+            if png.remove_chunks_where(|c| filter.matches(c)).is_empty() {
+                return Err(
+                    format!("Invalid Remove Filter {} : No Matching Chunk in Png", expr).into(),
+                );
+            }
+        }
+        (None, Some(chunk_type)) if args.all => {
+            if png.remove_chunks_by_type(chunk_type).is_empty() {
+                return Err(format!(
+                    "Invalid Remove Chunk Type {} : No such Chunk in Png",
+                    chunk_type
+                )
+                .into());
+            }
+        }
+        (None, Some(chunk_type)) => match args.index {
+            Some(index) => {
+                png.remove_chunk_at(chunk_type, index)?;
+            }
+            None => {
+                png.remove_first_chunk(chunk_type)?;
+            }
+        },
+        (None, None) => return Err("Invalid Remove : Give A Chunk Type Or --where".into()),
+    }
+    save(
+        vfs,
+        &original,
+        &png,
+        &args.file,
+        args.output.as_deref(),
+        args.dry_run,
+        args.units,
+    )
+}
+
+/// Removes every ancillary chunk except the `--keep` types and saves the
+/// result. Critical chunks cannot be kept or removed this way, so listing
+/// one in `--keep` is an error.
+pub fn strip(
+    vfs: &dyn Vfs,
+    args: &StripArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    for keep in &args.keep {
+        if ChunkType::from_str(keep)?.is_critical() {
+            return Err(format!(
+                "Invalid Strip Keep Type {} : Critical Chunks Are Always Kept",
+                keep
+            )
+            .into());
+        }
+    }
+    let mut png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    let original = png.clone();
+    png.remove_ancillary_chunks(&args.keep);
+    save(
+        vfs,
+        &original,
+        &png,
+        &args.file,
+        args.output.as_deref(),
+        args.dry_run,
+        args.units,
+    )
+}
+
+/// Rewrites the image data into IDAT chunks of at most `--idat-size` bytes,
+/// or a single IDAT, and saves the result. The compressed stream itself is
+/// unchanged.
+pub fn resplit(
+    vfs: &dyn Vfs,
+    args: &ResplitArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let mut png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    let original = png.clone();
+    png.resplit_idat(args.idat_size)?;
+    save(
+        vfs,
+        &original,
+        &png,
+        &args.file,
+        args.output.as_deref(),
+        args.dry_run,
+        args.units,
+    )
+}
+
+/// Saves `png` where [`destination`] says, or with `dry_run` only prints
+/// how it differs from `original` and how big the written file would be.
+fn save(
+    vfs: &dyn Vfs,
+    original: &Png,
+    png: &Png,
+    file: &Path,
+    output: Option<&Path>,
+    dry_run: bool,
+    units: Units,
+) -> Result<()> {
+    let path = destination(vfs, file, output);
+    if dry_run {
+        print!("{}", dry_run_report(original, png, &path, units));
+        return Ok(());
+    }
+    png.save_to_file(vfs, path)?;
+    Ok(())
+}
+
+fn dry_run_report(original: &Png, png: &Png, path: &Path, units: Units) -> String {
+    let mut report = String::new();
+    for change in pngme::diff::diff(original, png) {
+        report.push_str(&format!("{}\n", change));
+    }
+    let size = png.as_bytes().len();
+    let before = original.as_bytes().len();
+    report.push_str(&format!("{}\n", would_write(path, size, before, units)));
+    report
+}
+
+fn would_write(path: &Path, size: usize, before: usize, units: Units) -> String {
+    format!(
+        "would write {} to {} (input is {})",
+        units::size(size as u64, units),
+        path.display(),
+        units::size(before as u64, units)
+    )
+}
+
+/// Where a changed file is saved: `output` if given, otherwise `file`. An
+/// input reached through a symlink, or an output that names the input under
+/// another spelling or through a symlink, resolves to the real input file,
+/// so the edit happens in place (atomically, see `Vfs::write_atomic`)
+/// instead of replacing the link with a new file.
+fn destination(vfs: &dyn Vfs, file: &Path, output: Option<&Path>) -> PathBuf {
+    match output {
+        Some(output) if !same_file(vfs, file, output) => output.to_path_buf(),
+        _ => vfs
+            .canonicalize(file)
+            .unwrap_or_else(|_| file.to_path_buf()),
+    }
+}
+
+/// True if both paths exist in `vfs` and resolve to the same file.
+fn same_file(vfs: &dyn Vfs, a: &Path, b: &Path) -> bool {
+    match (vfs.canonicalize(a), vfs.canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Prints one table row per chunk, or with `--json` the chunk list as JSON.
+/// Unless `--parse-mode` says otherwise the file is parsed permissively, so
+/// damaged chunks can be inspected too; in table mode the parse warnings go
+/// to stderr. With `--head` only the first chunks are parsed. Previews show
+/// the raw data unless `--decode` asks for known chunks to be decoded.
+pub fn print(
+    vfs: &dyn Vfs,
+    args: &PrintArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let opts = parse.unwrap_or_else(ParseOptions::permissive);
+    let png = match args.head {
+        Some(n) => {
+            let png = Png::parse_prefix(&read_bytes(vfs, &args.file)?, &opts, n)?;
+            token.check()?;
+            png
+        }
+        None => read_png(vfs, &args.file, opts, token)?,
+    };
+    if args.json {
+        println!("{}", print_json(&png, &args.file, args.decode));
+    } else {
+        print!("{}", chunk_table(&png, args.decode));
+        for warning in png.warnings() {
+            eprintln!("Warning : {}", warning);
+        }
+    }
+    Ok(())
+}
+
+/// Lists the PNG files under a directory that hold the requested chunk
+/// type, or any private chunk, and any embedded formats or extra PNG
+/// streams found in them. Files that fail to parse are reported and
+/// skipped; the whole scan fails once `token` is cancelled.
+pub fn scan(
+    vfs: &dyn Vfs,
+    args: &ScanArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let target = match &args.chunk_type {
+        Some(chunk_type) => {
+            ChunkType::from_str(chunk_type)?;
+            ScanTarget::ChunkType(chunk_type.clone())
+        }
+        None => ScanTarget::Private,
+    };
+    let entries = scan_dir(
+        vfs,
+        &args.dir,
+        &target,
+        &parse.unwrap_or_default(),
+        token,
+        &mut |_: &Event| {},
+    )?;
+    if args.json {
+        println!("{}", scan_json(&entries, &args.dir));
+    } else {
+        print!("{}", scan_report(&entries, args.units));
+    }
+    Ok(())
+}
+
+/// One line per matching or failed file, listing each matching chunk with
+/// its entropy in bits per byte and estimated compression ratio, and one
+/// per embedded format, then a summary line.
+fn scan_report(entries: &[ScanEntry], units: Units) -> String {
+    let mut report = String::new();
+    let mut matched = 0;
+    let mut failed = 0;
+    let mut polyglots = 0;
+    for entry in entries {
+        match &entry.outcome {
+            ScanOutcome::Matches(matches) if matches.is_empty() => {}
+            ScanOutcome::Matches(matches) => {
+                matched += 1;
+                let chunks: Vec<String> = matches
+                    .iter()
+                    .map(|m| {
+                        format!(
+                            "{} {} (entropy {:.2}, ratio {:.2})",
+                            m.chunk_type,
+                            units::size(m.length as u64, units),
+                            m.entropy,
+                            m.compress_ratio
+                        )
+                    })
+                    .collect();
+                report.push_str(&format!(
+                    "{}: {}\n",
+                    entry.path.display(),
+                    chunks.join(", ")
+                ));
+            }
+            ScanOutcome::Failed(e) => {
+                failed += 1;
+                report.push_str(&format!("{}: error: {}\n", entry.path.display(), e));
+            }
+        }
+        if !entry.embedded.is_empty() {
+            polyglots += 1;
+        }
+        for embedded in &entry.embedded {
+            report.push_str(&format!(
+                "{}: embedded {} at offset {}{}\n",
+                entry.path.display(),
+                embedded.format.name(),
+                embedded.offset,
+                if embedded.trailing { " after IEND" } else { "" }
+            ));
+        }
+    }
+    report.push_str(&format!(
+        "{} of {} files matched, {} failed, {} with embedded data\n",
+        matched,
+        entries.len(),
+        failed,
+        polyglots
+    ));
+    report
+}
+
+/// Prints aggregate statistics over the PNG files under a directory: chunk
+/// type frequency, mean ancillary overhead, files failing validation and
+/// files with suspicious findings, as text, `--json` or `--html`. The whole
+/// report fails once `token` is cancelled.
+pub fn report(vfs: &dyn Vfs, args: &ReportArgs, token: &CancellationToken) -> Result<()> {
+    let report = corpus_report(vfs, &args.dir, token, &mut |_: &Event| {})?;
+    if args.json {
+        println!("{}", report_json(&report, &args.dir));
+    } else if args.html {
+        print!("{}", report_html(&report, &args.dir));
+    } else {
+        print!("{}", report_text(&report));
+    }
+    Ok(())
+}
+
+fn report_text(report: &CorpusReport) -> String {
+    let mut text = format!(
+        "files:               {}\n\
+         failed validation:   {}\n\
+         suspicious:          {}\n\
+         ancillary overhead:  {:.1}%\n",
+        report.files,
+        report.failed.len(),
+        report.suspicious.len(),
+        report.ancillary_overhead * 100.0
+    );
+    text.push_str("chunk types:\n");
+    for (chunk_type, count) in &report.chunk_types {
+        text.push_str(&format!("  {}  {}\n", chunk_type, count));
+    }
+    for (path, reason) in &report.failed {
+        text.push_str(&format!("{}: failed: {}\n", path.display(), reason));
+    }
+    for (path, findings) in &report.suspicious {
+        for finding in findings {
+            text.push_str(&format!("{}: {}\n", path.display(), finding));
+        }
+    }
+    text
+}
+
+/// The `report --json` document.
+/// The `scan --json` document: every file with its error, matching chunks
+/// and embedded formats.
+fn scan_json(entries: &[ScanEntry], dir: &Path) -> Json {
+    let files = entries
+        .iter()
+        .map(|entry| {
+            let (error, matches) = match &entry.outcome {
+                ScanOutcome::Matches(matches) => (Json::Null, matches.as_slice()),
+                ScanOutcome::Failed(e) => (Json::string(e.as_str()), &[][..]),
+            };
+            let matches = matches
+                .iter()
+                .map(|m| {
+                    Json::Object(vec![
+                        ("type", Json::string(m.chunk_type.as_str())),
+                        ("length", Json::Number(m.length as u64)),
+                        ("entropy", Json::Float(m.entropy)),
+                        ("compress_ratio", Json::Float(m.compress_ratio)),
+                    ])
+                })
+                .collect();
+            let embedded = entry
+                .embedded
+                .iter()
+                .map(|e| {
+                    Json::Object(vec![
+                        ("format", Json::string(e.format.name())),
+                        ("offset", Json::Number(e.offset as u64)),
+                        ("trailing", Json::Bool(e.trailing)),
+                    ])
+                })
+                .collect();
+            Json::Object(vec![
+                ("file", Json::string(entry.path.to_string_lossy())),
+                ("error", error),
+                ("matches", Json::Array(matches)),
+                ("embedded", Json::Array(embedded)),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        ("schema_version", Json::Number(schema::VERSION)),
+        ("dir", Json::string(dir.to_string_lossy())),
+        ("files", Json::Array(files)),
+    ])
+}
+
+fn report_json(report: &CorpusReport, dir: &Path) -> Json {
+    let chunk_types = report
+        .chunk_types
+        .iter()
+        .map(|(chunk_type, count)| {
+            Json::Object(vec![
+                ("type", Json::string(chunk_type.as_str())),
+                ("count", Json::Number(*count as u64)),
+            ])
+        })
+        .collect();
+    let failed = report
+        .failed
+        .iter()
+        .map(|(path, reason)| {
+            Json::Object(vec![
+                ("file", Json::string(path.to_string_lossy())),
+                ("reason", Json::string(reason.as_str())),
+            ])
+        })
+        .collect();
+    let suspicious = report
+        .suspicious
+        .iter()
+        .map(|(path, findings)| {
+            Json::Object(vec![
+                ("file", Json::string(path.to_string_lossy())),
+                (
+                    "findings",
+                    Json::Array(findings.iter().map(|f| Json::string(f.as_str())).collect()),
+                ),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        ("schema_version", Json::Number(schema::VERSION)),
+        ("dir", Json::string(dir.to_string_lossy())),
+        ("files", Json::Number(report.files as u64)),
+        ("ancillary_overhead", Json::Float(report.ancillary_overhead)),
+        ("chunk_types", Json::Array(chunk_types)),
+        ("failed", Json::Array(failed)),
+        ("suspicious", Json::Array(suspicious)),
+    ])
+}
+
+/// The `report --html` page: the summary and one table per section.
+fn report_html(report: &CorpusReport, dir: &Path) -> String {
+    let title = html_escape(&format!("PNG corpus report: {}", dir.display()));
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>{0}</title></head>\n\
+         <body>\n<h1>{0}</h1>\n<p>{1} files, {2} failing validation, {3} suspicious, \
+         {4:.1}% ancillary overhead</p>\n",
+        title,
+        report.files,
+        report.failed.len(),
+        report.suspicious.len(),
+        report.ancillary_overhead * 100.0
+    );
+    html.push_str(&html_table(
+        "Chunk types",
+        ["Type", "Count"],
+        report
+            .chunk_types
+            .iter()
+            .map(|(t, n)| [t.clone(), n.to_string()]),
+    ));
+    html.push_str(&html_table(
+        "Failing validation",
+        ["File", "Reason"],
+        report
+            .failed
+            .iter()
+            .map(|(p, r)| [p.display().to_string(), r.clone()]),
+    ));
+    html.push_str(&html_table(
+        "Suspicious",
+        ["File", "Findings"],
+        report
+            .suspicious
+            .iter()
+            .map(|(p, f)| [p.display().to_string(), f.join("; ")]),
+    ));
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn html_table(heading: &str, header: [&str; 2], rows: impl Iterator<Item = [String; 2]>) -> String {
+    let mut table = format!(
+        "<h2>{}</h2>\n<table>\n<tr><th>{}</th><th>{}</th></tr>\n",
+        heading, header[0], header[1]
+    );
+    for row in rows {
+        table.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td></tr>\n",
+            html_escape(&row[0]),
+            html_escape(&row[1])
+        ));
+    }
+    table.push_str("</table>\n");
+    table
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Recomputes every chunk CRC and checks the signature and IEND, printing a
+/// per-chunk report. Fails, so the process exits non-zero, if anything is
+/// wrong, after moving the file into the `--quarantine` directory if given.
+pub fn verify(vfs: &dyn Vfs, args: &VerifyArgs) -> Result<()> {
+    let bytes = vfs
+        .read(&args.file)
+        .map_err(|e| format!("Invalid Png File {} : {}", args.file.display(), e))?;
+    let check = full_check(&bytes);
+    if args.json {
+        println!("{}", verify_json(&check, &args.file));
+    } else {
+        print!("{}", verify_report(&check));
+    }
+    if check.is_ok() {
+        return Ok(());
+    }
+    if let Some(dir) = &args.quarantine {
+        if args.dry_run {
+            eprintln!(
+                "would quarantine {} to {}",
+                args.file.display(),
+                dir.display()
+            );
+        } else {
+            let target = pngme::verify::quarantine(vfs, &args.file, dir)?;
+            eprintln!(
+                "quarantined {} to {}",
+                args.file.display(),
+                target.display()
+            );
+        }
+    }
+    Err(format!(
+        "Invalid Png File {} : Verification Failed",
+        args.file.display()
+    )
+    .into())
+}
+
+/// Prints every problem [`diagnose`] finds in the file, most urgent first,
+/// each with the command that fixes it. Fails if any problem is an error.
+pub fn doctor(vfs: &dyn Vfs, args: &DoctorArgs) -> Result<()> {
+    let bytes = vfs
+        .read(&args.file)
+        .map_err(|e| format!("Invalid Png File {} : {}", args.file.display(), e))?;
+    let findings = diagnose(&bytes);
+    print!("{}", doctor_report(&findings, &args.file));
+    if findings.iter().any(|f| f.severity == Severity::Error) {
+        return Err(format!("Invalid Png File {} : Problems Found", args.file.display()).into());
+    }
+    Ok(())
+}
+
+/// A numbered entry per finding with its severity, followed by the fix.
+fn doctor_report(findings: &[Finding], file: &Path) -> String {
+    if findings.is_empty() {
+        return "no problems found\n".to_string();
+    }
+    let mut report = String::new();
+    for (i, finding) in findings.iter().enumerate() {
+        report.push_str(&format!(
+            "{}. {}: {}\n",
+            i + 1,
+            finding.severity.name(),
+            finding.problem
+        ));
+        match &finding.remedy {
+            Some(remedy) => report.push_str(&format!("   fix: {}\n", remedy_command(remedy, file))),
+            None => report.push_str("   no automatic fix\n"),
+        }
+    }
+    report
+}
+
+/// The command line that applies `remedy` to `file`.
+fn remedy_command(remedy: &Remedy, file: &Path) -> String {
+    let file = file.display();
+    match remedy {
+        Remedy::Repair => format!("pngme repair {}", file),
+        Remedy::RepairLengths => format!("pngme repair {} --fix-lengths", file),
+        Remedy::Salvage => format!("pngme salvage {}", file),
+        Remedy::Strip => format!("pngme strip {}", file),
+        Remedy::Remove(chunk_type) => {
+            format!("pngme remove {} {} --all --force", file, chunk_type)
+        }
+        Remedy::Carve => format!("pngme carve {} --out-dir carved", file),
+    }
+}
+
+/// One line per chunk with its stored CRC and whether it matches, then the
+/// structural result.
+fn verify_report(check: &FullCheck) -> String {
+    let mut report = String::new();
+    for (i, chunk) in check.chunks.iter().enumerate() {
+        let status = if chunk.is_ok() {
+            "ok".to_string()
+        } else {
+            format!("BAD, computed {:08x}", chunk.computed)
+        };
+        report.push_str(&format!(
+            "{:>3}  {}  offset {:<8}  length {:<8}  crc {:08x}  {}\n",
+            i, chunk.chunk_type, chunk.offset, chunk.length, chunk.stored, status
+        ));
+    }
+    let summary = if check.is_ok() {
+        "OK".to_string()
+    } else if !check.framing.is_ok() {
+        format!("FAILED: {}", check.framing)
+    } else {
+        let bad = check.chunks.iter().filter(|c| !c.is_ok()).count();
+        format!("FAILED: {} chunk CRC mismatches", bad)
+    };
+    report.push_str(&summary);
+    report.push('\n');
+    report
+}
+
+/// The `verify --json` document.
+fn verify_json(check: &FullCheck, file: &Path) -> Json {
+    let chunks = check
+        .chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            Json::Object(vec![
+                ("index", Json::Number(i as u64)),
+                ("type", Json::string(chunk.chunk_type.clone())),
+                ("offset", Json::Number(chunk.offset as u64)),
+                ("length", Json::Number(chunk.length as u64)),
+                ("crc", Json::string(format!("{:08x}", chunk.stored))),
+                (
+                    "computed_crc",
+                    Json::string(format!("{:08x}", chunk.computed)),
+                ),
+                ("crc_ok", Json::Bool(chunk.is_ok())),
+            ])
+        })
+        .collect();
+    let problem = if check.framing.is_ok() {
+        Json::Null
+    } else {
+        Json::string(check.framing.to_string())
+    };
+    Json::Object(vec![
+        ("file", Json::string(file.to_string_lossy())),
+        ("ok", Json::Bool(check.is_ok())),
+        ("problem", problem),
+        ("chunks", Json::Array(chunks)),
+    ])
+}
+
+/// Recomputes wrong chunk CRCs, and with `--fix-lengths` wrong length
+/// fields, then prints each fix and saves the result. Nothing is written if
+/// there was nothing to fix.
+pub fn repair(vfs: &dyn Vfs, args: &RepairArgs) -> Result<()> {
+    let bytes = vfs
+        .read(&args.file)
+        .map_err(|e| format!("Invalid Png File {} : {}", args.file.display(), e))?;
+    let opts = RepairOptions {
+        fix_lengths: args.fix_lengths,
+    };
+    let repair = pngme::repair::repair(&bytes, &opts)?;
+    if repair.fixes.is_empty() {
+        println!("nothing to repair");
+        return Ok(());
+    }
+    for fix in &repair.fixes {
+        println!("{}", describe_fix(fix));
+    }
+    let path = destination(vfs, &args.file, args.output.as_deref());
+    if args.dry_run {
+        println!(
+            "{}",
+            would_write(&path, repair.bytes.len(), bytes.len(), args.units)
+        );
+        return Ok(());
+    }
+    vfs.write_atomic(&path, &repair.bytes)?;
+    Ok(())
+}
+
+/// Keeps the chunks of a damaged file whose CRC checks out, up to the first
+/// IEND, puts IHDR first and regenerates IEND, then prints what was dropped
+/// and saves the result.
+pub fn salvage(vfs: &dyn Vfs, args: &SalvageArgs) -> Result<()> {
+    let bytes = vfs
+        .read(&args.file)
+        .map_err(|e| format!("Invalid Png File {} : {}", args.file.display(), e))?;
+    let png = Png::salvage(&bytes)?;
+    for warning in png.warnings() {
+        println!("{}", warning);
+    }
+    let path = destination(vfs, &args.file, args.output.as_deref());
+    if args.dry_run {
+        let size = png.as_bytes().len();
+        println!("{}", would_write(&path, size, bytes.len(), args.units));
+        return Ok(());
+    }
+    png.save_to_file(vfs, &path)?;
+    println!(
+        "salvaged {} chunks to {}",
+        png.chunks().len(),
+        path.display()
+    );
+    Ok(())
+}
+
+fn describe_fix(fix: &Fix) -> String {
+    match fix {
+        Fix::Crc {
+            offset,
+            chunk_type,
+            old,
+            new,
+        } => format!(
+            "offset {} {}: crc {:08x} -> {:08x}",
+            offset, chunk_type, old, new
+        ),
+        Fix::Length {
+            offset,
+            chunk_type,
+            old,
+            new,
+        } => format!(
+            "offset {} {}: length {} -> {}",
+            offset, chunk_type, old, new
+        ),
+    }
+}
+
+/// Prints the decoded image header and a breakdown of the chunks.
+pub fn info(
+    vfs: &dyn Vfs,
+    args: &InfoArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let png = read_png(vfs, &args.file, parse.unwrap_or_default(), token)?;
+    print!("{}", info_report(&png, args.units)?);
+    Ok(())
+}
+
+fn info_report(png: &Png, units: Units) -> Result<String> {
+    let ihdr = png.header()?;
+    let critical = png
+        .chunks()
+        .filter(|c| c.chunk_type().is_critical())
+        .count();
+    let total = png.chunks().len();
+    let compressed: u64 = png
+        .chunks_by_type("IDAT")
+        .iter()
+        .map(|c| c.length() as u64)
+        .sum();
+    let interlace = match ihdr.interlace {
+        0 => "none",
+        1 => "Adam7",
+        _ => "unknown",
+    };
+    let mut types: Vec<(&ChunkType, usize)> = Vec::new();
+    for chunk in png.chunks() {
+        match types
+            .iter_mut()
+            .find(|(t, _)| t.bytes() == chunk.chunk_type().bytes())
+        {
+            Some((_, count)) => *count += 1,
+            None => types.push((chunk.chunk_type(), 1)),
+        }
+    }
+    let mut report = format!(
+        "dimensions:  {}x{}\n\
+         bit depth:   {}\n\
+         color type:  {} ({})\n\
+         interlace:   {}\n\
+         chunks:      {} ({} critical, {} ancillary)\n\
+         pixel data:  {} inflated, {} compressed\n",
+        ihdr.width,
+        ihdr.height,
+        ihdr.bit_depth,
+        ihdr.color_type,
+        ihdr.color_type_name(),
+        interlace,
+        total,
+        critical,
+        total - critical,
+        units::size(ihdr.raw_data_size(), units),
+        units::size(compressed, units),
+    );
+    for (chunk_type, count) in types {
+        report.push_str(&format!(
+            "  {}  {:>3}  {}\n",
+            chunk_type,
+            count,
+            description(chunk_type)
+        ));
+    }
+    Ok(report)
+}
+
+/// Prints every chunk whose type is not a registered public PNG chunk, with
+/// the reasons it stands out. The file is parsed permissively unless
+/// `--parse-mode` says otherwise.
+pub fn list(
+    vfs: &dyn Vfs,
+    args: &ListArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let png = read_png(
+        vfs,
+        &args.file,
+        parse.unwrap_or_else(ParseOptions::permissive),
+        token,
+    )?;
+    let filter = chunk_filter(args.filter.as_deref())?;
+    if args.json {
+        println!(
+            "{}",
+            list_json(&png, &args.file, args.filter.as_deref(), filter.as_ref())
+        );
+    } else {
+        print!("{}", list_report(&png, args.units, filter.as_ref()));
+    }
+    Ok(())
+}
+
+/// A chunk shown by `list`, with its index, file offset and oddities.
+struct Listed<'a> {
+    index: usize,
+    offset: usize,
+    chunk: &'a Chunk,
+    oddities: Vec<&'static str>,
+}
+
+/// The non-standard chunks of `png`, or with a filter the chunks it matches.
+fn listed<'a>(png: &'a Png, filter: Option<&Filter>) -> Vec<Listed<'a>> {
+    png.chunks()
+        .zip(chunk_offsets(png))
+        .enumerate()
+        .map(|(index, (chunk, offset))| Listed {
+            index,
+            offset,
+            chunk,
+            oddities: registry::oddities(chunk.chunk_type()),
+        })
+        .filter(|l| match filter {
+            Some(filter) => filter.matches(l.chunk),
+            None => !l.oddities.is_empty(),
+        })
+        .collect()
+}
+
+/// Lists the non-standard chunks, or with a filter the chunks it matches,
+/// followed by a count line.
+fn list_report(png: &Png, units: Units, filter: Option<&Filter>) -> String {
+    let mut report = String::new();
+    let listed = listed(png, filter);
+    for l in &listed {
+        let line = format!(
+            "{:<3} {}  offset {:<8} {:>8}  {}",
+            l.index,
+            l.chunk.chunk_type(),
+            l.offset,
+            units::size(l.chunk.length() as u64, units),
+            l.oddities.join(", ")
+        );
+        report.push_str(line.trim_end());
+        report.push('\n');
+    }
+    report.push_str(&format!(
+        "{} of {} chunks {}\n",
+        listed.len(),
+        png.chunks().len(),
+        if filter.is_some() {
+            "match"
+        } else {
+            "are non-standard"
+        }
+    ));
+    report
+}
+
+/// The `list --json` document: the listed chunks with their oddities, the
+/// `--where` expression and the total number of chunks.
+fn list_json(png: &Png, file: &Path, expr: Option<&str>, filter: Option<&Filter>) -> Json {
+    let chunks = listed(png, filter)
+        .into_iter()
+        .map(|l| {
+            Json::Object(vec![
+                ("index", Json::Number(l.index as u64)),
+                ("type", Json::string(l.chunk.chunk_type().to_string())),
+                ("offset", Json::Number(l.offset as u64)),
+                ("length", Json::Number(l.chunk.length() as u64)),
+                (
+                    "oddities",
+                    Json::Array(l.oddities.into_iter().map(Json::string).collect()),
+                ),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        ("schema_version", Json::Number(schema::VERSION)),
+        ("file", Json::string(file.to_string_lossy())),
+        ("filter", expr.map_or(Json::Null, Json::string)),
+        ("chunks", Json::Array(chunks)),
+        ("total", Json::Number(png.chunks().len() as u64)),
+    ])
+}
+
+/// Prints the JSON Schema of the `--json` output of one command, or an
+/// object holding the schema of each command by name.
+pub fn schema(args: &SchemaArgs) -> Result<()> {
+    let schema = match args.output {
+        Some(output) => schema::schema(output),
+        None => Json::Object(
+            Output::ALL
+                .iter()
+                .map(|o| (o.name(), schema::schema(*o)))
+                .collect(),
+        ),
+    };
+    println!("{}", schema);
+    Ok(())
+}
+
+/// Parses a `--where` expression, if one was given.
+fn chunk_filter(expr: Option<&str>) -> Result<Option<Filter>> {
+    Ok(expr.map(Filter::from_str).transpose()?)
+}
+
+/// Prints one line per chunk added, removed or modified between two files,
+/// matching chunks by type and occurrence, then the size of both files.
+/// Both are parsed permissively unless `--parse-mode` says otherwise.
+pub fn diff(
+    vfs: &dyn Vfs,
+    args: &DiffArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let opts = parse.unwrap_or_else(ParseOptions::permissive);
+    let old = read_png(vfs, &args.old, opts, token)?;
+    let new = read_png(vfs, &args.new, opts, token)?;
+    let changes = pngme::diff::diff(&old, &new);
+    if changes.is_empty() {
+        println!("no differences");
+        return Ok(());
+    }
+    for change in changes {
+        println!("{}", change);
+    }
+    println!(
+        "size {} -> {}",
+        units::size(old.as_bytes().len() as u64, args.units),
+        units::size(new.as_bytes().len() as u64, args.units)
+    );
+    Ok(())
+}
+
+/// Prints the dates found in the metadata of every file (tIME, "Creation
+/// Time" text, EXIF and XMP), merged and sorted oldest first; values that
+/// are not recognizable dates come last. The files are parsed permissively
+/// unless `--parse-mode` says otherwise.
+pub fn timeline(
+    vfs: &dyn Vfs,
+    args: &TimelineArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let opts = parse.unwrap_or_else(ParseOptions::permissive);
+    let mut pngs = Vec::new();
+    for file in &args.files {
+        pngs.push((
+            file.display().to_string(),
+            read_png(vfs, file, opts, token)?,
+        ));
+    }
+    print!("{}", timeline_report(&pngs));
+    Ok(())
+}
+
+/// One line per entry: the normalized timestamp, or `-`, the file, where
+/// the value came from and the value as stored.
+fn timeline_report(pngs: &[(String, Png)]) -> String {
+    let entries =
+        pngme::timeline::timeline(pngs.iter().map(|(source, png)| (source.as_str(), png)));
+    let mut report = String::new();
+    for entry in entries {
+        report.push_str(&format!(
+            "{:<19}  {}  {}  {}\n",
+            entry.timestamp.as_deref().unwrap_or("-"),
+            entry.source,
+            entry.origin,
+            entry.raw
+        ));
+    }
+    report
+}
+
+/// Writes the data of each chunk, or of those matching `--where`, to
+/// `NN_TYPE.bin` in the output directory, creating it if needed, and
+/// describes them in `manifest.json`. The
+/// file is parsed permissively unless `--parse-mode` says otherwise, so
+/// damaged chunks are extracted too.
+pub fn extract(
+    vfs: &dyn Vfs,
+    args: &ExtractArgs,
+    parse: Option<ParseOptions>,
+    token: &CancellationToken,
+) -> Result<()> {
+    let png = read_png(
+        vfs,
+        &args.file,
+        parse.unwrap_or_else(ParseOptions::permissive),
+        token,
+    )?;
+    let filter = chunk_filter(args.filter.as_deref())?;
+    let names = extract_names(&png);
+    let selected: Vec<usize> = png
+        .chunks()
+        .enumerate()
+        .filter(|(_, chunk)| filter.as_ref().map_or(true, |f| f.matches(chunk)))
+        .map(|(i, _)| i)
+        .collect();
+    if args.dry_run {
+        println!(
+            "would extract {} chunks to {}",
+            selected.len(),
+            args.out_dir.display()
+        );
+        return Ok(());
+    }
+    vfs.create_dir_all(&args.out_dir).map_err(|e| {
+        format!(
+            "Invalid Extract Directory {} : {}",
+            args.out_dir.display(),
+            e
+        )
+    })?;
+    for (_, (chunk, name)) in selected_chunks(&png, &names, &selected) {
+        vfs.write(&args.out_dir.join(name), chunk.data())?;
+    }
+    let manifest = extract_manifest(&png, &args.file, &names, &selected);
+    vfs.write(
+        &args.out_dir.join("manifest.json"),
+        format!("{}\n", manifest).as_bytes(),
+    )?;
+    println!(
+        "extracted {} chunks to {}",
+        selected.len(),
+        args.out_dir.display()
+    );
+    Ok(())
+}
+
+/// Copies every complete PNG stream found in an arbitrary file to
+/// `carved_NNNN_OFFSET.png` in the output directory, with a `report.txt`.
+pub fn carve(vfs: &dyn Vfs, args: &CarveArgs, token: &CancellationToken) -> Result<()> {
+    let bytes = vfs
+        .read(&args.file)
+        .map_err(|e| format!("Invalid Input File {} : {}", args.file.display(), e))?;
+    if args.dry_run {
+        let found = pngme::carve::carve(&bytes, token)?;
+        for range in &found {
+            println!(
+                "{} at offset {}",
+                units::size(range.len() as u64, args.units),
+                range.start
+            );
+        }
+        println!(
+            "would carve {} PNGs to {}",
+            found.len(),
+            args.out_dir.display()
+        );
+        return Ok(());
+    }
+    let paths = pngme::carve::carve_to_dir(vfs, &bytes, &args.out_dir, token)?;
+    println!("carved {} PNGs to {}", paths.len(), args.out_dir.display());
+    Ok(())
+}
+
+/// `NN_TYPE.bin` for each chunk, with the index zero-padded to at least two
+/// digits so the names sort in file order.
+fn extract_names(png: &Png) -> Vec<String> {
+    let width = png
+        .chunks()
+        .len()
+        .saturating_sub(1)
+        .to_string()
+        .len()
+        .max(2);
+    png.chunks()
+        .enumerate()
+        .map(|(i, chunk)| format!("{:0width$}_{}.bin", i, chunk.chunk_type(), width = width))
+        .collect()
+}
+
+/// The chunks at the sorted `selected` indices, with their index and name.
+fn selected_chunks<'a>(
+    png: &'a Png,
+    names: &'a [String],
+    selected: &'a [usize],
+) -> impl Iterator<Item = (usize, (&'a Chunk, &'a String))> {
+    png.chunks()
+        .zip(names)
+        .enumerate()
+        .filter(move |(i, _)| selected.binary_search(i).is_ok())
+}
+
+fn extract_manifest(png: &Png, file: &Path, names: &[String], selected: &[usize]) -> Json {
+    let chunks = selected_chunks(png, names, selected)
+        .map(|(i, (chunk, name))| {
+            Json::Object(vec![
+                ("index", Json::Number(i as u64)),
+                ("type", Json::string(chunk.chunk_type().to_string())),
+                ("length", Json::Number(chunk.length() as u64)),
+                ("crc", Json::string(format!("{:08x}", chunk.crc()))),
+                ("file", Json::string(name.as_str())),
+            ])
+        })
+        .collect();
+    Json::Object(vec![
+        ("file", Json::string(file.to_string_lossy())),
+        ("chunks", Json::Array(chunks)),
+    ])
+}
+
+/// Writes `count` synthetic files per damage pattern to the output
+/// directory as `DAMAGE_NNN.png`, seeding them consecutively from `seed`.
+pub fn bench_corpus(
+    vfs: &dyn Vfs,
+    args: &BenchCorpusArgs,
+    token: &CancellationToken,
+) -> Result<()> {
+    let damages = match args.damage {
+        Some(damage) => vec![damage],
+        None => Damage::ALL.to_vec(),
+    };
+    let total = damages.len() * args.count;
+    if args.dry_run {
+        println!("would write {} files to {}", total, args.out_dir.display());
+        return Ok(());
+    }
+    vfs.create_dir_all(&args.out_dir)?;
+    for damage in damages {
+        for i in 0..args.count {
+            token.check()?;
+            let bytes = corpus::generate(&CorpusOptions {
+                chunk_count: args.chunks,
+                chunk_size: args.chunk_size,
+                damage,
+                seed: args.seed + i as u64,
+            });
+            let name = format!("{}_{:03}.png", damage.name(), i);
+            vfs.write(&args.out_dir.join(name), &bytes)?;
+        }
+    }
+    println!("wrote {} files to {}", total, args.out_dir.display());
+    Ok(())
+}
+
+/// Reads and parses a file with `opts`: the global `--parse-mode` if given,
+/// otherwise the command's own default. Fails once `token` is cancelled.
+fn read_png(
+    vfs: &dyn Vfs,
+    path: &Path,
+    opts: ParseOptions,
+    token: &CancellationToken,
+) -> Result<Png> {
+    Ok(Png::try_from_cancellable(
+        &read_bytes(vfs, path)?,
+        &opts,
+        token,
+    )?)
+}
+
+fn read_bytes(vfs: &dyn Vfs, path: &Path) -> Result<Vec<u8>> {
+    Ok(vfs
+        .read(path)
+        .map_err(|e| format!("Invalid Png File {} : {}", path.display(), e))?)
+}
+
+/// File offset of each chunk of `png`, as laid out by `Png::as_bytes`.
+fn chunk_offsets(png: &Png) -> Vec<usize> {
+    png.chunks()
+        .scan(Png::STANDARD_HEADER.len(), |pos, chunk| {
+            let offset = *pos;
+            *pos += chunk.length() as usize + 12;
+            Some(offset)
+        })
+        .collect()
+}
+
+/// Whether each chunk's stored CRC matched, from the parse warnings.
+fn crc_status(png: &Png) -> Vec<bool> {
+    chunk_offsets(png)
+        .into_iter()
+        .map(|offset| {
+            !png.warnings()
+                .iter()
+                .any(|w| matches!(w, Warning::BadCrc { .. }) && w.offset() == offset)
+        })
+        .collect()
+}
+
+/// Renders the chunks of `png` as an aligned table with a header row: index,
+/// type, length, CRC (marked `!` if wrong), property flags, the registry
+/// description and a data preview. If `decode` is set the preview is decoded
+/// and followed by the provenance recorded for the chunk.
+fn chunk_table(png: &Png, decode: bool) -> String {
+    let previews = previews(png, decode);
+    let crc_ok = crc_status(png);
+    let rows: Vec<[String; 7]> = png
+        .chunks()
+        .zip(previews)
+        .enumerate()
+        .map(|(i, (chunk, preview))| {
+            let mut preview = preview.unwrap_or_else(|| raw_preview(chunk.data()));
+            if let Some(p) = provenance::lookup(png, chunk).filter(|_| decode) {
+                preview = format!("{} {}", preview, provenance_note(&p))
+                    .trim_start()
+                    .to_string();
+            }
+            [
+                i.to_string(),
+                chunk.chunk_type().to_string(),
+                chunk.length().to_string(),
+                format!("{:08x}{}", chunk.crc(), if crc_ok[i] { "" } else { "!" }),
+                flags(chunk.chunk_type()),
+                description(chunk.chunk_type()).to_string(),
+                preview,
+            ]
+        })
+        .collect();
+    let header = [
+        "#",
+        "TYPE",
+        "LENGTH",
+        "CRC",
+        "FLAGS",
+        "DESCRIPTION",
+        "PREVIEW",
+    ]
+    .map(str::to_string);
+    let mut widths = header.clone().map(|h| h.len());
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut table = String::new();
+    for row in std::iter::once(&header).chain(&rows) {
+        let line = format!(
+            "{:>w0$}  {:<w1$}  {:>w2$}  {:<w3$}  {:<w4$}  {:<w5$}  {}",
+            row[0],
+            row[1],
+            row[2],
+            row[3],
+            row[4],
+            row[5],
+            row[6],
+            w0 = widths[0],
+            w1 = widths[1],
+            w2 = widths[2],
+            w3 = widths[3],
+            w4 = widths[4],
+            w5 = widths[5],
+        );
+        table.push_str(line.trim_end());
+        table.push('\n');
+    }
+    table
+}
+
+/// The `print --json` document: every chunk with its offset, CRC status,
+/// properties, description and, if `decode` is set, decoded preview and
+/// provenance, followed by the parse warnings.
+fn print_json(png: &Png, file: &Path, decode: bool) -> Json {
+    let previews = previews(png, decode);
+    let chunks = png
+        .chunks()
+        .zip(chunk_offsets(png))
+        .zip(crc_status(png))
+        .zip(previews)
+        .enumerate()
+        .map(|(i, (((chunk, offset), crc_ok), preview))| {
+            let chunk_type = chunk.chunk_type();
+            Json::Object(vec![
+                ("index", Json::Number(i as u64)),
+                ("type", Json::string(chunk_type.to_string())),
+                ("offset", Json::Number(offset as u64)),
+                ("length", Json::Number(chunk.length() as u64)),
+                ("crc", Json::string(format!("{:08x}", chunk.crc()))),
+                ("crc_ok", Json::Bool(crc_ok)),
+                ("critical", Json::Bool(chunk_type.is_critical())),
+                ("public", Json::Bool(chunk_type.is_public())),
+                ("safe_to_copy", Json::Bool(chunk_type.is_safe_to_copy())),
+                (
+                    "description",
+                    registry::describe(&chunk_type.to_string()).map_or(Json::Null, Json::string),
+                ),
+                ("preview", preview.map_or(Json::Null, Json::String)),
+                (
+                    "provenance",
+                    provenance::lookup(png, chunk)
+                        .filter(|_| decode)
+                        .map_or(Json::Null, |p| provenance_json(&p)),
+                ),
+            ])
+        })
+        .collect();
+    let warnings = png
+        .warnings()
+        .iter()
+        .map(|w| Json::string(w.to_string()))
+        .collect();
+    Json::Object(vec![
+        ("schema_version", Json::Number(schema::VERSION)),
+        ("file", Json::string(file.to_string_lossy())),
+        ("chunks", Json::Array(chunks)),
+        ("warnings", Json::Array(warnings)),
+    ])
+}
+
+/// `[added by <tool> (<operator>) at <timestamp>: <reason>]`, with the
+/// operator left out if empty and the timestamp in seconds since the epoch.
+fn provenance_note(p: &Provenance) -> String {
+    let operator = if p.operator.is_empty() {
+        String::new()
+    } else {
+        format!(" ({})", p.operator)
+    };
+    format!(
+        "[added by {}{} at {}: {}]",
+        p.tool_version, operator, p.timestamp, p.reason
+    )
+}
+
+fn provenance_json(p: &Provenance) -> Json {
+    Json::Object(vec![
+        ("tool_version", Json::string(&p.tool_version)),
+        ("timestamp", Json::Number(p.timestamp)),
+        ("operator", Json::string(&p.operator)),
+        ("reason", Json::string(&p.reason)),
+    ])
+}
+
+/// The registry description of `chunk_type`, or `unknown chunk`.
+fn description(chunk_type: &ChunkType) -> &'static str {
+    registry::describe(&chunk_type.to_string()).unwrap_or("unknown chunk")
+}
+
+/// `A`, `P`, `R` and `S` for ancillary, private, reserved and safe to copy,
+/// with `-` for each property the chunk type lacks.
+fn flags(chunk_type: &ChunkType) -> String {
+    let properties = chunk_type.properties();
+    [
+        (ChunkProperties::ANCILLARY, 'A'),
+        (ChunkProperties::PRIVATE, 'P'),
+        (ChunkProperties::RESERVED, 'R'),
+        (ChunkProperties::SAFE_TO_COPY, 'S'),
+    ]
+    .iter()
+    .map(|(flag, c)| if properties.contains(*flag) { *c } else { '-' })
+    .collect()
+}
+
+/// Decoded previews of every chunk if `decode` is set, otherwise none.
+fn previews(png: &Png, decode: bool) -> Vec<Option<String>> {
+    if decode {
+        preview::decode_all(png)
+    } else {
+        vec![None; png.chunks().len()]
+    }
+}
+
+/// Fallback preview for chunks `preview::decode` does not know: the data as
+/// text if it is printable ASCII, otherwise its first bytes in hex.
+fn raw_preview(data: &[u8]) -> String {
+    const MAX: usize = 16;
+    let shown = &data[..data.len().min(MAX)];
+    let more = if data.len() > MAX { "..." } else { "" };
+    if data.is_empty() {
+        String::new()
+    } else if data.iter().all(|b| b.is_ascii_graphic() || *b == b' ') {
+        format!("\"{}{}\"", String::from_utf8_lossy(shown), more)
+    } else {
+        let hex: Vec<String> = shown.iter().map(|b| format!("{:02x}", b)).collect();
+        format!("{}{}", hex.join(" "), more)
+    }
+}
+
+/// The `decode --json` document: every chunk of type `chunk_type` with its
+/// index, offset, data in base64 and, if it is valid UTF-8, as text.
+fn decode_json(png: &Png, file: &Path, chunk_type: &str) -> Result<Json> {
+    ChunkType::from_str(chunk_type)?;
+    let chunks: Vec<Json> = png
+        .chunks()
+        .zip(chunk_offsets(png))
+        .enumerate()
+        .filter(|(_, (chunk, _))| chunk.chunk_type().to_string() == chunk_type)
+        .map(|(i, (chunk, offset))| {
+            Json::Object(vec![
+                ("index", Json::Number(i as u64)),
+                ("offset", Json::Number(offset as u64)),
+                ("length", Json::Number(chunk.length() as u64)),
+                ("base64", Json::String(base64(chunk.data()))),
+                (
+                    "text",
+                    chunk.data_as_string().map_or(Json::Null, Json::String),
+                ),
+            ])
+        })
+        .collect();
+    if chunks.is_empty() {
+        return Err(format!("Invalid Chunk Type {} : Not Found", chunk_type).into());
+    }
+    Ok(Json::Object(vec![
+        ("file", Json::string(file.to_string_lossy())),
+        ("chunk_type", Json::string(chunk_type)),
+        ("chunks", Json::Array(chunks)),
+    ]))
+}
+
+/// The data of every chunk of type `chunk_type` as text, in file order.
+/// Fails if there is no such chunk. Data that is not UTF-8 is shown as its
+/// size and base64 instead.
+fn messages(png: &Png, chunk_type: &str) -> Result<Vec<String>> {
+    Ok(matching_chunks(png, chunk_type)?
+        .iter()
+        .map(|chunk| {
+            chunk.data_as_string().unwrap_or_else(|_| {
+                format!(
+                    "<{} bytes of binary data, base64> {}",
+                    chunk.length(),
+                    base64(chunk.data())
+                )
+            })
+        })
+        .collect())
+}
+
+/// The data of every chunk of the type, concatenated in file order.
+fn payload(png: &Png, chunk_type: &str) -> Result<Vec<u8>> {
+    Ok(matching_chunks(png, chunk_type)?
+        .iter()
+        .flat_map(|chunk| chunk.data())
+        .copied()
+        .collect())
+}
+
+fn matching_chunks<'a>(png: &'a Png, chunk_type: &str) -> Result<Vec<&'a Chunk>> {
+    ChunkType::from_str(chunk_type)?;
+    let chunks = png.chunks_by_type(chunk_type);
+    if chunks.is_empty() {
+        return Err(format!("Invalid Chunk Type {} : Not Found", chunk_type).into());
+    }
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pngme::ancillary::Time;
+    use pngme::cancel::Cancelled;
+    use pngme::polyglot::{Embedded, EmbeddedFormat};
+    use pngme::scan::ChunkMatch;
+    use pngme::text::Text;
+    use pngme::vfs::{MemFs, StdFs};
+    use std::fs;
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pngme_cmd_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    fn write_png(path: &Path) {
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        fs::write(path, png.as_bytes()).unwrap();
+    }
+
+    fn types(path: &Path) -> Vec<String> {
+        Png::from_file(&StdFs, path)
+            .unwrap()
+            .chunks()
+            .map(|c| c.chunk_type().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_encode_in_place() {
+        let dir = temp_dir("encode");
+        let file = dir.join("in.png");
+        write_png(&file);
+        encode(
+            &StdFs,
+            &EncodeArgs {
+                file: file.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "ruSt", "IEND"]);
+        let png = Png::from_file(&StdFs, &file).unwrap();
+        assert_eq!(&png.chunk_by_type("ruSt").unwrap().data(), b"hello");
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_encode_input_file() {
+        let dir = temp_dir("encode_input");
+        let file = dir.join("in.png");
+        let input = dir.join("secret.bin");
+        write_png(&file);
+        fs::write(&input, [0xff, 0x00, 0xc3, 0x28]).unwrap();
+        let mut args = EncodeArgs {
+            file: file.clone(),
+            chunk_type: "ruSt".to_string(),
+            payload: Payload::File(input),
+            itxt: None,
+            provenance: None,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        encode(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+
+        let png = Png::from_file(&StdFs, &file).unwrap();
+        assert_eq!(
+            png.chunk_by_type("ruSt").unwrap().data(),
+            &[0xff, 0x00, 0xc3, 0x28]
+        );
+        args.payload = Payload::File(dir.join("missing.bin"));
+        assert!(encode(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_encode_itxt() {
+        let vfs = MemFs::new();
+        let file = PathBuf::from("in.png");
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        vfs.write(&file, &png.as_bytes()).unwrap();
+        let mut args = EncodeArgs {
+            file: file.clone(),
+            chunk_type: "iTXt".to_string(),
+            payload: Payload::Message("Grüße".to_string()),
+            itxt: Some(ITxtArgs {
+                keyword: "Title".to_string(),
+                language_tag: "de-DE".to_string(),
+                translated_keyword: "Titel".to_string(),
+            }),
+            provenance: None,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        encode(&vfs, &args, None, &CancellationToken::new()).unwrap();
+
+        let png = Png::from_file(&vfs, &file).unwrap();
+        let itxt = ITxt::try_from(png.chunk_by_type("iTXt").unwrap()).unwrap();
+        assert_eq!(itxt.keyword(), "Title");
+        assert_eq!(itxt.language_tag(), "de-DE");
+        assert_eq!(itxt.translated_keyword(), "Titel");
+        assert_eq!(itxt.text().unwrap(), "Grüße");
+
+        args.chunk_type = "ruSt".to_string();
+        assert!(encode(&vfs, &args, None, &CancellationToken::new()).is_err());
+        args.chunk_type = "iTXt".to_string();
+        args.itxt.as_mut().unwrap().translated_keyword = "Ti\0tel".to_string();
+        assert!(encode(&vfs, &args, None, &CancellationToken::new()).is_err());
+    }
+
+    #[test]
+    fn test_commands_use_vfs() {
+        let vfs = MemFs::new();
+        let file = PathBuf::from("in.png");
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        vfs.write(&file, &png.as_bytes()).unwrap();
+        encode(
+            &vfs,
+            &EncodeArgs {
+                file: file.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        let png = Png::from_file(&vfs, &file).unwrap();
+        assert_eq!(png.chunk_by_type("ruSt").unwrap().data(), b"hello");
+        assert_eq!(vfs.files(), vec![file]);
+    }
+
+    #[test]
+    fn test_encode_to_output() {
+        let dir = temp_dir("encode_output");
+        let file = dir.join("in.png");
+        let output = dir.join("out.png");
+        write_png(&file);
+        encode(
+            &StdFs,
+            &EncodeArgs {
+                file: file.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: Some(output.clone()),
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        assert_eq!(types(&output), vec!["IHDR", "IDAT", "ruSt", "IEND"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        let dir = temp_dir("encode_errors");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let mut args = EncodeArgs {
+            file: file.clone(),
+            chunk_type: "rust".to_string(),
+            payload: Payload::Message("hello".to_string()),
+            itxt: None,
+            provenance: None,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        assert!(encode(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        args.chunk_type = "ru5t".to_string();
+        assert!(encode(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        args.chunk_type = "ruSt".to_string();
+        args.file = dir.join("missing.png");
+        assert!(encode(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove() {
+        let dir = temp_dir("remove");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let encode_args = EncodeArgs {
+            file: file.clone(),
+            chunk_type: "ruSt".to_string(),
+            payload: Payload::Message("hello".to_string()),
+            itxt: None,
+            provenance: None,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        encode(&StdFs, &encode_args, None, &CancellationToken::new()).unwrap();
+        encode(&StdFs, &encode_args, None, &CancellationToken::new()).unwrap();
+        encode(&StdFs, &encode_args, None, &CancellationToken::new()).unwrap();
+
+        let mut args = RemoveArgs {
+            file: file.clone(),
+            chunk_type: Some("ruSt".to_string()),
+            all: false,
+            index: None,
+            filter: None,
+            force: false,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        remove(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "ruSt", "ruSt", "IEND"]);
+        args.index = Some(1);
+        remove(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "ruSt", "IEND"]);
+        assert!(remove(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        args.index = None;
+        args.all = true;
+        remove(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        assert!(remove(&StdFs, &args, None, &CancellationToken::new()).is_err());
+
+        args.chunk_type = Some("IDAT".to_string());
+        assert!(remove(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        args.force = true;
+        remove(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IEND"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_remove_where() {
+        let dir = temp_dir("remove_where");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let encode_args = EncodeArgs {
+            file: file.clone(),
+            chunk_type: "ruSt".to_string(),
+            payload: Payload::Message("hidden".to_string()),
+            itxt: None,
+            provenance: None,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        encode(&StdFs, &encode_args, None, &CancellationToken::new()).unwrap();
+
+        let mut args = RemoveArgs {
+            file: file.clone(),
+            chunk_type: None,
+            all: false,
+            index: None,
+            filter: Some("len > 2".to_string()),
+            force: false,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        let err = remove(&StdFs, &args, None, &CancellationToken::new())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Matches Critical Chunk IHDR"));
+        args.filter = Some("!critical && len > 2".to_string());
+        remove(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        let err = remove(&StdFs, &args, None, &CancellationToken::new())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("No Matching Chunk"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_cancelled() {
+        let dir = temp_dir("cancelled");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let before = fs::read(&file).unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let args = StripArgs {
+            file: file.clone(),
+            keep: Vec::new(),
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        let err = strip(&StdFs, &args, None, &token).err().unwrap();
+        assert_eq!(err.to_string(), Cancelled.to_string());
+        assert_eq!(fs::read(&file).unwrap(), before);
+        let args = CarveArgs {
+            file,
+            out_dir: dir.join("carved"),
+            dry_run: false,
+            units: Units::Human,
+        };
+        let err = carve(&StdFs, &args, &token).err().unwrap();
+        assert_eq!(err.to_string(), Cancelled.to_string());
+        assert!(!dir.join("carved").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_strip() {
+        let dir = temp_dir("strip");
+        let file = dir.join("in.png");
+        let output = dir.join("out.png");
+        write_png(&file);
+        for chunk_type in ["tEXt", "ruSt", "iCCP"] {
+            encode(
+                &StdFs,
+                &EncodeArgs {
+                    file: file.clone(),
+                    chunk_type: chunk_type.to_string(),
+                    payload: Payload::Message("hello".to_string()),
+                    itxt: None,
+                    provenance: None,
+                    output: None,
+                    dry_run: false,
+                    units: Units::Human,
+                },
+                None,
+                &CancellationToken::new(),
+            )
+            .unwrap();
+        }
+
+        let mut args = StripArgs {
+            file: file.clone(),
+            keep: vec!["tEXt".to_string(), "iCCP".to_string()],
+            output: Some(output.clone()),
+            dry_run: false,
+            units: Units::Human,
+        };
+        strip(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&output), vec!["IHDR", "IDAT", "tEXt", "iCCP", "IEND"]);
+        assert_eq!(types(&file).len(), 6);
+
+        args.keep = vec!["IDAT".to_string()];
+        assert!(strip(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        args.keep = vec!["bad".to_string()];
+        assert!(strip(&StdFs, &args, None, &CancellationToken::new()).is_err());
+
+        args.keep.clear();
+        args.output = None;
+        strip(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract() {
+        let dir = temp_dir("extract");
+        let file = dir.join("in.png");
+        let out_dir = dir.join("out");
+        write_png(&file);
+        extract(
+            &StdFs,
+            &ExtractArgs {
+                file: file.clone(),
+                out_dir: out_dir.clone(),
+                filter: None,
+                dry_run: false,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(fs::read(out_dir.join("00_IHDR.bin")).unwrap(), vec![0; 13]);
+        assert_eq!(
+            fs::read(out_dir.join("01_IDAT.bin")).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert!(fs::read(out_dir.join("02_IEND.bin")).unwrap().is_empty());
+        let manifest = fs::read_to_string(out_dir.join("manifest.json")).unwrap();
+        assert!(manifest.contains(r#"{"index":1,"type":"IDAT","length":3,"crc":"#));
+        assert!(manifest.contains(r#""file":"02_IEND.bin"}"#));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_where() {
+        let dir = temp_dir("extract_where");
+        let file = dir.join("in.png");
+        let out_dir = dir.join("out");
+        write_png(&file);
+        extract(
+            &StdFs,
+            &ExtractArgs {
+                file: file.clone(),
+                out_dir: out_dir.clone(),
+                filter: Some("type == \"IDAT\"".to_string()),
+                dry_run: false,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs::read(out_dir.join("01_IDAT.bin")).unwrap(),
+            vec![1, 2, 3]
+        );
+        assert!(!out_dir.join("00_IHDR.bin").exists());
+        let manifest = fs::read_to_string(out_dir.join("manifest.json")).unwrap();
+        assert!(manifest.contains(r#""chunks":[{"index":1,"type":"IDAT""#));
+        assert!(!manifest.contains("IHDR"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_carve() {
+        let dir = temp_dir("carve");
+        let file = dir.join("dump.bin");
+        let out_dir = dir.join("out");
+        write_png(&file);
+        let png = fs::read(&file).unwrap();
+        let mut dump = b"header".to_vec();
+        dump.extend(&png);
+        dump.extend(b"between");
+        dump.extend(&png);
+        fs::write(&file, &dump).unwrap();
+
+        let mut args = CarveArgs {
+            file: file.clone(),
+            out_dir: out_dir.clone(),
+            dry_run: true,
+            units: Units::Human,
+        };
+        carve(&StdFs, &args, &CancellationToken::new()).unwrap();
+        assert!(!out_dir.exists());
+        args.dry_run = false;
+        carve(&StdFs, &args, &CancellationToken::new()).unwrap();
+        assert_eq!(
+            fs::read(out_dir.join("carved_0000_00000006.png")).unwrap(),
+            png
+        );
+        assert_eq!(fs::read_dir(&out_dir).unwrap().count(), 3);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_bench_corpus() {
+        let vfs = MemFs::new();
+        let mut args = BenchCorpusArgs {
+            out_dir: PathBuf::from("corpus"),
+            count: 2,
+            chunks: 1,
+            chunk_size: 16,
+            damage: None,
+            seed: 7,
+            dry_run: false,
+        };
+        bench_corpus(&vfs, &args, &CancellationToken::new()).unwrap();
+        assert_eq!(vfs.files().len(), 2 * Damage::ALL.len());
+        let bytes = vfs.read(Path::new("corpus/none_001.png")).unwrap();
+        assert!(Png::try_from(bytes.as_ref()).is_ok());
+
+        let vfs = MemFs::new();
+        args.damage = Some(Damage::BadCrc);
+        bench_corpus(&vfs, &args, &CancellationToken::new()).unwrap();
+        assert_eq!(
+            vfs.files(),
+            vec![
+                PathBuf::from("corpus/bad-crc_000.png"),
+                PathBuf::from("corpus/bad-crc_001.png")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_resplit() {
+        let dir = temp_dir("resplit");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let mut args = ResplitArgs {
+            file: file.clone(),
+            idat_size: Some(1),
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        resplit(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IDAT", "IDAT", "IEND"]);
+        args.idat_size = None;
+        resplit(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        let png = Png::from_file(&StdFs, &file).unwrap();
+        assert_eq!(png.chunk_by_type("IDAT").unwrap().data(), &[1, 2, 3]);
+        args.idat_size = Some(0);
+        assert!(resplit(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_extract_names() {
+        let chunks = (0..11)
+            .map(|_| Chunk::new(ChunkType::from_str("ruSt").unwrap(), Vec::new()))
+            .collect();
+        let names = extract_names(&Png::from_chunks(chunks));
+        assert_eq!(names[0], "00_ruSt.bin");
+        assert_eq!(names[10], "10_ruSt.bin");
+        let chunks = (0..101)
+            .map(|_| Chunk::new(ChunkType::from_str("ruSt").unwrap(), Vec::new()))
+            .collect();
+        assert_eq!(extract_names(&Png::from_chunks(chunks))[7], "007_ruSt.bin");
+    }
+
+    #[test]
+    fn test_inject() {
+        let dir = temp_dir("inject");
+        let file = dir.join("in.png");
+        let data = dir.join("payload.bin");
+        write_png(&file);
+        fs::write(&data, [0, 159, 146, 150, 255]).unwrap();
+
+        let mut args = InjectArgs {
+            file: file.clone(),
+            chunk_type: "ruSt".to_string(),
+            data: data.clone(),
+            position: None,
+            provenance: None,
+            output: None,
+            dry_run: false,
+            units: Units::Human,
+        };
+        inject(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        args.position = Some(1);
+        args.chunk_type = "biNy".to_string();
+        inject(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(types(&file), vec!["IHDR", "biNy", "IDAT", "ruSt", "IEND"]);
+        let png = Png::from_file(&StdFs, &file).unwrap();
+        assert_eq!(
+            png.chunk_by_type("ruSt").unwrap().data(),
+            &[0, 159, 146, 150, 255]
+        );
+
+        args.chunk_type = "biNz".to_string();
+        args.provenance = Some(ProvenanceArgs {
+            operator: "examiner".to_string(),
+            reason: "test marker".to_string(),
+        });
+        inject(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        let png = Png::from_file(&StdFs, &file).unwrap();
+        let chunk = png.chunk_by_type("biNz").unwrap();
+        let provenance = provenance::lookup(&png, chunk).unwrap();
+        assert_eq!(provenance.operator, "examiner");
+        assert_eq!(provenance.reason, "test marker");
+        assert!(provenance::lookup(&png, png.chunk_by_type("ruSt").unwrap()).is_none());
+        let table = chunk_table(&png, true);
+        assert!(table.contains("[added by pngme "));
+        assert!(table.contains(" (examiner) at "));
+        assert!(table.contains(": test marker]"));
+        assert!(!chunk_table(&png, false).contains("test marker"));
+        let json = print_json(&png, &file, true);
+        assert!(schema::conforms(&json, &schema::schema(Output::Print)));
+        let json = json.to_string();
+        assert!(json.contains(r#""operator":"examiner","reason":"test marker"}"#));
+        args.provenance = None;
+
+        args.position = Some(0);
+        assert!(inject(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        args.position = None;
+        args.data = dir.join("missing.bin");
+        assert!(inject(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run() {
+        let dir = temp_dir("dry_run");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let before = fs::read(&file).unwrap();
+        encode(
+            &StdFs,
+            &EncodeArgs {
+                file: file.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: None,
+                dry_run: true,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        strip(
+            &StdFs,
+            &StripArgs {
+                file: file.clone(),
+                keep: Vec::new(),
+                output: Some(dir.join("out.png")),
+                dry_run: true,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        extract(
+            &StdFs,
+            &ExtractArgs {
+                file: file.clone(),
+                out_dir: dir.join("out"),
+                filter: None,
+                dry_run: true,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert_eq!(fs::read(&file).unwrap(), before);
+        assert!(!dir.join("out.png").exists());
+        assert!(!dir.join("out").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_dry_run_report() {
+        let original = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let mut png = original.clone();
+        png.insert_before_end(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"hi".to_vec(),
+        ));
+        let report = dry_run_report(&original, &png, Path::new("a.png"), Units::Human);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("+ ruSt#0 crc "));
+        assert_eq!(lines[1], "would write 59 B to a.png (input is 45 B)");
+        assert_eq!(
+            would_write(Path::new("a.png"), 4803, 45, Units::Human),
+            "would write 4.7 KiB to a.png (input is 45 B)"
+        );
+        assert_eq!(
+            would_write(Path::new("a.png"), 4803, 45, Units::Exact),
+            "would write 4803 B to a.png (input is 45 B)"
+        );
+    }
+
+    #[test]
+    fn test_remove_to_output() {
+        let dir = temp_dir("remove_output");
+        let file = dir.join("in.png");
+        let output = dir.join("out.png");
+        write_png(&file);
+        remove(
+            &StdFs,
+            &RemoveArgs {
+                file: file.clone(),
+                chunk_type: Some("IDAT".to_string()),
+                all: false,
+                index: None,
+                filter: None,
+                force: true,
+                output: Some(output.clone()),
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        assert_eq!(types(&output), vec!["IHDR", "IEND"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_destination() {
+        let vfs = MemFs::new();
+        let file = PathBuf::from("dir/in.png");
+        vfs.write(&file, b"png").unwrap();
+        assert_eq!(destination(&vfs, &file, None), file);
+        let other = PathBuf::from("dir/out.png");
+        assert_eq!(destination(&vfs, &file, Some(&other)), other);
+        let same = PathBuf::from("./dir/../dir/in.png");
+        assert_eq!(destination(&vfs, &file, Some(&same)), file);
+        assert!(same_file(&vfs, &file, &same));
+        assert!(!same_file(&vfs, &file, &other));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_destination_symlink() {
+        let dir = temp_dir("destination");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let resolved = fs::canonicalize(&file).unwrap();
+        let link = dir.join("link.png");
+        std::os::unix::fs::symlink(&file, &link).unwrap();
+        assert_eq!(destination(&StdFs, &file, Some(&link)), resolved);
+        encode(
+            &StdFs,
+            &EncodeArgs {
+                file: file.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: Some(link.clone()),
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "ruSt", "IEND"]);
+
+        assert_eq!(destination(&StdFs, &link, None), resolved);
+        encode(
+            &StdFs,
+            &EncodeArgs {
+                file: link.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("again".to_string()),
+                itxt: None,
+                provenance: None,
+                output: None,
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "ruSt", "ruSt", "IEND"]);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_chunk_table() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(
+                ChunkType::from_str("IHDR").unwrap(),
+                vec![0, 0, 0, 2, 0, 0, 0, 1, 8, 6, 0, 0, 0],
+            ),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hello".to_vec()),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xff; 20]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let table = chunk_table(&png, true);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert_eq!(
+            lines[0],
+            "#  TYPE  LENGTH  CRC       FLAGS  DESCRIPTION    PREVIEW"
+        );
+        assert!(lines[1].starts_with("0  IHDR      13  "));
+        assert!(
+            lines[1].ends_with("----   image header   2x1, bit depth 8, color type 6, interlace 0")
+        );
+        assert!(lines[2].ends_with("AP-S   unknown chunk  \"hello\""));
+        assert!(lines[3].ends_with(&format!("{}...", ["ff"; 16].join(" "))));
+        assert!(lines[4].starts_with("3  IEND       0  ae426082  ----   image trailer"));
+
+        let table = chunk_table(&png, false);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[1].ends_with("image header   00 00 00 02 00 00 00 01 08 06 00 00 00"));
+        assert!(lines[2].ends_with("unknown chunk  \"hello\""));
+        assert!(lines[4].ends_with("image trailer"));
+    }
+
+    #[test]
+    fn test_parse_mode_overrides_default() {
+        let dir = temp_dir("parse_mode");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let mut bytes = fs::read(&file).unwrap();
+        bytes[32] ^= 1;
+        fs::write(&file, &bytes).unwrap();
+        let args = ListArgs {
+            file: file.clone(),
+            filter: None,
+            units: Units::Human,
+            json: false,
+        };
+        assert!(list(&StdFs, &args, None, &CancellationToken::new()).is_ok());
+        assert!(list(
+            &StdFs,
+            &args,
+            Some(ParseOptions::default()),
+            &CancellationToken::new()
+        )
+        .is_err());
+        let args = InfoArgs {
+            file,
+            units: Units::Human,
+        };
+        let err = info(&StdFs, &args, None, &CancellationToken::new())
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Wrong CRC"));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_print_head() {
+        let dir = temp_dir("print_head");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let bytes = fs::read(&file).unwrap();
+        fs::write(&file, &bytes[..40]).unwrap();
+        let mut args = PrintArgs {
+            file: file.clone(),
+            json: false,
+            head: None,
+            decode: false,
+        };
+        assert!(print(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        args.head = Some(1);
+        print(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_chunk_table_marks_bad_crc() {
+        let mut bytes = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes();
+        bytes[32] ^= 1;
+        let png = Png::try_from_with(&bytes, &ParseOptions::permissive()).unwrap();
+        let table = chunk_table(&png, false);
+        assert!(table.lines().nth(1).unwrap().contains("! "));
+        assert!(!table.lines().nth(2).unwrap().contains('!'));
+        assert_eq!(crc_status(&png), vec![false, true]);
+    }
+
+    #[test]
+    fn test_print_json() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let json = print_json(&png, Path::new("a.png"), true).to_string();
+        assert!(json.starts_with(
+            r#"{"schema_version":1,"file":"a.png","chunks":[{"index":0,"type":"IHDR","offset":8,"#
+        ));
+        assert!(json.contains(r#"{"index":1,"type":"ruSt","offset":33,"length":2,"#));
+        assert!(json
+            .contains(r#""public":false,"safe_to_copy":true,"description":null,"preview":null,"#));
+        assert!(json.contains(r#""description":"image header","#));
+        assert!(json.ends_with(r#""preview":"end of image","provenance":null}],"warnings":[]}"#));
+
+        for decode in [false, true] {
+            assert!(schema::conforms(
+                &print_json(&png, Path::new("a.png"), decode),
+                &schema::schema(Output::Print)
+            ));
+        }
+        let json = print_json(&png, Path::new("a.png"), false).to_string();
+        assert!(json.ends_with(r#""preview":null,"provenance":null}],"warnings":[]}"#));
+        assert!(!json.contains("end of image"));
+    }
+
+    #[test]
+    fn test_decode_json() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec()),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![0xff]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let json = decode_json(&png, Path::new("a.png"), "ruSt").unwrap();
+        assert_eq!(
+            json.to_string(),
+            concat!(
+                r#"{"file":"a.png","chunk_type":"ruSt","chunks":["#,
+                r#"{"index":1,"offset":33,"length":2,"base64":"aGk=","text":"hi"},"#,
+                r#"{"index":2,"offset":47,"length":1,"base64":"/w==","text":null}]}"#
+            )
+        );
+        assert!(decode_json(&png, Path::new("a.png"), "abCd").is_err());
+    }
+
+    #[test]
+    fn test_scan_report() {
+        let entries = vec![
+            ScanEntry {
+                path: PathBuf::from("a.png"),
+                outcome: ScanOutcome::Matches(vec![
+                    ChunkMatch {
+                        chunk_type: "ruSt".to_string(),
+                        length: 4803,
+                        entropy: 7.98,
+                        compress_ratio: 0.997,
+                    },
+                    ChunkMatch {
+                        chunk_type: "ruSt".to_string(),
+                        length: 2,
+                        entropy: 0.0,
+                        compress_ratio: 0.0,
+                    },
+                ]),
+                embedded: Vec::new(),
+            },
+            ScanEntry {
+                path: PathBuf::from("b.png"),
+                outcome: ScanOutcome::Matches(Vec::new()),
+                embedded: Vec::new(),
+            },
+            ScanEntry {
+                path: PathBuf::from("c.png"),
+                outcome: ScanOutcome::Failed("bad".to_string()),
+                embedded: vec![Embedded {
+                    format: EmbeddedFormat::Gif,
+                    offset: 0,
+                    trailing: false,
+                }],
+            },
+            ScanEntry {
+                path: PathBuf::from("d.png"),
+                outcome: ScanOutcome::Matches(Vec::new()),
+                embedded: vec![Embedded {
+                    format: EmbeddedFormat::Zip,
+                    offset: 120,
+                    trailing: true,
+                }],
+            },
+        ];
+        assert_eq!(
+            scan_report(&entries, Units::Human),
+            concat!(
+                "a.png: ruSt 4.7 KiB (entropy 7.98, ratio 1.00), ",
+                "ruSt 2 B (entropy 0.00, ratio 0.00)\n",
+                "c.png: error: bad\n",
+                "c.png: embedded GIF at offset 0\n",
+                "d.png: embedded ZIP/JAR at offset 120 after IEND\n",
+                "1 of 4 files matched, 1 failed, 2 with embedded data\n"
+            )
+        );
+        let json = scan_json(&entries, Path::new("d"));
+        assert!(schema::conforms(&json, &schema::schema(Output::Scan)));
+        let json = json.to_string();
+        assert!(json.starts_with(concat!(
+            r#"{"schema_version":1,"dir":"d","files":[{"file":"a.png","error":null,"#,
+            r#""matches":[{"type":"ruSt","length":4803,"entropy":7.98,"compress_ratio":0.997},"#
+        )));
+        assert!(json.contains(
+            r#"{"file":"c.png","error":"bad","matches":[],"embedded":[{"format":"GIF","#
+        ));
+        assert!(json.ends_with(r#"{"format":"ZIP/JAR","offset":120,"trailing":true}]}]}"#));
+    }
+
+    #[test]
+    fn test_report_outputs() {
+        let report = CorpusReport {
+            files: 3,
+            chunk_types: vec![("IHDR".to_string(), 2), ("ruSt".to_string(), 1)],
+            ancillary_overhead: 0.125,
+            failed: vec![(PathBuf::from("c.png"), "no IEND chunk".to_string())],
+            suspicious: vec![(
+                PathBuf::from("b.png"),
+                vec!["ruSt: unregistered, private".to_string()],
+            )],
+        };
+        assert_eq!(
+            report_text(&report),
+            concat!(
+                "files:               3\n",
+                "failed validation:   1\n",
+                "suspicious:          1\n",
+                "ancillary overhead:  12.5%\n",
+                "chunk types:\n",
+                "  IHDR  2\n",
+                "  ruSt  1\n",
+                "c.png: failed: no IEND chunk\n",
+                "b.png: ruSt: unregistered, private\n"
+            )
+        );
+        assert_eq!(
+            report_json(&report, Path::new("d")).to_string(),
+            concat!(
+                r#"{"schema_version":1,"dir":"d","files":3,"ancillary_overhead":0.125,"#,
+                r#""chunk_types":[{"type":"IHDR","count":2},{"type":"ruSt","count":1}],"#,
+                r#""failed":[{"file":"c.png","reason":"no IEND chunk"}],"#,
+                r#""suspicious":[{"file":"b.png","findings":["ruSt: unregistered, private"]}]}"#
+            )
+        );
+        assert!(schema::conforms(
+            &report_json(&report, Path::new("d")),
+            &schema::schema(Output::Report)
+        ));
+        let html = report_html(&report, Path::new("<d>"));
+        assert!(html.contains("<title>PNG corpus report: &lt;d&gt;</title>"));
+        assert!(html.contains("<tr><td>ruSt</td><td>1</td></tr>"));
+        assert!(html.contains("<tr><td>c.png</td><td>no IEND chunk</td></tr>"));
+        assert!(html.ends_with("</html>\n"));
+    }
+
+    #[test]
+    fn test_timeline() {
+        let vfs = MemFs::new();
+        let older = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Text::new("Creation Time", "2019-01-01 00:00:00")
+                .unwrap()
+                .to_chunk(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let newer = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Time {
+                year: 2023,
+                month: 4,
+                day: 5,
+                hour: 6,
+                minute: 7,
+                second: 8,
+            }
+            .to_chunk(),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        vfs.write(Path::new("new.png"), &newer.as_bytes()).unwrap();
+        vfs.write(Path::new("old.png"), &older.as_bytes()).unwrap();
+        let args = TimelineArgs {
+            files: vec![PathBuf::from("new.png"), PathBuf::from("old.png")],
+        };
+        assert!(timeline(&vfs, &args, None, &CancellationToken::new()).is_ok());
+
+        let pngs = vec![
+            ("new.png".to_string(), newer),
+            ("old.png".to_string(), older),
+        ];
+        let report = timeline_report(&pngs);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "2019-01-01T00:00:00  old.png  tEXt Creation Time  2019-01-01 00:00:00",
+                "2023-04-05T06:07:08  new.png  tIME  2023-04-05T06:07:08Z",
+            ]
+        );
+
+        let args = TimelineArgs {
+            files: vec![PathBuf::from("missing.png")],
+        };
+        assert!(timeline(&vfs, &args, None, &CancellationToken::new()).is_err());
+    }
+
+    #[test]
+    fn test_doctor() {
+        let vfs = MemFs::new();
+        let file = PathBuf::from("in.png");
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"hi".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let mut bytes = png.as_bytes();
+        vfs.write(&file, &bytes).unwrap();
+        doctor(&vfs, &DoctorArgs { file: file.clone() }).unwrap();
+
+        bytes[41] ^= 1;
+        bytes.extend_from_slice(b"junk");
+        vfs.write(&file, &bytes).unwrap();
+        let err = doctor(&vfs, &DoctorArgs { file: file.clone() })
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("Problems Found"));
+        assert_eq!(
+            doctor_report(&diagnose(&bytes), &file),
+            concat!(
+                "1. error: wrong CRC in ruSt at offset 33\n",
+                "   fix: pngme repair in.png\n",
+                "2. warning: 4 bytes of trailing data at offset 59\n",
+                "   fix: pngme salvage in.png\n",
+                "3. notice: non-standard chunks: ruSt (unregistered, private)\n",
+                "   fix: pngme strip in.png\n"
+            )
+        );
+        assert_eq!(doctor_report(&[], &file), "no problems found\n");
+        let finding = Finding {
+            severity: Severity::Notice,
+            problem: "embedded GIF at offset 40".to_string(),
+            remedy: None,
+        };
+        assert!(doctor_report(&[finding], &file).ends_with("   no automatic fix\n"));
+        assert_eq!(
+            remedy_command(&Remedy::Remove("ABCD".to_string()), &file),
+            "pngme remove in.png ABCD --all --force"
+        );
+    }
+
+    #[test]
+    fn test_verify() {
+        let dir = temp_dir("verify");
+        let file = dir.join("in.png");
+        write_png(&file);
+        let mut args = VerifyArgs {
+            file: file.clone(),
+            json: false,
+            quarantine: None,
+            dry_run: false,
+        };
+        verify(&StdFs, &args).unwrap();
+
+        let mut bytes = fs::read(&file).unwrap();
+        bytes[33 + 8] ^= 1;
+        fs::write(&file, &bytes).unwrap();
+        assert!(verify(&StdFs, &args).is_err());
+        args.json = true;
+        assert!(verify(&StdFs, &args).is_err());
+
+        let quarantine = dir.join("quarantine");
+        args.quarantine = Some(quarantine.clone());
+        args.dry_run = true;
+        assert!(verify(&StdFs, &args).is_err());
+        assert!(file.exists());
+        args.dry_run = false;
+        assert!(verify(&StdFs, &args).is_err());
+        assert!(!file.exists());
+        assert_eq!(fs::read(quarantine.join("in.png")).unwrap(), bytes);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_verify_report() {
+        let mut bytes = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes();
+        let report = verify_report(&full_check(&bytes));
+        assert!(report.ends_with("crc ae426082  ok\nOK\n"));
+
+        bytes[20] ^= 1;
+        let check = full_check(&bytes);
+        let report = verify_report(&check);
+        let lines: Vec<&str> = report.lines().collect();
+        assert!(lines[0].starts_with("  0  IHDR  offset 8         length 13"));
+        assert!(lines[0].contains("BAD, computed"));
+        assert_eq!(lines[2], "FAILED: 1 chunk CRC mismatches");
+
+        let json = verify_json(&check, Path::new("a.png")).to_string();
+        assert!(json.starts_with(r#"{"file":"a.png","ok":false,"problem":null,"chunks":[{"index":0,"type":"IHDR","offset":8,"length":13,"#));
+        assert!(json.contains(r#""crc_ok":false}"#));
+
+        let truncated = full_check(&bytes[..bytes.len() - 4]);
+        assert!(verify_report(&truncated).ends_with("FAILED: truncated at offset 33\n"));
+    }
+
+    #[test]
+    fn test_repair() {
+        let dir = temp_dir("repair");
+        let file = dir.join("in.png");
+        let output = dir.join("out.png");
+        write_png(&file);
+        let good = fs::read(&file).unwrap();
+        let mut bad = good.clone();
+        bad[33 + 8] ^= 1;
+        bad[33 + 11] ^= 1;
+        fs::write(&file, &bad).unwrap();
+
+        let mut args = RepairArgs {
+            file: file.clone(),
+            fix_lengths: false,
+            output: Some(output.clone()),
+            dry_run: false,
+            units: Units::Human,
+        };
+        repair(&StdFs, &args).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), bad);
+        assert!(full_check(&fs::read(&output).unwrap()).is_ok());
+
+        args.output = None;
+        repair(&StdFs, &args).unwrap();
+        assert!(full_check(&fs::read(&file).unwrap()).is_ok());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_salvage() {
+        let dir = temp_dir("salvage");
+        let file = dir.join("in.png");
+        let output = dir.join("out.png");
+        write_png(&file);
+        let mut bytes = fs::read(&file).unwrap();
+        bytes.truncate(bytes.len() - 6);
+        fs::write(&file, &bytes).unwrap();
+
+        let mut args = SalvageArgs {
+            file: file.clone(),
+            output: Some(output.clone()),
+            dry_run: true,
+            units: Units::Human,
+        };
+        salvage(&StdFs, &args).unwrap();
+        assert!(!output.exists());
+        args.dry_run = false;
+        salvage(&StdFs, &args).unwrap();
+        assert_eq!(fs::read(&file).unwrap(), bytes);
+        assert_eq!(types(&output), vec!["IHDR", "IDAT", "IEND"]);
+
+        fs::write(&file, &bytes[..20]).unwrap();
+        assert!(salvage(&StdFs, &args).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_describe_fix() {
+        let fix = Fix::Crc {
+            offset: 33,
+            chunk_type: "IDAT".to_string(),
+            old: 1,
+            new: 0xabcdef,
+        };
+        assert_eq!(
+            describe_fix(&fix),
+            "offset 33 IDAT: crc 00000001 -> 00abcdef"
+        );
+        let fix = Fix::Length {
+            offset: 33,
+            chunk_type: "IDAT".to_string(),
+            old: 9,
+            new: 3,
+        };
+        assert_eq!(describe_fix(&fix), "offset 33 IDAT: length 9 -> 3");
+    }
+
+    #[test]
+    fn test_info_report() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(
+                ChunkType::from_str("IHDR").unwrap(),
+                vec![0, 0, 0, 50, 0, 0, 0, 40, 8, 6, 0, 0, 1],
+            ),
+            Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"a\0b".to_vec()),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![0; 100]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![0; 20]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let report = info_report(&png, Units::Human).unwrap();
+        assert_eq!(
+            report,
+            "dimensions:  50x40\n\
+             bit depth:   8\n\
+             color type:  6 (truecolor with alpha)\n\
+             interlace:   Adam7\n\
+             chunks:      5 (4 critical, 1 ancillary)\n\
+             pixel data:  7.9 KiB inflated, 120 B compressed\n  \
+             IHDR    1  image header\n  \
+             tEXt    1  textual metadata\n  \
+             IDAT    2  image data\n  \
+             IEND    1  image trailer\n"
+        );
+        let report = info_report(&png, Units::Exact).unwrap();
+        assert!(report.contains("pixel data:  8075 B inflated, 120 B compressed\n"));
+        assert!(info_report(&Png::from_chunks(Vec::new()), Units::Human).is_err());
+    }
+
+    #[test]
+    fn test_diff() {
+        let dir = temp_dir("diff");
+        let old = dir.join("old.png");
+        let new = dir.join("new.png");
+        write_png(&old);
+        encode(
+            &StdFs,
+            &EncodeArgs {
+                file: old.clone(),
+                chunk_type: "ruSt".to_string(),
+                payload: Payload::Message("hello".to_string()),
+                itxt: None,
+                provenance: None,
+                output: Some(new.clone()),
+                dry_run: false,
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        diff(
+            &StdFs,
+            &DiffArgs {
+                old: old.clone(),
+                new: new.clone(),
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .unwrap();
+        assert!(diff(
+            &StdFs,
+            &DiffArgs {
+                old: old.clone(),
+                new: dir.join("missing.png"),
+                units: Units::Human,
+            },
+            None,
+            &CancellationToken::new(),
+        )
+        .is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_decode_messages() {
+        let chunk_type = ChunkType::from_str("ruSt").unwrap();
+        let mut png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        png.insert_before_end(Chunk::new(chunk_type.clone(), b"first".to_vec()));
+        png.insert_before_end(Chunk::new(chunk_type.clone(), b"second".to_vec()));
+
+        assert_eq!(messages(&png, "ruSt").unwrap(), vec!["first", "second"]);
+        assert!(messages(&png, "abCd").is_err());
+        assert!(messages(&png, "ru5t").is_err());
+
+        png.insert_before_end(Chunk::new(chunk_type, vec![0xff, 0xfe]));
+        assert_eq!(
+            messages(&png, "ruSt").unwrap()[2],
+            "<2 bytes of binary data, base64> //4="
+        );
+        assert_eq!(payload(&png, "ruSt").unwrap(), b"firstsecond\xff\xfe");
+        assert!(payload(&png, "abCd").is_err());
+    }
+
+    #[test]
+    fn test_list_report() {
+        let mut png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"a\0b".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        assert_eq!(
+            list_report(&png, Units::Human, None),
+            "0 of 3 chunks are non-standard\n"
+        );
+
+        png.insert_before_end(Chunk::new(
+            ChunkType::from_str("ruSt").unwrap(),
+            b"hi".to_vec(),
+        ));
+        let report = list_report(&png, Units::Human, None);
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("2   ruSt  offset 48 "));
+        assert!(lines[0].ends_with("unregistered, private"));
+        assert_eq!(lines[1], "1 of 4 chunks are non-standard");
+
+        let json = list_json(&png, Path::new("a.png"), None, None);
+        assert!(schema::conforms(&json, &schema::schema(Output::List)));
+        assert_eq!(
+            json.to_string(),
+            concat!(
+                r#"{"schema_version":1,"file":"a.png","filter":null,"chunks":["#,
+                r#"{"index":2,"type":"ruSt","offset":48,"length":2,"#,
+                r#""oddities":["unregistered","private"]}],"total":4}"#
+            )
+        );
+    }
+
+    #[test]
+    fn test_list_report_where() {
+        let png = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"a\0b".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ]);
+        let filter = Filter::from_str("!critical").unwrap();
+        let report = list_report(&png, Units::Exact, Some(&filter));
+        let lines: Vec<&str> = report.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("1   tEXt  offset 33 "));
+        assert_eq!(lines[0], lines[0].trim_end());
+        assert_eq!(lines[1], "1 of 3 chunks match");
+        let json = list_json(&png, Path::new("a.png"), Some("!critical"), Some(&filter));
+        assert!(json
+            .to_string()
+            .contains(r#""filter":"!critical","chunks":[{"index":1,"type":"tEXt","#));
+    }
+
+    #[test]
+    fn test_decode_output_file() {
+        let dir = temp_dir("decode_output");
+        let file = dir.join("in.png");
+        let output = dir.join("payload.bin");
+        write_png(&file);
+        let mut args = DecodeArgs {
+            file: file.clone(),
+            chunk_type: "IDAT".to_string(),
+            json: false,
+            output_file: Some(output.clone()),
+            dry_run: false,
+            units: Units::Human,
+        };
+        decode(&StdFs, &args, None, &CancellationToken::new()).unwrap();
+        assert_eq!(fs::read(&output).unwrap(), vec![1, 2, 3]);
+        args.output_file = Some(dir.join(".").join("in.png"));
+        let err = decode(&StdFs, &args, None, &CancellationToken::new())
+            .err()
+            .unwrap()
+            .to_string();
+        assert!(err.ends_with("Same As The Input"), "{}", err);
+        assert_eq!(types(&file), vec!["IHDR", "IDAT", "IEND"]);
+        args.output_file = Some(dir.join("missing").join("payload.bin"));
+        assert!(decode(&StdFs, &args, None, &CancellationToken::new()).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,208 @@
+use crate::chunk::Chunk;
+use crate::ihdr::Ihdr;
+use crate::png::Png;
+use crate::text::Text;
+
+/// Damage applied by [`generate`] after building a well-formed file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Damage {
+    None,
+    /// The CRC of the chunk before IEND (the last IDAT) has a flipped bit.
+    BadCrc,
+    /// The file is cut in the middle of the last IDAT, or of IEND if there
+    /// is no IDAT.
+    Truncated,
+    /// Random bytes are inserted between two chunks.
+    Garbage,
+    MissingIend,
+    /// Random bytes follow IEND.
+    TrailingData,
+}
+
+impl Damage {
+    pub const ALL: [Damage; 6] = [
+        Damage::None,
+        Damage::BadCrc,
+        Damage::Truncated,
+        Damage::Garbage,
+        Damage::MissingIend,
+        Damage::TrailingData,
+    ];
+    /// Short kebab-case name, used for file names and on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Damage::None => "none",
+            Damage::BadCrc => "bad-crc",
+            Damage::Truncated => "truncated",
+            Damage::Garbage => "garbage",
+            Damage::MissingIend => "missing-iend",
+            Damage::TrailingData => "trailing-data",
+        }
+    }
+    /// The damage called `name` by [`Damage::name`].
+    pub fn from_name(name: &str) -> Option<Damage> {
+        Damage::ALL.into_iter().find(|d| d.name() == name)
+    }
+}
+
+/// Shape of a synthetic file. The same options always produce the same
+/// bytes, so inputs can be regenerated instead of shipped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CorpusOptions {
+    /// Number of IDAT chunks.
+    pub chunk_count: usize,
+    /// Data size of each IDAT chunk.
+    pub chunk_size: usize,
+    pub damage: Damage,
+    pub seed: u64,
+}
+
+impl Default for CorpusOptions {
+    fn default() -> Self {
+        CorpusOptions {
+            chunk_count: 4,
+            chunk_size: 1024,
+            damage: Damage::None,
+            seed: 1,
+        }
+    }
+}
+
+/// Builds a synthetic PNG: IHDR, a tEXt chunk, `chunk_count` IDAT chunks of
+/// pseudo-random data and IEND, then applies the requested damage. The
+/// image data is not a valid zlib stream; the files exercise chunk-level
+/// code only.
+pub fn generate(opts: &CorpusOptions) -> Vec<u8> {
+    let mut rng = XorShift(opts.seed.max(1));
+    let mut png = Png::from_chunks(vec![
+        Ihdr {
+            width: 64,
+            height: 64,
+            bit_depth: 8,
+            color_type: 6,
+            compression: 0,
+            filter: 0,
+            interlace: 0,
+        }
+        .to_chunk(),
+        Text::new("Software", "pngme corpus").unwrap().to_chunk(),
+    ]);
+    for _ in 0..opts.chunk_count {
+        png.append_chunk(chunk("IDAT", rng.bytes(opts.chunk_size)));
+    }
+    png.append_chunk(chunk("IEND", Vec::new()));
+
+    let mut bytes = png.as_bytes();
+    let iend = bytes.len() - 12;
+    // Start of the last IDAT, or of IEND if there is none.
+    let last = iend
+        - if opts.chunk_count > 0 {
+            opts.chunk_size + 12
+        } else {
+            0
+        };
+    match opts.damage {
+        Damage::None => {}
+        Damage::BadCrc => bytes[iend - 1] ^= 1,
+        Damage::Truncated => bytes.truncate((last + 8 + opts.chunk_size / 2).min(iend + 8)),
+        Damage::Garbage => {
+            let garbage = rng.bytes(16);
+            bytes.splice(last..last, garbage);
+        }
+        Damage::MissingIend => bytes.truncate(iend),
+        Damage::TrailingData => bytes.extend(rng.bytes(16)),
+    }
+    bytes
+}
+
+fn chunk(chunk_type: &str, data: Vec<u8>) -> Chunk {
+    crate::ancillary::new_chunk(chunk_type, data)
+}
+
+/// Small deterministic generator; the corpus must not depend on a rand crate
+/// or on the platform.
+struct XorShift(u64);
+
+impl XorShift {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+    fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next() as u8).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::verify::{quick_check, FileCheck};
+
+    fn with_damage(damage: Damage) -> Vec<u8> {
+        generate(&CorpusOptions {
+            damage,
+            ..CorpusOptions::default()
+        })
+    }
+
+    #[test]
+    fn test_generate_valid() {
+        let bytes = with_damage(Damage::None);
+        assert!(quick_check(&bytes).is_ok());
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(png.chunks_by_type("IDAT").len(), 4);
+        assert_eq!(png.header().unwrap().width, 64);
+        assert_eq!(bytes, with_damage(Damage::None));
+    }
+
+    #[test]
+    fn test_generate_seed_changes_data() {
+        let other = generate(&CorpusOptions {
+            seed: 2,
+            ..CorpusOptions::default()
+        });
+        assert_ne!(other, with_damage(Damage::None));
+        assert_eq!(other.len(), with_damage(Damage::None).len());
+    }
+
+    #[test]
+    fn test_generate_damage() {
+        for damage in Damage::ALL {
+            let bytes = with_damage(damage);
+            let parsed = Png::try_from(bytes.as_ref()).is_ok();
+            assert_eq!(parsed, matches!(damage, Damage::None | Damage::MissingIend));
+        }
+        assert_eq!(
+            quick_check(&with_damage(Damage::MissingIend)),
+            FileCheck::MissingIend
+        );
+        assert!(matches!(
+            quick_check(&with_damage(Damage::TrailingData)),
+            FileCheck::TrailingData { .. }
+        ));
+        let recovered = Png::recover(&with_damage(Damage::Garbage));
+        assert_eq!(recovered.skipped.len(), 1);
+        assert_eq!(recovered.png.chunks().len(), 7);
+    }
+
+    #[test]
+    fn test_damage_names() {
+        for damage in Damage::ALL {
+            assert_eq!(Damage::from_name(damage.name()), Some(damage));
+        }
+        assert_eq!(Damage::BadCrc.name(), "bad-crc");
+        assert_eq!(Damage::from_name("BadCrc"), None);
+    }
+
+    #[test]
+    fn test_generate_no_idat() {
+        let bytes = generate(&CorpusOptions {
+            chunk_count: 0,
+            damage: Damage::Truncated,
+            ..CorpusOptions::default()
+        });
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+    }
+}
@@ -0,0 +1,166 @@
+use crate::png::Png;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+
+/// Identifies a chunk across two files: its type and how many chunks of the
+/// same type come before it, so the second `tEXt` matches the second `tEXt`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ChunkKey {
+    pub chunk_type: String,
+    pub occurrence: usize,
+}
+
+impl Display for ChunkKey {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}#{}", self.chunk_type, self.occurrence)
+    }
+}
+
+/// One difference found by [`diff`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChunkChange {
+    Added {
+        key: ChunkKey,
+        crc: u32,
+    },
+    Removed {
+        key: ChunkKey,
+        crc: u32,
+    },
+    /// Present in both, with a different CRC or length.
+    Modified {
+        key: ChunkKey,
+        old_crc: u32,
+        new_crc: u32,
+    },
+}
+
+impl Display for ChunkChange {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ChunkChange::Added { key, crc } => write!(f, "+ {} crc {:08x}", key, crc),
+            ChunkChange::Removed { key, crc } => write!(f, "- {} crc {:08x}", key, crc),
+            ChunkChange::Modified {
+                key,
+                old_crc,
+                new_crc,
+            } => write!(f, "~ {} crc {:08x} -> {:08x}", key, old_crc, new_crc),
+        }
+    }
+}
+
+/// Compares the chunks of `old` and `new` by [`ChunkKey`]. Removed and
+/// modified chunks come first in the order of `old`, then added chunks in
+/// the order of `new`. Chunks that only moved are not reported.
+pub fn diff(old: &Png, new: &Png) -> Vec<ChunkChange> {
+    let old_chunks = keyed(old);
+    let new_chunks = keyed(new);
+    let new_by_key: HashMap<&ChunkKey, (u32, u32)> = new_chunks
+        .iter()
+        .map(|(k, len, crc)| (k, (*len, *crc)))
+        .collect();
+    let old_by_key: HashMap<&ChunkKey, (u32, u32)> = old_chunks
+        .iter()
+        .map(|(k, len, crc)| (k, (*len, *crc)))
+        .collect();
+    let mut changes = Vec::new();
+    for (key, length, crc) in &old_chunks {
+        match new_by_key.get(key) {
+            None => changes.push(ChunkChange::Removed {
+                key: key.clone(),
+                crc: *crc,
+            }),
+            Some((new_length, new_crc)) if new_length != length || new_crc != crc => {
+                changes.push(ChunkChange::Modified {
+                    key: key.clone(),
+                    old_crc: *crc,
+                    new_crc: *new_crc,
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    for (key, _, crc) in &new_chunks {
+        if !old_by_key.contains_key(key) {
+            changes.push(ChunkChange::Added {
+                key: key.clone(),
+                crc: *crc,
+            });
+        }
+    }
+    changes
+}
+
+/// Key, length and CRC of every chunk, in file order.
+fn keyed(png: &Png) -> Vec<(ChunkKey, u32, u32)> {
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    png.chunks()
+        .map(|chunk| {
+            let chunk_type = chunk.chunk_type().to_string();
+            let occurrence = seen.entry(chunk_type.clone()).or_insert(0);
+            let key = ChunkKey {
+                chunk_type,
+                occurrence: *occurrence,
+            };
+            *occurrence += 1;
+            (key, chunk.length(), chunk.crc())
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn key(chunk_type: &str, occurrence: usize) -> ChunkKey {
+        ChunkKey {
+            chunk_type: chunk_type.to_string(),
+            occurrence,
+        }
+    }
+
+    #[test]
+    fn test_diff_identical() {
+        let png = Png::from_chunks(vec![chunk("IHDR", &[0; 13]), chunk("IEND", &[])]);
+        assert!(diff(&png, &png).is_empty());
+    }
+
+    #[test]
+    fn test_diff_changes() {
+        let old = Png::from_chunks(vec![
+            chunk("IHDR", &[0; 13]),
+            chunk("tEXt", b"a\0b"),
+            chunk("tEXt", b"c\0d"),
+            chunk("IDAT", &[1, 2, 3]),
+            chunk("IEND", &[]),
+        ]);
+        let new = Png::from_chunks(vec![
+            chunk("IHDR", &[0; 13]),
+            chunk("tEXt", b"a\0b"),
+            chunk("IDAT", &[1, 2, 4]),
+            chunk("ruSt", b"secret"),
+            chunk("IEND", &[]),
+        ]);
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 3);
+        assert_eq!(
+            changes[0],
+            ChunkChange::Removed {
+                key: key("tEXt", 1),
+                crc: old.chunks().nth(2).unwrap().crc()
+            }
+        );
+        assert!(
+            matches!(&changes[1], ChunkChange::Modified { key: k, .. } if *k == key("IDAT", 0))
+        );
+        assert!(matches!(&changes[2], ChunkChange::Added { key: k, .. } if *k == key("ruSt", 0)));
+        assert!(changes[2].to_string().starts_with("+ ruSt#0 crc "));
+    }
+}
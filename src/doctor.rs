@@ -0,0 +1,284 @@
+use crate::png::{ParseOptions, Png, Warning};
+use crate::polyglot::{self, EmbeddedFormat};
+use crate::registry;
+use crate::repair::{repair, RepairOptions};
+use crate::verify::{full_check, FileCheck};
+
+/// How urgent a [`Finding`] is. Findings are listed most urgent first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    /// Decoders are likely to reject the file.
+    Error,
+    /// The file decodes, but carries damage or data no decoder reads.
+    Warning,
+    /// Worth a look when hunting for hidden data.
+    Notice,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Notice => "notice",
+        }
+    }
+}
+
+/// The command that fixes a [`Finding`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Remedy {
+    /// `repair`: recompute wrong CRCs.
+    Repair,
+    /// `repair --fix-lengths`: also fix length fields.
+    RepairLengths,
+    /// `salvage`: rebuild from the intact chunks, dropping anything after
+    /// IEND.
+    Salvage,
+    /// `strip`: remove the ancillary chunks.
+    Strip,
+    /// `remove --all --force`: remove every chunk of this type.
+    Remove(String),
+    /// `carve`: copy the embedded PNGs out.
+    Carve,
+}
+
+/// One problem found by [`diagnose`], with the command that fixes it, if
+/// any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Finding {
+    pub severity: Severity,
+    pub problem: String,
+    pub remedy: Option<Remedy>,
+}
+
+impl Finding {
+    fn new(severity: Severity, problem: String, remedy: Option<Remedy>) -> Self {
+        Finding {
+            severity,
+            problem,
+            remedy,
+        }
+    }
+}
+
+/// Validates the framing of `value`, checks every CRC, looks for data after
+/// IEND and for known bad patterns (unknown critical chunks, chunks after
+/// IEND, non-standard chunk types and embedded files), and returns what it
+/// found, most urgent first. Empty for a clean file.
+pub fn diagnose(value: &[u8]) -> Vec<Finding> {
+    let check = full_check(value);
+    let embedded = polyglot::detect(value);
+    let mut findings = Vec::new();
+    if check.framing == FileCheck::BadSignature {
+        let carve = polyglot::find(value, &Png::STANDARD_HEADER, 0).is_some();
+        findings.push(Finding::new(
+            Severity::Error,
+            check.framing.to_string(),
+            carve.then_some(Remedy::Carve),
+        ));
+        return findings;
+    }
+    let framing = match check.framing {
+        FileCheck::Truncated { .. } | FileCheck::MissingIend => {
+            let remedy = framing_remedy(value);
+            findings.push(Finding::new(
+                Severity::Error,
+                check.framing.to_string(),
+                remedy.clone(),
+            ));
+            remedy
+        }
+        FileCheck::TrailingData { offset } => {
+            findings.push(Finding::new(
+                Severity::Warning,
+                format!(
+                    "{} bytes of trailing data at offset {}",
+                    value.len() - offset,
+                    offset
+                ),
+                Some(Remedy::Salvage),
+            ));
+            Some(Remedy::Repair)
+        }
+        _ => Some(Remedy::Repair),
+    };
+    let bad: Vec<String> = check
+        .chunks
+        .iter()
+        .filter(|c| !c.is_ok())
+        .map(|c| format!("{} at offset {}", c.chunk_type, c.offset))
+        .collect();
+    if !bad.is_empty() {
+        findings.push(Finding::new(
+            Severity::Error,
+            format!("wrong CRC in {}", bad.join(", ")),
+            framing,
+        ));
+    }
+    if let Ok(png) = Png::try_from_with(value, &ParseOptions::permissive()) {
+        for warning in png.warnings() {
+            match warning {
+                Warning::UnknownCriticalChunk { offset, chunk_type } => {
+                    findings.push(Finding::new(
+                        Severity::Error,
+                        format!("unknown critical chunk {} at offset {}", chunk_type, offset),
+                        Some(Remedy::Remove(chunk_type.clone())),
+                    ))
+                }
+                Warning::AfterEnd { offset, chunk_type } => findings.push(Finding::new(
+                    Severity::Warning,
+                    format!("chunk {} after IEND at offset {}", chunk_type, offset),
+                    Some(Remedy::Salvage),
+                )),
+                _ => {}
+            }
+        }
+        let odd: Vec<String> = png
+            .chunks()
+            .filter(|c| !c.chunk_type().is_critical())
+            .filter_map(|c| {
+                let oddities = registry::oddities(c.chunk_type());
+                (!oddities.is_empty())
+                    .then(|| format!("{} ({})", c.chunk_type(), oddities.join(", ")))
+            })
+            .collect();
+        if !odd.is_empty() {
+            findings.push(Finding::new(
+                Severity::Notice,
+                format!("non-standard chunks: {}", odd.join(", ")),
+                Some(Remedy::Strip),
+            ));
+        }
+    }
+    for e in embedded {
+        let remedy = match (e.format, e.trailing) {
+            (EmbeddedFormat::Png, _) => Some(Remedy::Carve),
+            (_, true) => Some(Remedy::Salvage),
+            (_, false) => None,
+        };
+        findings.push(Finding::new(
+            Severity::Notice,
+            format!(
+                "embedded {} at offset {}{}",
+                e.format.name(),
+                e.offset,
+                if e.trailing { " after IEND" } else { "" }
+            ),
+            remedy,
+        ));
+    }
+    findings.sort_by_key(|f| f.severity);
+    findings
+}
+
+/// `repair --fix-lengths` if it yields a file with sound framing, else
+/// `salvage` if anything can be salvaged.
+fn framing_remedy(value: &[u8]) -> Option<Remedy> {
+    let opts = RepairOptions { fix_lengths: true };
+    match repair(value, &opts) {
+        Ok(fixed) if full_check(&fixed.bytes).framing.is_ok() => Some(Remedy::RepairLengths),
+        _ => Png::salvage(value).is_ok().then_some(Remedy::Salvage),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn png(extra: &[(&str, &[u8])]) -> Vec<u8> {
+        let mut chunks = vec![Chunk::new(
+            ChunkType::from_str("IHDR").unwrap(),
+            vec![0; 13],
+        )];
+        for (chunk_type, data) in extra {
+            chunks.push(Chunk::new(
+                ChunkType::from_str(chunk_type).unwrap(),
+                data.to_vec(),
+            ));
+        }
+        chunks.push(Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1; 8]));
+        chunks.push(Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()));
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[test]
+    fn test_diagnose_clean() {
+        assert_eq!(diagnose(&png(&[("tEXt", b"a\0b")])), Vec::new());
+    }
+
+    #[test]
+    fn test_diagnose_bad_crc_and_trailing_data() {
+        let mut bytes = png(&[("ruSt", b"hi")]);
+        bytes[33 + 8] ^= 1;
+        bytes.extend_from_slice(b"PK\x03\x04zip");
+        let findings = diagnose(&bytes);
+        assert_eq!(
+            findings,
+            vec![
+                Finding::new(
+                    Severity::Error,
+                    "wrong CRC in ruSt at offset 33".to_string(),
+                    Some(Remedy::Repair)
+                ),
+                Finding::new(
+                    Severity::Warning,
+                    "7 bytes of trailing data at offset 79".to_string(),
+                    Some(Remedy::Salvage)
+                ),
+                Finding::new(
+                    Severity::Notice,
+                    "non-standard chunks: ruSt (unregistered, private)".to_string(),
+                    Some(Remedy::Strip)
+                ),
+                Finding::new(
+                    Severity::Notice,
+                    "embedded ZIP/JAR at offset 79 after IEND".to_string(),
+                    Some(Remedy::Salvage)
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_diagnose_framing() {
+        let mut bytes = png(&[]);
+        bytes.truncate(bytes.len() - 12);
+        let findings = diagnose(&bytes);
+        assert_eq!(findings[0].problem, "no IEND chunk");
+        assert_eq!(findings[0].remedy, Some(Remedy::Salvage));
+
+        let mut bytes = png(&[]);
+        bytes[33..37].copy_from_slice(&[0, 0, 0, 99]);
+        let findings = diagnose(&bytes);
+        assert_eq!(findings[0].severity, Severity::Error);
+        assert_eq!(findings[0].remedy, Some(Remedy::RepairLengths));
+    }
+
+    #[test]
+    fn test_diagnose_unknown_critical() {
+        let findings = diagnose(&png(&[("ABCD", b"")]));
+        assert_eq!(
+            findings[0],
+            Finding::new(
+                Severity::Error,
+                "unknown critical chunk ABCD at offset 33".to_string(),
+                Some(Remedy::Remove("ABCD".to_string()))
+            )
+        );
+    }
+
+    #[test]
+    fn test_diagnose_not_png() {
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend(png(&[]));
+        let findings = diagnose(&bytes);
+        assert_eq!(findings.len(), 1);
+        assert_eq!(findings[0].problem, "not a PNG signature");
+        assert_eq!(findings[0].remedy, Some(Remedy::Carve));
+        assert_eq!(diagnose(b"plain text")[0].remedy, None);
+    }
+}
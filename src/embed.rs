@@ -0,0 +1,117 @@
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::png::Png;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// Options for [`encode_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EncodeOptions {
+    /// Type of the chunk carrying the payload.
+    pub chunk_type: String,
+}
+
+/// Options for [`decode_bytes`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeOptions {
+    /// Type of the chunk carrying the payload.
+    pub chunk_type: String,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            chunk_type: "ruSt".to_string(),
+        }
+    }
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        DecodeOptions {
+            chunk_type: "ruSt".to_string(),
+        }
+    }
+}
+
+/// Parses `cover`, inserts `payload` as a new chunk before IEND (or at the
+/// end if there is none) and returns the serialized PNG. Never touches the
+/// file system.
+pub fn encode_bytes(cover: &[u8], payload: &[u8], opts: &EncodeOptions) -> Result<Vec<u8>> {
+    let chunk_type = ChunkType::from_str(&opts.chunk_type)?;
+    if !chunk_type.is_valid() {
+        return Err(anyhow!(
+            "Invalid Encode Chunk Type {} : Reserved Bit Set",
+            chunk_type
+        ));
+    }
+    let mut png = Png::try_from(cover)?;
+    png.insert_before_end(Chunk::new(chunk_type, payload.to_vec()));
+    Ok(png.as_bytes())
+}
+
+/// Returns the data of the first chunk of the requested type in `image`.
+pub fn decode_bytes(image: &[u8], opts: &DecodeOptions) -> Result<Vec<u8>> {
+    let png = Png::try_from(image)?;
+    png.chunk_by_type(&opts.chunk_type)
+        .map(|c| c.data().to_vec())
+        .ok_or_else(|| {
+            anyhow!(
+                "Invalid Decode Chunk Type {} : No such Chunk in Png",
+                opts.chunk_type
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cover() -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes()
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let payload = [0u8, 159, 146, 150, 255];
+        let encoded = encode_bytes(&cover(), &payload, &EncodeOptions::default()).unwrap();
+
+        let png = Png::try_from(encoded.as_ref()).unwrap();
+        let types: Vec<String> = png.chunks().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["IHDR", "IDAT", "ruSt", "IEND"]);
+
+        let decoded = decode_bytes(&encoded, &DecodeOptions::default()).unwrap();
+        assert_eq!(decoded, payload);
+    }
+
+    #[test]
+    fn test_encode_custom_chunk_type() {
+        let opts = EncodeOptions {
+            chunk_type: "prIv".to_string(),
+        };
+        let encoded = encode_bytes(&cover(), b"hidden", &opts).unwrap();
+        let opts = DecodeOptions {
+            chunk_type: "prIv".to_string(),
+        };
+        assert_eq!(decode_bytes(&encoded, &opts).unwrap(), b"hidden");
+    }
+
+    #[test]
+    fn test_encode_errors() {
+        let opts = EncodeOptions {
+            chunk_type: "rust".to_string(),
+        };
+        assert!(encode_bytes(&cover(), b"x", &opts).is_err());
+        assert!(encode_bytes(b"not a png", b"x", &EncodeOptions::default()).is_err());
+    }
+
+    #[test]
+    fn test_decode_missing_chunk() {
+        assert!(decode_bytes(&cover(), &DecodeOptions::default()).is_err());
+    }
+}
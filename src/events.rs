@@ -0,0 +1,49 @@
+use crate::png::Warning;
+use crate::verify::FileCheck;
+use std::path::PathBuf;
+
+/// Something a long-running operation reports while it works, so a GUI can
+/// show live progress instead of waiting for the final result.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// `done` of `total` units handled: input bytes when parsing one file,
+    /// files when working through several.
+    Progress { done: usize, total: usize },
+    /// A tolerated anomaly, reported as soon as it is found.
+    Warning(Warning),
+    /// One file of a multi-file operation has been checked.
+    FileChecked { path: PathBuf, check: FileCheck },
+}
+
+/// Receives [`Event`]s, in order, on the thread running the operation. Any
+/// `FnMut(&Event)` closure is a listener.
+pub trait Listener {
+    fn on_event(&mut self, event: &Event);
+}
+
+impl<F: FnMut(&Event)> Listener for F {
+    fn on_event(&mut self, event: &Event) {
+        self(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_closure_listener() {
+        let mut seen = Vec::new();
+        let mut listener = |event: &Event| seen.push(event.clone());
+        let listener: &mut dyn Listener = &mut listener;
+        listener.on_event(&Event::Progress { done: 1, total: 2 });
+        listener.on_event(&Event::Progress { done: 2, total: 2 });
+        assert_eq!(
+            seen,
+            vec![
+                Event::Progress { done: 1, total: 2 },
+                Event::Progress { done: 2, total: 2 }
+            ]
+        );
+    }
+}
@@ -0,0 +1,383 @@
+use crate::ancillary::{check_type, new_chunk};
+use crate::chunk::Chunk;
+use crate::codec::{read_i32, read_u16, write_i32, write_u16};
+use crate::text::{latin1_decode, latin1_encode, split_null, validate_keyword};
+use anyhow::{anyhow, Result};
+
+/// Image offset (`oFFs`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Offs {
+    pub x: i32,
+    pub y: i32,
+    /// 0 = pixels, 1 = micrometers.
+    pub unit: u8,
+}
+
+impl Offs {
+    pub const CHUNK_TYPE: &'static str = "oFFs";
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        write_i32(&mut data, self.x);
+        write_i32(&mut data, self.y);
+        data.push(self.unit);
+        new_chunk(Offs::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Offs {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Offs::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() != 9 {
+            return Err(anyhow!(
+                "Invalid oFFs Chunk : Length {} , Should Be 9",
+                data.len()
+            ));
+        }
+        if data[8] > 1 {
+            return Err(anyhow!("Invalid oFFs Chunk : Unit {}", data[8]));
+        }
+        Ok(Offs {
+            x: read_i32(data, 0)?,
+            y: read_i32(data, 4)?,
+            unit: data[8],
+        })
+    }
+}
+
+/// Physical scale of the image subject (`sCAL`). Width and height are kept
+/// as the ASCII floating-point strings stored in the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scal {
+    /// 1 = meters, 2 = radians.
+    pub unit: u8,
+    pub width: String,
+    pub height: String,
+}
+
+impl Scal {
+    pub const CHUNK_TYPE: &'static str = "sCAL";
+
+    pub fn width_value(&self) -> Result<f64> {
+        parse_positive_float(&self.width)
+    }
+    pub fn height_value(&self) -> Result<f64> {
+        parse_positive_float(&self.height)
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = vec![self.unit];
+        data.extend_from_slice(self.width.as_bytes());
+        data.push(0);
+        data.extend_from_slice(self.height.as_bytes());
+        new_chunk(Scal::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Scal {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Scal::CHUNK_TYPE)?;
+        let (&unit, rest) = chunk
+            .data()
+            .split_first()
+            .ok_or_else(|| anyhow!("Invalid sCAL Chunk : Missing Unit"))?;
+        if unit != 1 && unit != 2 {
+            return Err(anyhow!("Invalid sCAL Chunk : Unit {}", unit));
+        }
+        let (width, height) = split_null(rest)
+            .ok_or_else(|| anyhow!("Invalid sCAL Chunk : Missing Width Terminator"))?;
+        let scal = Scal {
+            unit,
+            width: ascii_string(width, "sCAL")?,
+            height: ascii_string(height, "sCAL")?,
+        };
+        scal.width_value()?;
+        scal.height_value()?;
+        Ok(scal)
+    }
+}
+
+/// Pixel calibration (`pCAL`): maps stored sample values to physical values.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pcal {
+    pub name: String,
+    pub x0: i32,
+    pub x1: i32,
+    /// 0 = linear, 1 = exponential base e, 2 = arbitrary base, 3 = hyperbolic.
+    pub equation_type: u8,
+    pub unit: String,
+    pub parameters: Vec<String>,
+}
+
+impl Pcal {
+    pub const CHUNK_TYPE: &'static str = "pCAL";
+
+    fn parameter_count(equation_type: u8) -> Option<usize> {
+        match equation_type {
+            0 => Some(2),
+            1 | 2 => Some(3),
+            3 => Some(4),
+            _ => None,
+        }
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = latin1_encode(&self.name);
+        data.push(0);
+        write_i32(&mut data, self.x0);
+        write_i32(&mut data, self.x1);
+        data.push(self.equation_type);
+        data.push(self.parameters.len() as u8);
+        data.extend(latin1_encode(&self.unit));
+        for p in &self.parameters {
+            data.push(0);
+            data.extend_from_slice(p.as_bytes());
+        }
+        new_chunk(Pcal::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Pcal {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Pcal::CHUNK_TYPE)?;
+        let (name, rest) = split_null(chunk.data())
+            .ok_or_else(|| anyhow!("Invalid pCAL Chunk : Missing Name Terminator"))?;
+        let name = latin1_decode(name);
+        validate_keyword(&name)?;
+        if rest.len() < 10 {
+            return Err(anyhow!("Invalid pCAL Chunk : Too Short"));
+        }
+        let x0 = read_i32(rest, 0)?;
+        let x1 = read_i32(rest, 4)?;
+        if x0 == x1 {
+            return Err(anyhow!("Invalid pCAL Chunk : x0 Equals x1 ({})", x0));
+        }
+        let equation_type = rest[8];
+        let count = rest[9] as usize;
+        match Pcal::parameter_count(equation_type) {
+            Some(expected) if expected == count => {}
+            Some(expected) => {
+                return Err(anyhow!(
+                    "Invalid pCAL Chunk : {} Parameters , Equation Type {} Needs {}",
+                    count,
+                    equation_type,
+                    expected
+                ))
+            }
+            None => {
+                return Err(anyhow!(
+                    "Invalid pCAL Chunk : Equation Type {}",
+                    equation_type
+                ))
+            }
+        }
+        let mut fields = rest[10..].split(|b| *b == 0);
+        let unit = latin1_decode(fields.next().unwrap_or_default());
+        let parameters = fields
+            .map(|p| ascii_string(p, "pCAL"))
+            .collect::<Result<Vec<_>>>()?;
+        if parameters.len() != count {
+            return Err(anyhow!(
+                "Invalid pCAL Chunk : Found {} Parameters , Header Says {}",
+                parameters.len(),
+                count
+            ));
+        }
+        for p in &parameters {
+            p.parse::<f64>()
+                .map_err(|_| anyhow!("Invalid pCAL Chunk : Parameter {} Is Not A Number", p))?;
+        }
+        Ok(Pcal {
+            name,
+            x0,
+            x1,
+            equation_type,
+            unit,
+            parameters,
+        })
+    }
+}
+
+/// Stereo image indicator (`sTER`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Ster {
+    CrossFuse,
+    DivergingFuse,
+}
+
+impl Ster {
+    pub const CHUNK_TYPE: &'static str = "sTER";
+
+    pub fn to_chunk(&self) -> Chunk {
+        new_chunk(Ster::CHUNK_TYPE, vec![*self as u8])
+    }
+}
+
+impl TryFrom<&Chunk> for Ster {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Ster::CHUNK_TYPE)?;
+        match chunk.data() {
+            [0] => Ok(Ster::CrossFuse),
+            [1] => Ok(Ster::DivergingFuse),
+            [mode] => Err(anyhow!("Invalid sTER Chunk : Mode {}", mode)),
+            data => Err(anyhow!(
+                "Invalid sTER Chunk : Length {} , Should Be 1",
+                data.len()
+            )),
+        }
+    }
+}
+
+/// GIF graphic control extension (`gIFg`) kept by GIF-to-PNG converters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Gifg {
+    pub disposal_method: u8,
+    pub user_input: bool,
+    /// Delay in hundredths of a second.
+    pub delay_time: u16,
+}
+
+impl Gifg {
+    pub const CHUNK_TYPE: &'static str = "gIFg";
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = vec![self.disposal_method, self.user_input as u8];
+        write_u16(&mut data, self.delay_time);
+        new_chunk(Gifg::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Gifg {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Gifg::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() != 4 {
+            return Err(anyhow!(
+                "Invalid gIFg Chunk : Length {} , Should Be 4",
+                data.len()
+            ));
+        }
+        Ok(Gifg {
+            disposal_method: data[0],
+            user_input: data[1] != 0,
+            delay_time: read_u16(data, 2)?,
+        })
+    }
+}
+
+fn ascii_string(bytes: &[u8], chunk_type: &str) -> Result<String> {
+    if bytes.is_ascii() {
+        Ok(String::from_utf8(bytes.to_vec()).unwrap())
+    } else {
+        Err(anyhow!(
+            "Invalid {} Chunk : Non ASCII Numeric Field",
+            chunk_type
+        ))
+    }
+}
+
+fn parse_positive_float(s: &str) -> Result<f64> {
+    match s.parse::<f64>() {
+        Ok(v) if v > 0.0 => Ok(v),
+        _ => Err(anyhow!(
+            "Invalid Floating Point Value {} : Must Be Positive",
+            s
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        new_chunk(chunk_type, data.to_vec())
+    }
+
+    #[test]
+    fn test_offs_round_trip() {
+        let offs = Offs {
+            x: -20,
+            y: 300,
+            unit: 1,
+        };
+        assert_eq!(Offs::try_from(&offs.to_chunk()).unwrap(), offs);
+        assert!(Offs::try_from(&chunk("oFFs", &[0; 8])).is_err());
+        assert!(Offs::try_from(&chunk("oFFs", &[0, 0, 0, 0, 0, 0, 0, 0, 2])).is_err());
+    }
+
+    #[test]
+    fn test_scal() {
+        let scal = Scal::try_from(&chunk("sCAL", b"\x011.5\x002e-3")).unwrap();
+        assert_eq!(scal.unit, 1);
+        assert_eq!(scal.width_value().unwrap(), 1.5);
+        assert_eq!(scal.height_value().unwrap(), 0.002);
+        assert_eq!(scal.to_chunk().data(), b"\x011.5\x002e-3");
+
+        assert!(Scal::try_from(&chunk("sCAL", b"\x031\x001")).is_err());
+        assert!(Scal::try_from(&chunk("sCAL", b"\x01-1\x001")).is_err());
+        assert!(Scal::try_from(&chunk("sCAL", b"\x011")).is_err());
+    }
+
+    #[test]
+    fn test_pcal_round_trip() {
+        let pcal = Pcal {
+            name: "Elevation".to_string(),
+            x0: 0,
+            x1: 65535,
+            equation_type: 0,
+            unit: "m".to_string(),
+            parameters: vec!["-100".to_string(), "2.5".to_string()],
+        };
+        let parsed = Pcal::try_from(&pcal.to_chunk()).unwrap();
+        assert_eq!(parsed, pcal);
+    }
+
+    #[test]
+    fn test_pcal_parameter_count() {
+        let pcal = Pcal {
+            name: "Elevation".to_string(),
+            x0: 0,
+            x1: 1,
+            equation_type: 3,
+            unit: "m".to_string(),
+            parameters: vec!["1".to_string(), "2".to_string()],
+        };
+        assert!(Pcal::try_from(&pcal.to_chunk()).is_err());
+    }
+
+    #[test]
+    fn test_ster() {
+        assert_eq!(
+            Ster::try_from(&chunk("sTER", &[1])).unwrap(),
+            Ster::DivergingFuse
+        );
+        assert_eq!(Ster::CrossFuse.to_chunk().data(), &[0]);
+        assert!(Ster::try_from(&chunk("sTER", &[2])).is_err());
+        assert!(Ster::try_from(&chunk("sTER", &[0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_gifg() {
+        let gifg = Gifg::try_from(&chunk("gIFg", &[2, 1, 0, 10])).unwrap();
+        assert_eq!(
+            gifg,
+            Gifg {
+                disposal_method: 2,
+                user_input: true,
+                delay_time: 10,
+            }
+        );
+        assert_eq!(gifg.to_chunk().data(), &[2, 1, 0, 10]);
+        assert!(Gifg::try_from(&chunk("gIFg", &[2, 1, 0])).is_err());
+    }
+}
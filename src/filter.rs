@@ -0,0 +1,396 @@
+use crate::chunk::Chunk;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// A predicate over chunk metadata parsed from a small expression syntax,
+/// e.g. `type ~ "tE.t" && len > 1024 && !critical`.
+///
+/// * fields: `type` (string), `len`, `crc` and `entropy` (numbers)
+/// * flags: `critical`, `ancillary`, `public`, `private`, `safe_to_copy`
+/// * comparisons: `==`, `!=`, `<`, `<=`, `>`, `>=`, and `~` which matches
+///   `type` against a pattern supporting `.`, `*`, `^` and `$`
+/// * combinators: `!`, `&&`, `||` and parentheses
+#[derive(Debug, Clone, PartialEq)]
+pub struct Filter {
+    _expr: Expr,
+}
+
+impl Filter {
+    pub fn matches(&self, chunk: &Chunk) -> bool {
+        self._expr.eval(chunk)
+    }
+}
+
+impl FromStr for Filter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let tokens = tokenize(s)?;
+        let mut parser = Parser { tokens, pos: 0 };
+        let expr = parser.or()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(anyhow!(
+                "Invalid Filter Expression {} : Unexpected {:?}",
+                s,
+                parser.tokens[parser.pos]
+            ));
+        }
+        Ok(Filter { _expr: expr })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Str(String),
+    Num(f64),
+    Op(&'static str),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Type,
+    Len,
+    Crc,
+    Entropy,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Flag {
+    Critical,
+    Ancillary,
+    Public,
+    Private,
+    SafeToCopy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    Str(String),
+    Num(f64),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Expr {
+    Not(Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Flag(Flag),
+    Compare(Field, &'static str, Value),
+}
+
+impl Expr {
+    fn eval(&self, chunk: &Chunk) -> bool {
+        match self {
+            Expr::Not(e) => !e.eval(chunk),
+            Expr::And(a, b) => a.eval(chunk) && b.eval(chunk),
+            Expr::Or(a, b) => a.eval(chunk) || b.eval(chunk),
+            Expr::Flag(flag) => {
+                let t = chunk.chunk_type();
+                match flag {
+                    Flag::Critical => t.is_critical(),
+                    Flag::Ancillary => !t.is_critical(),
+                    Flag::Public => t.is_public(),
+                    Flag::Private => !t.is_public(),
+                    Flag::SafeToCopy => t.is_safe_to_copy(),
+                }
+            }
+            Expr::Compare(field, op, value) => match (field, value) {
+                (Field::Type, Value::Str(s)) => {
+                    let t = chunk.chunk_type().to_string();
+                    match *op {
+                        "~" => pattern_match(s.as_bytes(), t.as_bytes()),
+                        "==" => &t == s,
+                        "!=" => &t != s,
+                        _ => false,
+                    }
+                }
+                (Field::Len, Value::Num(n)) => compare(chunk.length() as f64, op, *n),
+                (Field::Crc, Value::Num(n)) => compare(chunk.crc() as f64, op, *n),
+                (Field::Entropy, Value::Num(n)) => compare(chunk.entropy(), op, *n),
+                _ => false,
+            },
+        }
+    }
+}
+
+fn compare(left: f64, op: &str, right: f64) -> bool {
+    match op {
+        "==" => left == right,
+        "!=" => left != right,
+        "<" => left < right,
+        "<=" => left <= right,
+        ">" => left > right,
+        ">=" => left >= right,
+        _ => false,
+    }
+}
+
+const OPERATORS: [&str; 13] = [
+    "&&", "||", "==", "!=", "<=", ">=", "<", ">", "~", "!", "(", ")", "=",
+];
+
+fn tokenize(s: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = s.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '"' {
+            let end = chars[i + 1..]
+                .iter()
+                .position(|c| *c == '"')
+                .ok_or_else(|| anyhow!("Invalid Filter Expression {} : Unterminated String", s))?;
+            tokens.push(Token::Str(chars[i + 1..i + 1 + end].iter().collect()));
+            i += end + 2;
+        } else if c.is_ascii_digit() {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_digit() || **c == '.')
+                .count();
+            let text: String = chars[i..i + len].iter().collect();
+            let num = text
+                .parse()
+                .map_err(|_| anyhow!("Invalid Filter Expression {} : Bad Number {}", s, text))?;
+            tokens.push(Token::Num(num));
+            i += len;
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let len = chars[i..]
+                .iter()
+                .take_while(|c| c.is_ascii_alphanumeric() || **c == '_')
+                .count();
+            tokens.push(Token::Ident(chars[i..i + len].iter().collect()));
+            i += len;
+        } else {
+            let rest: String = chars[i..].iter().take(2).collect();
+            let op = OPERATORS
+                .iter()
+                .find(|op| rest.starts_with(*op))
+                .ok_or_else(|| anyhow!("Invalid Filter Expression {} : Unexpected '{}'", s, c))?;
+            // A lone `=` is accepted as a shorthand for `==`.
+            tokens.push(Token::Op(if *op == "=" { "==" } else { op }));
+            i += op.len();
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek_op(&self, op: &str) -> bool {
+        matches!(self.tokens.get(self.pos), Some(Token::Op(o)) if *o == op)
+    }
+    fn next(&mut self) -> Result<Token> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or_else(|| anyhow!("Invalid Filter Expression : Unexpected End"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+    fn or(&mut self) -> Result<Expr> {
+        let mut left = self.and()?;
+        while self.peek_op("||") {
+            self.pos += 1;
+            left = Expr::Or(Box::new(left), Box::new(self.and()?));
+        }
+        Ok(left)
+    }
+    fn and(&mut self) -> Result<Expr> {
+        let mut left = self.unary()?;
+        while self.peek_op("&&") {
+            self.pos += 1;
+            left = Expr::And(Box::new(left), Box::new(self.unary()?));
+        }
+        Ok(left)
+    }
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek_op("!") {
+            self.pos += 1;
+            return Ok(Expr::Not(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next()? {
+            Token::Op("(") => {
+                let expr = self.or()?;
+                match self.next()? {
+                    Token::Op(")") => Ok(expr),
+                    t => Err(anyhow!(
+                        "Invalid Filter Expression : Expected ')' , Found {:?}",
+                        t
+                    )),
+                }
+            }
+            Token::Ident(name) => {
+                let flag = match name.as_str() {
+                    "critical" => Some(Flag::Critical),
+                    "ancillary" => Some(Flag::Ancillary),
+                    "public" => Some(Flag::Public),
+                    "private" => Some(Flag::Private),
+                    "safe_to_copy" => Some(Flag::SafeToCopy),
+                    _ => None,
+                };
+                if let Some(flag) = flag {
+                    return Ok(Expr::Flag(flag));
+                }
+                let field = match name.as_str() {
+                    "type" => Field::Type,
+                    "len" => Field::Len,
+                    "crc" => Field::Crc,
+                    "entropy" => Field::Entropy,
+                    _ => {
+                        return Err(anyhow!(
+                            "Invalid Filter Expression : Unknown Field {}",
+                            name
+                        ))
+                    }
+                };
+                let op = match self.next()? {
+                    Token::Op(op) if !matches!(op, "&&" | "||" | "!" | "(" | ")") => op,
+                    t => {
+                        return Err(anyhow!(
+                            "Invalid Filter Expression : Expected Operator After {} , Found {:?}",
+                            name,
+                            t
+                        ))
+                    }
+                };
+                let value = match self.next()? {
+                    Token::Str(s) => Value::Str(s),
+                    Token::Num(n) => Value::Num(n),
+                    t => {
+                        return Err(anyhow!(
+                            "Invalid Filter Expression : Expected Value , Found {:?}",
+                            t
+                        ))
+                    }
+                };
+                match (field, op, &value) {
+                    (Field::Type, "~" | "==" | "!=", Value::Str(_)) => {}
+                    (Field::Type, _, _) => {
+                        return Err(anyhow!(
+                            "Invalid Filter Expression : type Supports ~ == != With A String"
+                        ))
+                    }
+                    (_, "~", _) | (_, _, Value::Str(_)) => {
+                        return Err(anyhow!(
+                            "Invalid Filter Expression : {} Needs A Numeric Comparison",
+                            name
+                        ))
+                    }
+                    _ => {}
+                }
+                Ok(Expr::Compare(field, op, value))
+            }
+            t => Err(anyhow!("Invalid Filter Expression : Unexpected {:?}", t)),
+        }
+    }
+}
+
+/// Unanchored match of a minimal regular expression: `.` any character,
+/// `x*` zero or more of `x`, `^` and `$` anchors.
+fn pattern_match(pattern: &[u8], text: &[u8]) -> bool {
+    if let Some(rest) = pattern.strip_prefix(b"^") {
+        return match_here(rest, text);
+    }
+    (0..=text.len()).any(|i| match_here(pattern, &text[i..]))
+}
+
+fn match_here(pattern: &[u8], text: &[u8]) -> bool {
+    match pattern {
+        [] => true,
+        [b'$'] => text.is_empty(),
+        [c, b'*', rest @ ..] => {
+            let mut i = 0;
+            loop {
+                if match_here(rest, &text[i..]) {
+                    return true;
+                }
+                if i < text.len() && (*c == b'.' || text[i] == *c) {
+                    i += 1;
+                } else {
+                    return false;
+                }
+            }
+        }
+        [c, rest @ ..] => match text.split_first() {
+            Some((t, text_rest)) if *c == b'.' || c == t => match_here(rest, text_rest),
+            _ => false,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+
+    fn chunk(chunk_type: &str, len: usize) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), vec![0; len])
+    }
+
+    fn matches(expr: &str, chunk: &Chunk) -> bool {
+        Filter::from_str(expr).unwrap().matches(chunk)
+    }
+
+    #[test]
+    fn test_filter_example() {
+        let expr = r#"type ~ "tE.t" && len > 1024 && !critical"#;
+        assert!(matches(expr, &chunk("tEXt", 2000)));
+        assert!(!matches(expr, &chunk("tEXt", 10)));
+        assert!(!matches(expr, &chunk("iTXt", 2000)));
+    }
+
+    #[test]
+    fn test_filter_flags_and_grouping() {
+        let idat = chunk("IDAT", 4);
+        let rust = chunk("ruSt", 4);
+        assert!(matches("critical", &idat));
+        assert!(matches("private && safe_to_copy", &rust));
+        assert!(matches("!(public || critical)", &rust));
+        assert!(!matches("ancillary && public", &rust));
+    }
+
+    #[test]
+    fn test_filter_comparisons() {
+        let c = chunk("ruSt", 16);
+        assert!(matches("len == 16", &c));
+        assert!(matches("len >= 16 && len <= 16", &c));
+        assert!(matches("len != 15", &c));
+        assert!(matches(r#"type = "ruSt""#, &c));
+        assert!(matches(r#"type != "IDAT""#, &c));
+        assert!(matches("entropy < 0.5", &c));
+        assert!(matches(&format!("crc == {}", c.crc()), &c));
+    }
+
+    #[test]
+    fn test_filter_patterns() {
+        assert!(pattern_match(b"^ru", b"ruSt"));
+        assert!(!pattern_match(b"^uS", b"ruSt"));
+        assert!(pattern_match(b"St$", b"ruSt"));
+        assert!(pattern_match(b"r.*t$", b"ruSt"));
+        assert!(pattern_match(b"X*ru", b"ruSt"));
+        assert!(!pattern_match(b"^IDAT$", b"IDATx"));
+    }
+
+    #[test]
+    fn test_filter_errors() {
+        assert!(Filter::from_str("size > 3").is_err());
+        assert!(Filter::from_str("len >").is_err());
+        assert!(Filter::from_str(r#"len ~ "a""#).is_err());
+        assert!(Filter::from_str("type > 3").is_err());
+        assert!(Filter::from_str(r#"type ~ "abc"#).is_err());
+        assert!(Filter::from_str("(critical").is_err());
+        assert!(Filter::from_str("critical public").is_err());
+        assert!(Filter::from_str("len > 1 #").is_err());
+    }
+}
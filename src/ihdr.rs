@@ -0,0 +1,279 @@
+use crate::ancillary::{check_type, new_chunk};
+use crate::chunk::Chunk;
+use crate::codec::{read_u32, write_u32};
+use anyhow::{anyhow, Result};
+
+/// Image header (`IHDR`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ihdr {
+    pub width: u32,
+    pub height: u32,
+    pub bit_depth: u8,
+    /// 0 = grayscale, 2 = truecolor, 3 = indexed, 4 = grayscale with alpha,
+    /// 6 = truecolor with alpha.
+    pub color_type: u8,
+    pub compression: u8,
+    pub filter: u8,
+    /// 0 = none, 1 = Adam7.
+    pub interlace: u8,
+}
+
+impl Ihdr {
+    pub const CHUNK_TYPE: &'static str = "IHDR";
+    const MAX_DIMENSION: u32 = (1 << 31) - 1;
+
+    /// Checks the fields against the combinations the specification allows.
+    pub fn validate(&self) -> Result<()> {
+        for (name, value) in [("Width", self.width), ("Height", self.height)] {
+            if value == 0 || value > Ihdr::MAX_DIMENSION {
+                return Err(anyhow!(
+                    "Invalid IHDR Chunk : {} {} , Should Be 1..={}",
+                    name,
+                    value,
+                    Ihdr::MAX_DIMENSION
+                ));
+            }
+        }
+        let depths: &[u8] = match self.color_type {
+            0 => &[1, 2, 4, 8, 16],
+            3 => &[1, 2, 4, 8],
+            2 | 4 | 6 => &[8, 16],
+            _ => {
+                return Err(anyhow!(
+                    "Invalid IHDR Chunk : Color Type {}",
+                    self.color_type
+                ))
+            }
+        };
+        if !depths.contains(&self.bit_depth) {
+            return Err(anyhow!(
+                "Invalid IHDR Chunk : Bit Depth {} , Should Be One Of {:?} For Color Type {}",
+                self.bit_depth,
+                depths,
+                self.color_type
+            ));
+        }
+        if self.compression != 0 {
+            return Err(anyhow!(
+                "Invalid IHDR Chunk : Compression Method {}",
+                self.compression
+            ));
+        }
+        if self.filter != 0 {
+            return Err(anyhow!(
+                "Invalid IHDR Chunk : Filter Method {}",
+                self.filter
+            ));
+        }
+        if self.interlace > 1 {
+            return Err(anyhow!(
+                "Invalid IHDR Chunk : Interlace Method {}",
+                self.interlace
+            ));
+        }
+        Ok(())
+    }
+    /// Samples per pixel for the color type, or 0 for an invalid one.
+    pub fn channels(&self) -> u8 {
+        match self.color_type {
+            0 | 3 => 1,
+            4 => 2,
+            2 => 3,
+            6 => 4,
+            _ => 0,
+        }
+    }
+    pub fn color_type_name(&self) -> &'static str {
+        match self.color_type {
+            0 => "grayscale",
+            2 => "truecolor",
+            3 => "indexed",
+            4 => "grayscale with alpha",
+            6 => "truecolor with alpha",
+            _ => "unknown",
+        }
+    }
+    /// Size of the image data once inflated: every scanline of every
+    /// interlace pass, each with its leading filter-type byte.
+    pub fn raw_data_size(&self) -> u64 {
+        const ADAM7: [(u32, u32, u32, u32); 7] = [
+            (0, 0, 8, 8),
+            (4, 0, 8, 8),
+            (0, 4, 4, 8),
+            (2, 0, 4, 4),
+            (0, 2, 2, 4),
+            (1, 0, 2, 2),
+            (0, 1, 1, 2),
+        ];
+        let passes: &[(u32, u32, u32, u32)] = if self.interlace == 1 {
+            &ADAM7
+        } else {
+            &[(0, 0, 1, 1)]
+        };
+        let bits_per_pixel = self.channels() as u64 * self.bit_depth as u64;
+        passes
+            .iter()
+            .map(|(x0, y0, dx, dy)| {
+                let width = self.width.saturating_sub(*x0).div_ceil(*dx) as u64;
+                let height = self.height.saturating_sub(*y0).div_ceil(*dy) as u64;
+                if width == 0 {
+                    0
+                } else {
+                    height * (1 + (width * bits_per_pixel).div_ceil(8))
+                }
+            })
+            .sum()
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        write_u32(&mut data, self.width);
+        write_u32(&mut data, self.height);
+        data.extend_from_slice(&[
+            self.bit_depth,
+            self.color_type,
+            self.compression,
+            self.filter,
+            self.interlace,
+        ]);
+        new_chunk(Ihdr::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Ihdr {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Ihdr::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() != 13 {
+            return Err(anyhow!(
+                "Invalid IHDR Chunk : Length {} , Should Be 13",
+                data.len()
+            ));
+        }
+        let ihdr = Ihdr {
+            width: read_u32(data, 0)?,
+            height: read_u32(data, 4)?,
+            bit_depth: data[8],
+            color_type: data[9],
+            compression: data[10],
+            filter: data[11],
+            interlace: data[12],
+        };
+        ihdr.validate()?;
+        Ok(ihdr)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ihdr() -> Ihdr {
+        Ihdr {
+            width: 50,
+            height: 40,
+            bit_depth: 8,
+            color_type: 6,
+            compression: 0,
+            filter: 0,
+            interlace: 0,
+        }
+    }
+
+    #[test]
+    fn test_ihdr_round_trip() {
+        let chunk = ihdr().to_chunk();
+        assert_eq!(chunk.data(), &[0, 0, 0, 50, 0, 0, 0, 40, 8, 6, 0, 0, 0]);
+        assert_eq!(Ihdr::try_from(&chunk).unwrap(), ihdr());
+    }
+
+    #[test]
+    fn test_ihdr_invalid_combinations() {
+        let cases = [
+            Ihdr { width: 0, ..ihdr() },
+            Ihdr {
+                height: 1 << 31,
+                ..ihdr()
+            },
+            Ihdr {
+                bit_depth: 4,
+                ..ihdr()
+            },
+            Ihdr {
+                color_type: 3,
+                bit_depth: 16,
+                ..ihdr()
+            },
+            Ihdr {
+                color_type: 5,
+                ..ihdr()
+            },
+            Ihdr {
+                compression: 1,
+                ..ihdr()
+            },
+            Ihdr {
+                filter: 1,
+                ..ihdr()
+            },
+            Ihdr {
+                interlace: 2,
+                ..ihdr()
+            },
+        ];
+        for case in cases {
+            assert!(case.validate().is_err(), "{:?}", case);
+            assert!(Ihdr::try_from(&case.to_chunk()).is_err());
+        }
+        assert!(Ihdr {
+            color_type: 0,
+            bit_depth: 1,
+            ..ihdr()
+        }
+        .validate()
+        .is_ok());
+    }
+
+    #[test]
+    fn test_ihdr_raw_data_size() {
+        let ihdr = ihdr();
+        assert_eq!(ihdr.channels(), 4);
+        assert_eq!(ihdr.color_type_name(), "truecolor with alpha");
+        assert_eq!(ihdr.raw_data_size(), 40 * (1 + 50 * 4));
+
+        let gray = Ihdr {
+            bit_depth: 1,
+            color_type: 0,
+            ..ihdr
+        };
+        assert_eq!(gray.raw_data_size(), 40 * (1 + 7));
+
+        let tiny = Ihdr {
+            width: 1,
+            height: 1,
+            interlace: 1,
+            ..ihdr
+        };
+        assert_eq!(tiny.raw_data_size(), 5);
+        let interlaced = Ihdr {
+            width: 8,
+            height: 8,
+            interlace: 1,
+            ..ihdr
+        };
+        // Passes of 1x1, 1x1, 2x1, 2x2, 4x2, 4x4 and 8x4 pixels.
+        assert_eq!(
+            interlaced.raw_data_size(),
+            5 + 5 + 9 + 2 * 9 + 2 * 17 + 4 * 17 + 4 * 33
+        );
+    }
+
+    #[test]
+    fn test_ihdr_wrong_chunk() {
+        let chunk = new_chunk("IHDR", vec![0; 12]);
+        assert!(Ihdr::try_from(&chunk).is_err());
+        let chunk = new_chunk("IDAT", ihdr().to_chunk().data().to_vec());
+        assert!(Ihdr::try_from(&chunk).is_err());
+    }
+}
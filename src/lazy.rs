@@ -0,0 +1,210 @@
+use crate::checksum::{Checksum, PNG_CRC};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::codec::read_u32;
+use crate::png::{Png, PngError};
+use anyhow::{anyhow, Result};
+use std::io::{Read, Seek, SeekFrom};
+
+/// Framing of one chunk as read by [`LazyPng::open`]; the data itself has
+/// not been read or verified.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkHeader {
+    /// File offset of the length field.
+    pub offset: u64,
+    pub length: u32,
+    pub chunk_type: ChunkType,
+    pub crc: u32,
+}
+
+/// A PNG whose chunk headers are read up front while chunk data is read,
+/// CRC-checked and cached only when asked for. Finding a small chunk in a
+/// large file therefore costs one seek per chunk instead of reading IDAT.
+pub struct LazyPng<R: Read + Seek> {
+    _reader: R,
+    _headers: Vec<ChunkHeader>,
+    _data: Vec<Option<Vec<u8>>>,
+}
+
+impl<R: Read + Seek> LazyPng<R> {
+    /// Checks the signature and walks the chunk headers to the end of the
+    /// input, seeking over chunk data.
+    pub fn open(mut reader: R) -> Result<Self> {
+        let end = reader.seek(SeekFrom::End(0))?;
+        reader.seek(SeekFrom::Start(0))?;
+        let mut signature = vec![0u8; end.min(8) as usize];
+        reader.read_exact(&mut signature)?;
+        if signature != Png::STANDARD_HEADER {
+            return Err(PngError::BadSignature { found: signature }.into());
+        }
+        let mut headers = Vec::new();
+        let mut offset = 8;
+        while offset < end {
+            let bad = |reason: String| PngError::BadChunk {
+                offset: offset as usize,
+                reason,
+            };
+            let mut head = [0u8; 8];
+            if offset + 12 > end {
+                return Err(bad("Truncated Header".to_string()).into());
+            }
+            reader.read_exact(&mut head)?;
+            let length = read_u32(&head, 0)?;
+            let next = offset + 12 + length as u64;
+            if next > end {
+                return Err(bad(format!("Truncated Data : Length {}", length)).into());
+            }
+            reader.seek(SeekFrom::Current(length as i64))?;
+            let mut crc = [0u8; 4];
+            reader.read_exact(&mut crc)?;
+            headers.push(ChunkHeader {
+                offset,
+                length,
+                chunk_type: ChunkType::try_from([head[4], head[5], head[6], head[7]]).unwrap(),
+                crc: read_u32(&crc, 0)?,
+            });
+            offset = next;
+        }
+        Ok(LazyPng {
+            _reader: reader,
+            _data: vec![None; headers.len()],
+            _headers: headers,
+        })
+    }
+    pub fn headers(&self) -> &[ChunkHeader] {
+        &self._headers
+    }
+    /// Index of the first chunk of type `chunk_type`.
+    pub fn position(&self, chunk_type: &str) -> Option<usize> {
+        self._headers
+            .iter()
+            .position(|h| h.chunk_type.to_string() == chunk_type)
+    }
+    /// Data of chunk `index`, read and CRC-checked on first access.
+    pub fn data(&mut self, index: usize) -> Result<&[u8]> {
+        let header = self._headers.get(index).ok_or_else(|| {
+            anyhow!(
+                "Invalid Chunk Index {} : Png Has {} Chunks",
+                index,
+                self._headers.len()
+            )
+        })?;
+        if self._data[index].is_none() {
+            let mut data = vec![0u8; header.length as usize];
+            self._reader.seek(SeekFrom::Start(header.offset + 8))?;
+            self._reader.read_exact(&mut data)?;
+            let computed = PNG_CRC.checksum(&header.chunk_type.bytes(), &data);
+            if computed != header.crc {
+                return Err(PngError::BadChunk {
+                    offset: header.offset as usize,
+                    reason: format!("Wrong CRC {} , Should Be {}", header.crc, computed),
+                }
+                .into());
+            }
+            self._data[index] = Some(data);
+        }
+        Ok(self._data[index].as_deref().unwrap())
+    }
+    /// Chunk `index` with its data loaded.
+    pub fn chunk(&mut self, index: usize) -> Result<Chunk> {
+        let data = self.data(index)?.to_vec();
+        let header = &self._headers[index];
+        Ok(Chunk::new_raw(
+            header.length,
+            header.chunk_type.clone(),
+            data,
+            header.crc,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    fn png_bytes() -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1; 4096]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), b"secret".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes()
+    }
+
+    /// Counts bytes actually read, to check that skipped data is not read.
+    struct Counting {
+        inner: Cursor<Vec<u8>>,
+        read: usize,
+    }
+
+    impl Read for Counting {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let n = self.inner.read(buf)?;
+            self.read += n;
+            Ok(n)
+        }
+    }
+
+    impl Seek for Counting {
+        fn seek(&mut self, pos: SeekFrom) -> std::io::Result<u64> {
+            self.inner.seek(pos)
+        }
+    }
+
+    #[test]
+    fn test_lazy_headers_and_data() {
+        let reader = Counting {
+            inner: Cursor::new(png_bytes()),
+            read: 0,
+        };
+        let mut png = LazyPng::open(reader).unwrap();
+        let types: Vec<String> = png
+            .headers()
+            .iter()
+            .map(|h| h.chunk_type.to_string())
+            .collect();
+        assert_eq!(types, vec!["IHDR", "IDAT", "ruSt", "IEND"]);
+        assert_eq!(png.headers()[1].offset, 33);
+        assert_eq!(png.headers()[1].length, 4096);
+
+        let index = png.position("ruSt").unwrap();
+        assert_eq!(png.data(index).unwrap(), b"secret");
+        assert!(png._reader.read < 100);
+
+        let chunk = png.chunk(index).unwrap();
+        assert_eq!(
+            chunk.as_bytes(),
+            Png::try_from(png_bytes().as_ref())
+                .unwrap()
+                .chunks()
+                .nth(2)
+                .unwrap()
+                .as_bytes()
+        );
+        assert!(png.data(4).is_err());
+    }
+
+    #[test]
+    fn test_lazy_crc_checked_on_access() {
+        let mut bytes = png_bytes();
+        bytes[33 + 8 + 100] ^= 1;
+        let mut png = LazyPng::open(Cursor::new(bytes)).unwrap();
+        assert!(png.data(0).is_ok());
+        let err = png.data(1).err().unwrap();
+        assert_eq!(err.downcast_ref::<PngError>().unwrap().offset(), 33);
+        assert!(png.data(2).is_ok());
+    }
+
+    #[test]
+    fn test_lazy_open_errors() {
+        assert!(LazyPng::open(Cursor::new(b"GIF".to_vec())).is_err());
+        let bytes = png_bytes();
+        let err = LazyPng::open(Cursor::new(bytes[..100].to_vec()))
+            .err()
+            .unwrap();
+        assert_eq!(err.downcast_ref::<PngError>().unwrap().offset(), 33);
+    }
+}
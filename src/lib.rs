@@ -0,0 +1,31 @@
+pub mod ancillary;
+pub mod cancel;
+pub mod carve;
+pub mod checksum;
+pub mod chunk;
+pub mod chunk_type;
+pub mod codec;
+pub mod corpus;
+pub mod diff;
+pub mod doctor;
+pub mod embed;
+pub mod events;
+pub mod extension;
+pub mod filter;
+pub mod ihdr;
+pub mod lazy;
+pub mod png;
+pub mod polyglot;
+pub mod preview;
+pub mod proprietary;
+pub mod provenance;
+pub mod registry;
+pub mod repair;
+pub mod report;
+pub mod scan;
+pub mod stream;
+pub mod text;
+pub mod timeline;
+pub mod units;
+pub mod verify;
+pub mod vfs;
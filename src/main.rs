@@ -1,14 +1,47 @@
-extern crate core;
-
 mod args;
-mod chunk;
-mod chunk_type;
 mod commands;
-mod png;
+mod json;
+mod schema;
+
+use args::PngMeArgs;
+use pngme::cancel::CancellationToken;
+use pngme::vfs::{ModeFs, OpenMode, StdFs};
 
 pub type Error = Box<dyn std::error::Error>;
 pub type Result<T> = std::result::Result<T, Error>;
 
 fn main() -> Result<()> {
-    todo!()
+    let (global, command) = args::parse();
+    let mode = if global.read_only {
+        OpenMode::ReadOnly
+    } else {
+        OpenMode::ReadWrite
+    };
+    let vfs = ModeFs::new(StdFs, mode);
+    let token = global
+        .timeout
+        .map_or_else(CancellationToken::new, CancellationToken::with_timeout);
+    match command {
+        PngMeArgs::Encode(args) => commands::encode(&vfs, &args, global.parse, &token),
+        PngMeArgs::Decode(args) => commands::decode(&vfs, &args, global.parse, &token),
+        PngMeArgs::Remove(args) => commands::remove(&vfs, &args, global.parse, &token),
+        PngMeArgs::Print(args) => commands::print(&vfs, &args, global.parse, &token),
+        PngMeArgs::Scan(args) => commands::scan(&vfs, &args, global.parse, &token),
+        PngMeArgs::Report(args) => commands::report(&vfs, &args, &token),
+        PngMeArgs::Verify(args) => commands::verify(&vfs, &args),
+        PngMeArgs::Repair(args) => commands::repair(&vfs, &args),
+        PngMeArgs::Salvage(args) => commands::salvage(&vfs, &args),
+        PngMeArgs::Doctor(args) => commands::doctor(&vfs, &args),
+        PngMeArgs::Info(args) => commands::info(&vfs, &args, global.parse, &token),
+        PngMeArgs::Diff(args) => commands::diff(&vfs, &args, global.parse, &token),
+        PngMeArgs::Strip(args) => commands::strip(&vfs, &args, global.parse, &token),
+        PngMeArgs::Resplit(args) => commands::resplit(&vfs, &args, global.parse, &token),
+        PngMeArgs::Extract(args) => commands::extract(&vfs, &args, global.parse, &token),
+        PngMeArgs::Carve(args) => commands::carve(&vfs, &args, &token),
+        PngMeArgs::Inject(args) => commands::inject(&vfs, &args, global.parse, &token),
+        PngMeArgs::BenchCorpus(args) => commands::bench_corpus(&vfs, &args, &token),
+        PngMeArgs::Timeline(args) => commands::timeline(&vfs, &args, global.parse, &token),
+        PngMeArgs::List(args) => commands::list(&vfs, &args, global.parse, &token),
+        PngMeArgs::Schema(args) => commands::schema(&args),
+    }
 }
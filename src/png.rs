@@ -1,10 +1,40 @@
+use crate::cancel::CancellationToken;
 use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::codec::read_u32;
+use crate::events::{Event, Listener};
+use crate::ihdr::Ihdr;
+use crate::registry;
+use crate::vfs::Vfs;
 use anyhow::{anyhow, Result};
-use std::fmt::{write, Display, Formatter};
-use std::ptr::addr_of_mut;
+use std::fmt::{Display, Formatter};
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+use std::sync::Arc;
 
+/// Chunks are stored behind `Arc` so that [`Png::snapshot`] and `clone` only
+/// copy pointers; edits replace whole chunks and never mutate shared data.
+#[derive(Clone)]
 pub struct Png {
-    _chunks: Vec<Chunk>,
+    _chunks: Vec<Arc<Chunk>>,
+    _warnings: Vec<Warning>,
+}
+
+/// A saved chunk list, taken with [`Png::snapshot`] and brought back with
+/// [`Png::restore`]. Shares chunk data with the `Png` it came from.
+#[derive(Clone)]
+pub struct Snapshot {
+    _chunks: Vec<Arc<Chunk>>,
+}
+
+impl Snapshot {
+    pub fn len(&self) -> usize {
+        self._chunks.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self._chunks.is_empty()
+    }
 }
 
 impl Png {
@@ -20,20 +50,72 @@ impl Png {
         }
         true
     }
-    fn from_chunks(_chunks: Vec<Chunk>) -> Png {
-        Png { _chunks }
+    pub fn from_chunks(chunks: Vec<Chunk>) -> Png {
+        Png {
+            _chunks: chunks.into_iter().map(Arc::new).collect(),
+            _warnings: Vec::new(),
+        }
     }
-    fn append_chunk(&mut self, chunk: Chunk) {
-        self._chunks.push(chunk)
+    pub fn append_chunk(&mut self, chunk: Chunk) {
+        self._chunks.push(Arc::new(chunk))
     }
-    fn remove_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
+    /// Inserts `chunk` so that it becomes the chunk at index `position`.
+    /// Fails if that would put it before a leading IHDR (other than Apple's
+    /// CgBI marker) or after IEND.
+    pub fn insert_chunk_at(&mut self, position: usize, chunk: Chunk) -> Result<()> {
+        if position > self._chunks.len() {
+            return Err(anyhow!(
+                "Invalid Insert Position {} : Png Has {} Chunks",
+                position,
+                self._chunks.len()
+            ));
+        }
+        let is_cgbi = chunk.chunk_type().to_string() == "CgBI";
+        if position == 0 && self.is_type_at(0, "IHDR") && !is_cgbi {
+            return Err(anyhow!(
+                "Invalid Insert Position {} : IHDR Must Stay First",
+                position
+            ));
+        }
+        if (0..position).any(|i| self.is_type_at(i, "IEND")) {
+            return Err(anyhow!(
+                "Invalid Insert Position {} : IEND Must Stay Last",
+                position
+            ));
+        }
+        self._chunks.insert(position, Arc::new(chunk));
+        Ok(())
+    }
+    /// Inserts `chunk` right after IHDR, or at the front if there is none.
+    pub fn insert_after_header(&mut self, chunk: Chunk) {
+        let start = if self.is_type_at(0, "CgBI") { 1 } else { 0 };
+        let position = if self.is_type_at(start, "IHDR") {
+            start + 1
+        } else {
+            start
+        };
+        self._chunks.insert(position, Arc::new(chunk));
+    }
+    /// Inserts `chunk` right before IEND, or at the end if there is none.
+    pub fn insert_before_end(&mut self, chunk: Chunk) {
+        let position = self
+            ._chunks
+            .iter()
+            .position(|c| c.chunk_type().to_string() == "IEND")
+            .unwrap_or(self._chunks.len());
+        self._chunks.insert(position, Arc::new(chunk));
+    }
+    fn is_type_at(&self, position: usize, chunk_type: &str) -> bool {
+        self._chunks
+            .get(position)
+            .is_some_and(|c| c.chunk_type().to_string() == chunk_type)
+    }
+    /// Removes and returns the first chunk of type `chunk_type`; later chunks
+    /// of the same type stay in place.
+    pub fn remove_first_chunk(&mut self, chunk_type: &str) -> Result<Chunk> {
         for (i, v) in self._chunks.iter().enumerate() {
-            if String::from_utf8(v.chunk_type().bytes().to_vec())
-                .unwrap()
-                .to_string()
-                == chunk_type
-            {
-                return Ok(self._chunks.remove(i));
+            if v.chunk_type().to_string() == chunk_type {
+                return Ok(unshare(self._chunks.remove(i)));
             }
         }
         Err(anyhow!(
@@ -41,60 +123,715 @@ impl Png {
             chunk_type
         ))
     }
-    fn header(&self) -> &[u8; 8] {
+    /// Removes and returns the chunk of type `chunk_type` preceded by
+    /// `index` others of that type, counting from 0.
+    pub fn remove_chunk_at(&mut self, chunk_type: &str, index: usize) -> Result<Chunk> {
+        let position = self.position_of(chunk_type, index)?;
+        Ok(unshare(self._chunks.remove(position)))
+    }
+    /// Puts `chunk` in place of the chunk that [`Png::remove_chunk_at`]
+    /// would remove and returns the old one.
+    pub fn replace_chunk_at(
+        &mut self,
+        chunk_type: &str,
+        index: usize,
+        chunk: Chunk,
+    ) -> Result<Chunk> {
+        let position = self.position_of(chunk_type, index)?;
+        Ok(unshare(std::mem::replace(
+            &mut self._chunks[position],
+            Arc::new(chunk),
+        )))
+    }
+    /// Position in the chunk list of occurrence `index` of `chunk_type`.
+    fn position_of(&self, chunk_type: &str, index: usize) -> Result<usize> {
+        let positions: Vec<usize> = (0..self._chunks.len())
+            .filter(|i| self.is_type_at(*i, chunk_type))
+            .collect();
+        positions.get(index).copied().ok_or_else(|| {
+            anyhow!(
+                "Invalid Chunk Index {} : Png Has {} {} Chunks",
+                index,
+                positions.len(),
+                chunk_type
+            )
+        })
+    }
+    /// Removes every chunk of type `chunk_type` and returns them in file
+    /// order; empty if there were none.
+    pub fn remove_chunks_by_type(&mut self, chunk_type: &str) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self._chunks)
+            .into_iter()
+            .partition(|v| v.chunk_type().to_string() == chunk_type);
+        self._chunks = kept;
+        removed.into_iter().map(unshare).collect()
+    }
+    /// Removes every chunk for which `predicate` holds and returns them in
+    /// file order.
+    pub fn remove_chunks_where(&mut self, predicate: impl Fn(&Chunk) -> bool) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self._chunks)
+            .into_iter()
+            .partition(|v| predicate(v));
+        self._chunks = kept;
+        removed.into_iter().map(unshare).collect()
+    }
+    /// Removes every ancillary chunk whose type is not listed in `keep` and
+    /// returns them in file order. Critical chunks are always kept.
+    pub fn remove_ancillary_chunks(&mut self, keep: &[String]) -> Vec<Chunk> {
+        let (removed, kept) = std::mem::take(&mut self._chunks)
+            .into_iter()
+            .partition(|v| {
+                let chunk_type = v.chunk_type();
+                !chunk_type.is_critical() && !keep.contains(&chunk_type.to_string())
+            });
+        self._chunks = kept;
+        removed.into_iter().map(unshare).collect()
+    }
+    pub fn signature(&self) -> &[u8; 8] {
         &Png::STANDARD_HEADER
     }
-    fn chunks(&self) -> &[Chunk] {
-        self._chunks.as_ref()
+    /// The decoded IHDR chunk. Fails if there is none or it is invalid.
+    pub fn header(&self) -> Result<Ihdr> {
+        let chunk = self
+            .first_chunk_by_type(Ihdr::CHUNK_TYPE)
+            .ok_or_else(|| anyhow!("Invalid Png : No IHDR Chunk"))?;
+        Ihdr::try_from(chunk)
     }
-    fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
-        for v in &self._chunks {
-            if String::from_utf8(v.chunk_type().bytes().to_vec())
-                .unwrap()
-                .to_string()
-                == chunk_type
+    pub fn chunks(&self) -> Chunks<'_> {
+        Chunks {
+            _inner: self._chunks.iter(),
+        }
+    }
+    /// Same as [`Png::first_chunk_by_type`].
+    pub fn chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        self.first_chunk_by_type(chunk_type)
+    }
+    pub fn first_chunk_by_type(&self, chunk_type: &str) -> Option<&Chunk> {
+        self.chunks()
+            .find(|v| v.chunk_type().to_string() == chunk_type)
+    }
+    /// Every chunk of type `chunk_type`, in file order.
+    pub fn chunks_by_type(&self, chunk_type: &str) -> Vec<&Chunk> {
+        self.chunks()
+            .filter(|v| v.chunk_type().to_string() == chunk_type)
+            .collect()
+    }
+    /// Saves the current chunk list. Costs one pointer copy per chunk, so it
+    /// can be taken before every edit to build an undo history.
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            _chunks: self._chunks.clone(),
+        }
+    }
+    /// Checks chunk ordering against the specification: IHDR first, IEND
+    /// last, PLTE before IDAT, IDAT chunks consecutive and single-instance
+    /// chunks not repeated. Returns every violation found, in file order.
+    pub fn validate_order(&self) -> Vec<OrderViolation> {
+        let types: Vec<String> = self.chunks().map(|c| c.chunk_type().to_string()).collect();
+        let mut violations = Vec::new();
+        let last = types.len().saturating_sub(1);
+        // Apple's CgBI marker is the one chunk allowed before IHDR.
+        let header = if types.first().is_some_and(|t| t == "CgBI") {
+            1
+        } else {
+            0
+        };
+        let first_idat = types.iter().position(|t| t == "IDAT");
+        let mut previous_idat = None;
+        for (index, chunk_type) in types.iter().enumerate() {
+            match chunk_type.as_str() {
+                "IHDR" if index != header => {
+                    violations.push(OrderViolation::IhdrNotFirst { index })
+                }
+                "IEND" if index != last => violations.push(OrderViolation::IendNotLast { index }),
+                "PLTE" if first_idat.is_some_and(|i| i < index) => {
+                    violations.push(OrderViolation::PlteAfterIdat { index })
+                }
+                "IDAT" => {
+                    if previous_idat.is_some_and(|p| p + 1 != index) {
+                        violations.push(OrderViolation::IdatNotConsecutive { index });
+                    }
+                    previous_idat = Some(index);
+                }
+                _ => {}
+            }
+            if SINGLE_INSTANCE.contains(&chunk_type.as_str()) && types[..index].contains(chunk_type)
             {
-                return Some(v);
+                violations.push(OrderViolation::Duplicate {
+                    index,
+                    chunk_type: chunk_type.clone(),
+                });
             }
         }
-        None
+        if types.get(header).map(String::as_str) != Some("IHDR") {
+            violations.insert(0, OrderViolation::MissingIhdr);
+        }
+        if !types.iter().any(|t| t == "IEND") {
+            violations.push(OrderViolation::MissingIend);
+        }
+        violations
     }
-    fn as_bytes(&self) -> Vec<u8> {
-        Png::STANDARD_HEADER
+    /// Anomalies tolerated while parsing, in file order. Empty for a `Png`
+    /// built from chunks.
+    pub fn warnings(&self) -> &[Warning] {
+        &self._warnings
+    }
+    /// Replaces the chunk list with the one saved in `snapshot`.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        self._chunks = snapshot._chunks.clone();
+    }
+    /// Parses as much of a damaged file as possible: after a broken chunk,
+    /// scans forward byte by byte for the next plausible chunk header (ASCII
+    /// letter type with a matching CRC) and carries on from there.
+    pub fn recover(value: &[u8]) -> Recovery {
+        let mut _chunks = Vec::new();
+        let mut _warnings = Vec::new();
+        let mut skipped = Vec::new();
+        let mut pos = if value.len() >= 8 && Png::header_is_valid(&value[..8]) {
+            8
+        } else {
+            0
+        };
+        let mut skip_start = None;
+        let mut ended = false;
+        while pos < value.len() {
+            match plausible_chunk(&value[pos..]) {
+                Some(chunk) => {
+                    if let Some(start) = skip_start.take() {
+                        skipped.push(start..pos);
+                    }
+                    _warnings.extend(chunk_warning(ended, &chunk, pos));
+                    ended |= is_end(&chunk);
+                    pos += chunk.length() as usize + 12;
+                    _chunks.push(Arc::new(chunk));
+                }
+                None => {
+                    skip_start.get_or_insert(pos);
+                    pos += 1;
+                }
+            }
+        }
+        if let Some(start) = skip_start {
+            skipped.push(start..value.len());
+        }
+        _warnings.extend(skipped.iter().map(|r| Warning::SkippedBytes {
+            offset: r.start,
+            len: r.len(),
+        }));
+        _warnings.sort_by_key(|w| w.offset());
+        Recovery {
+            png: Png { _chunks, _warnings },
+            skipped,
+        }
+    }
+    /// Rebuilds the best viewable PNG from damaged input: keeps the chunks
+    /// [`Png::recover`] could verify up to the first IEND, moves IHDR to the
+    /// front and regenerates IEND. Fails if no IHDR or IDAT survived.
+    pub fn salvage(value: &[u8]) -> Result<Png> {
+        let recovered = Png::recover(value).png;
+        let _warnings = recovered._warnings;
+        let mut _chunks: Vec<Arc<Chunk>> = recovered
+            ._chunks
+            .into_iter()
+            .take_while(|c| c.chunk_type().to_string() != "IEND")
+            .collect();
+        let ihdr = _chunks
             .iter()
-            .cloned()
-            .chain(self._chunks.iter().map(|c| c.as_bytes()).flatten())
-            .collect()
+            .position(|c| c.chunk_type().to_string() == "IHDR")
+            .ok_or_else(|| anyhow!("Invalid Salvage : No Intact IHDR Chunk"))?;
+        let ihdr = _chunks.remove(ihdr);
+        _chunks.retain(|c| c.chunk_type().to_string() != "IHDR");
+        _chunks.insert(0, ihdr);
+        if !_chunks.iter().any(|c| c.chunk_type().to_string() == "IDAT") {
+            return Err(anyhow!("Invalid Salvage : No Intact IDAT Chunk"));
+        }
+        _chunks.push(Arc::new(Chunk::new(
+            ChunkType::from_str("IEND")?,
+            Vec::new(),
+        )));
+        Ok(Png { _chunks, _warnings })
     }
-}
-
-impl TryFrom<&[u8]> for Png {
-    type Error = anyhow::Error;
-    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
-        let (sh, cks) = value.split_at(8);
+    /// Rewrites the image data into IDAT chunks of at most `size` bytes, or a
+    /// single IDAT if `size` is `None`. The concatenated compressed stream is
+    /// unchanged, so the decoded image is too.
+    pub fn resplit_idat(&mut self, size: Option<usize>) -> Result<()> {
+        if size == Some(0) {
+            return Err(anyhow!("Invalid IDAT Size 0 : Must Be Positive"));
+        }
+        let is_idat = |c: &Arc<Chunk>| c.chunk_type().to_string() == "IDAT";
+        let first = match self._chunks.iter().position(is_idat) {
+            Some(first) => first,
+            None => return Err(anyhow!("Invalid Resplit : No IDAT Chunk in Png")),
+        };
+        let count = self._chunks[first..]
+            .iter()
+            .take_while(|c| is_idat(c))
+            .count();
+        if self._chunks[first + count..].iter().any(is_idat) {
+            return Err(anyhow!("Invalid Resplit : IDAT Chunks Are Not Consecutive"));
+        }
+        let stream: Vec<u8> = self._chunks[first..first + count]
+            .iter()
+            .flat_map(|c| c.data().iter().cloned())
+            .collect();
+        let idat = ChunkType::from_str("IDAT")?;
+        let pieces: Vec<Arc<Chunk>> = if stream.is_empty() {
+            vec![Arc::new(Chunk::new(idat, Vec::new()))]
+        } else {
+            stream
+                .chunks(size.unwrap_or(stream.len()))
+                .map(|piece| Arc::new(Chunk::new(idat.clone(), piece.to_vec())))
+                .collect()
+        };
+        self._chunks.splice(first..first + count, pieces);
+        Ok(())
+    }
+    /// Reads and parses the file at `path` in `vfs`.
+    pub fn from_file<P: AsRef<Path>>(vfs: &dyn Vfs, path: P) -> Result<Png> {
+        let path = path.as_ref();
+        let bytes = vfs
+            .read(path)
+            .map_err(|e| anyhow!("Invalid Png File {} : {}", path.display(), e))?;
+        Png::try_from(bytes.as_ref())
+    }
+    /// Writes the PNG to `path` in `vfs` with [`Vfs::write_atomic`], so an
+    /// interrupted save leaves the old file intact.
+    pub fn save_to_file<P: AsRef<Path>>(&self, vfs: &dyn Vfs, path: P) -> Result<()> {
+        vfs.write_atomic(path.as_ref(), &self.as_bytes())
+    }
+    /// Like [`Png::try_from_with`], but checks `token` before each chunk and
+    /// fails with [`Cancelled`](crate::cancel::Cancelled) once it is cancelled.
+    pub fn try_from_cancellable(
+        value: &[u8],
+        opts: &ParseOptions,
+        token: &CancellationToken,
+    ) -> Result<Png> {
+        let png = Png::parse_while(
+            value,
+            opts,
+            |_, _| !token.is_cancelled(),
+            &mut |_: &Event| {},
+        )?;
+        token.check()?;
+        Ok(png)
+    }
+    /// Parses `value` as [`Png::try_from`] does, with the checks relaxed or
+    /// tightened by `opts`. Tolerated problems become [`Warning`]s.
+    pub fn try_from_with(value: &[u8], opts: &ParseOptions) -> Result<Png> {
+        Png::parse_while(value, opts, |_, _| true, &mut |_: &Event| {})
+    }
+    /// Parses `value` as [`Png::try_from_with`] does, reporting progress
+    /// after each chunk and each warning as it is found to `listener`.
+    pub fn try_from_with_listener(
+        value: &[u8],
+        opts: &ParseOptions,
+        listener: &mut dyn Listener,
+    ) -> Result<Png> {
+        Png::parse_while(value, opts, |_, _| true, listener)
+    }
+    /// Parses only the first `n` chunks with `opts` and ignores the rest of
+    /// the input, which is not checked at all.
+    pub fn parse_prefix(value: &[u8], opts: &ParseOptions, n: usize) -> Result<Png> {
+        Png::parse_while(value, opts, |count, _| count < n, &mut |_: &Event| {})
+    }
+    /// Parses IHDR and the metadata chunks in front of the image data,
+    /// stopping at the first IDAT without reading it.
+    pub fn parse_metadata(value: &[u8]) -> Result<Png> {
+        Png::parse_while(
+            value,
+            &ParseOptions::default(),
+            |_, chunk_type| chunk_type != b"IDAT",
+            &mut |_: &Event| {},
+        )
+    }
+    /// Parses chunks for as long as `keep(chunks parsed so far, type bytes of
+    /// the next chunk)` holds, telling `listener` how far it got.
+    fn parse_while(
+        value: &[u8],
+        opts: &ParseOptions,
+        keep: impl Fn(usize, &[u8]) -> bool,
+        listener: &mut dyn Listener,
+    ) -> Result<Png> {
+        let (sh, cks) = value.split_at(8.min(value.len()));
         if !Png::header_is_valid(sh) {
-            return Err(anyhow!(
-                "Invalid Png String : Standard Header Should Be {:?}, Found {:?}",
-                Png::STANDARD_HEADER,
-                sh
-            ));
+            return Err(PngError::BadSignature { found: sh.to_vec() }.into());
         }
         let mut _chunks = Vec::new();
+        let mut _warnings = Vec::new();
 
-        if cks.len() == 0 {
-            return Ok(Png { _chunks });
+        if cks.is_empty() {
+            return Ok(Png { _chunks, _warnings });
         }
         let mut pos = 0;
-        while let (_, ck) = cks.split_at(pos) {
-            if ck.len() == 0 {
+        let mut reported = 0;
+        let mut ended = false;
+        loop {
+            let (_, ck) = cks.split_at(pos);
+            if ck.is_empty() || !keep(_chunks.len(), &ck[4.min(ck.len())..8.min(ck.len())]) {
                 break;
             }
-            let chunk = Chunk::try_from(ck)?;
+            let offset = pos + 8;
+            let bad = |reason: String| PngError::BadChunk { offset, reason };
+            if let (Some(max), Ok(length)) = (opts.max_chunk_size, read_u32(ck, 0)) {
+                if length > max {
+                    return Err(bad(format!("Length {} : Exceeds Maximum {}", length, max)).into());
+                }
+            }
+            let chunk = match Chunk::try_from(ck) {
+                Ok(chunk) => chunk,
+                Err(_) if opts.allow_trailing_garbage && ended => {
+                    _warnings.push(Warning::TrailingData {
+                        offset,
+                        len: ck.len(),
+                    });
+                    break;
+                }
+                Err(e) => match unchecked_chunk(ck) {
+                    Some(chunk) if !opts.strict_crc => {
+                        _warnings.push(Warning::BadCrc {
+                            offset,
+                            chunk_type: chunk.chunk_type().to_string(),
+                        });
+                        chunk
+                    }
+                    _ => return Err(bad(e.to_string()).into()),
+                },
+            };
+            if !opts.allow_unknown_critical && is_unknown_critical(&chunk) {
+                return Err(bad(format!(
+                    "Unknown Critical Chunk Type {}",
+                    chunk.chunk_type()
+                ))
+                .into());
+            }
+            _warnings.extend(chunk_warning(ended, &chunk, offset));
+            ended |= is_end(&chunk);
             pos += chunk.length() as usize + 12;
-            _chunks.push(chunk);
-            //break;
+            _chunks.push(Arc::new(chunk));
+            for warning in &_warnings[reported..] {
+                listener.on_event(&Event::Warning(warning.clone()));
+            }
+            reported = _warnings.len();
+            listener.on_event(&Event::Progress {
+                done: pos + 8,
+                total: value.len(),
+            });
+        }
+        for warning in &_warnings[reported..] {
+            listener.on_event(&Event::Warning(warning.clone()));
+        }
+        Ok(Png { _chunks, _warnings })
+    }
+    pub fn as_bytes(&self) -> Vec<u8> {
+        Png::STANDARD_HEADER
+            .iter()
+            .cloned()
+            .chain(self._chunks.iter().flat_map(|c| c.as_bytes()))
+            .collect()
+    }
+}
+
+/// Iterator over the chunks of a [`Png`], returned by [`Png::chunks`].
+pub struct Chunks<'a> {
+    _inner: std::slice::Iter<'a, Arc<Chunk>>,
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = &'a Chunk;
+    fn next(&mut self) -> Option<Self::Item> {
+        self._inner.next().map(|c| c.as_ref())
+    }
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self._inner.size_hint()
+    }
+}
+
+impl DoubleEndedIterator for Chunks<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self._inner.next_back().map(|c| c.as_ref())
+    }
+}
+
+impl ExactSizeIterator for Chunks<'_> {}
+
+impl<'a> IntoIterator for &'a Png {
+    type Item = &'a Chunk;
+    type IntoIter = Chunks<'a>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.chunks()
+    }
+}
+
+impl IntoIterator for Png {
+    type Item = Chunk;
+    type IntoIter = std::iter::Map<std::vec::IntoIter<Arc<Chunk>>, fn(Arc<Chunk>) -> Chunk>;
+    fn into_iter(self) -> Self::IntoIter {
+        self._chunks
+            .into_iter()
+            .map(unshare as fn(Arc<Chunk>) -> Chunk)
+    }
+}
+
+/// Chunk types the specification allows at most once per file.
+const SINGLE_INSTANCE: [&str; 17] = [
+    "IHDR", "PLTE", "IEND", "cHRM", "gAMA", "iCCP", "sBIT", "sRGB", "cICP", "mDCV", "cLLI", "bKGD",
+    "hIST", "tRNS", "pHYs", "tIME", "eXIf",
+];
+
+/// A chunk ordering rule broken, found by [`Png::validate_order`]. `index`
+/// is the position of the offending chunk.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum OrderViolation {
+    /// The first chunk is not IHDR.
+    MissingIhdr,
+    MissingIend,
+    IhdrNotFirst {
+        index: usize,
+    },
+    IendNotLast {
+        index: usize,
+    },
+    PlteAfterIdat {
+        index: usize,
+    },
+    /// An IDAT chunk is separated from the previous one by other chunks.
+    IdatNotConsecutive {
+        index: usize,
+    },
+    Duplicate {
+        index: usize,
+        chunk_type: String,
+    },
+}
+
+impl Display for OrderViolation {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderViolation::MissingIhdr => write!(f, "Invalid Chunk Order : IHDR Must Be First"),
+            OrderViolation::MissingIend => write!(f, "Invalid Chunk Order : No IEND Chunk"),
+            OrderViolation::IhdrNotFirst { index } => {
+                write!(f, "Invalid Chunk Order : IHDR At Index {}", index)
+            }
+            OrderViolation::IendNotLast { index } => write!(
+                f,
+                "Invalid Chunk Order : IEND At Index {} Is Not Last",
+                index
+            ),
+            OrderViolation::PlteAfterIdat { index } => write!(
+                f,
+                "Invalid Chunk Order : PLTE At Index {} Follows IDAT",
+                index
+            ),
+            OrderViolation::IdatNotConsecutive { index } => write!(
+                f,
+                "Invalid Chunk Order : IDAT At Index {} Is Not Consecutive",
+                index
+            ),
+            OrderViolation::Duplicate { index, chunk_type } => write!(
+                f,
+                "Invalid Chunk Order : Duplicate {} At Index {}",
+                chunk_type, index
+            ),
+        }
+    }
+}
+
+/// Something odd that parsing tolerated instead of failing on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Warning {
+    /// [`Png::recover`] skipped `len` unreadable bytes at `offset`.
+    SkippedBytes { offset: usize, len: usize },
+    /// A critical chunk type that is not in the registry was kept.
+    UnknownCriticalChunk { offset: usize, chunk_type: String },
+    /// A chunk follows IEND.
+    AfterEnd { offset: usize, chunk_type: String },
+    /// A chunk with a wrong CRC was kept.
+    BadCrc { offset: usize, chunk_type: String },
+    /// `len` unparseable bytes after IEND were ignored.
+    TrailingData { offset: usize, len: usize },
+}
+
+impl Warning {
+    /// File offset the warning refers to.
+    pub fn offset(&self) -> usize {
+        match self {
+            Warning::SkippedBytes { offset, .. }
+            | Warning::UnknownCriticalChunk { offset, .. }
+            | Warning::AfterEnd { offset, .. }
+            | Warning::BadCrc { offset, .. }
+            | Warning::TrailingData { offset, .. } => *offset,
         }
-        Ok(Png { _chunks })
+    }
+}
+
+impl Display for Warning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Warning::SkippedBytes { offset, len } => {
+                write!(f, "Skipped {} Bytes At Offset {} : Unreadable", len, offset)
+            }
+            Warning::UnknownCriticalChunk { offset, chunk_type } => write!(
+                f,
+                "Unknown Critical Chunk {} At Offset {} : Decoders May Reject It",
+                chunk_type, offset
+            ),
+            Warning::AfterEnd { offset, chunk_type } => write!(
+                f,
+                "Chunk {} At Offset {} : Found After IEND",
+                chunk_type, offset
+            ),
+            Warning::BadCrc { offset, chunk_type } => write!(
+                f,
+                "Chunk {} At Offset {} : Wrong CRC Ignored",
+                chunk_type, offset
+            ),
+            Warning::TrailingData { offset, len } => write!(
+                f,
+                "Ignored {} Bytes At Offset {} : Trailing Data After IEND",
+                len, offset
+            ),
+        }
+    }
+}
+
+/// Warning for `chunk`, read at file `offset`: it follows IEND if `ended`
+/// says an IEND came before it, or it is an unknown critical chunk.
+fn chunk_warning(ended: bool, chunk: &Chunk, offset: usize) -> Option<Warning> {
+    let chunk_type = chunk.chunk_type().to_string();
+    if ended {
+        Some(Warning::AfterEnd { offset, chunk_type })
+    } else if is_unknown_critical(chunk) {
+        Some(Warning::UnknownCriticalChunk { offset, chunk_type })
+    } else {
+        None
+    }
+}
+
+fn is_end(chunk: &Chunk) -> bool {
+    chunk.chunk_type().bytes() == *b"IEND"
+}
+
+fn is_unknown_critical(chunk: &Chunk) -> bool {
+    chunk.chunk_type().is_critical() && registry::lookup(&chunk.chunk_type().to_string()).is_none()
+}
+
+/// Reads a chunk whose framing is intact without checking its CRC.
+fn unchecked_chunk(value: &[u8]) -> Option<Chunk> {
+    let length = read_u32(value, 0).ok()?;
+    let data = value.get(8..8 + length as usize)?;
+    let crc = read_u32(value, 8 + length as usize).ok()?;
+    let chunk_type = ChunkType::try_from(<[u8; 4]>::try_from(&value[4..8]).unwrap()).unwrap();
+    Some(Chunk::new_raw(length, chunk_type, data.to_vec(), crc))
+}
+
+/// Knobs for [`Png::try_from_with`]. The default matches [`Png::try_from`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// Reject chunks with a wrong CRC. When off they are kept, with a
+    /// [`Warning::BadCrc`].
+    pub strict_crc: bool,
+    /// Stop at unparseable bytes after IEND instead of failing, with a
+    /// [`Warning::TrailingData`].
+    pub allow_trailing_garbage: bool,
+    /// Keep critical chunk types that are not in the registry (with a
+    /// warning) instead of failing.
+    pub allow_unknown_critical: bool,
+    /// Reject chunks whose declared length is larger.
+    pub max_chunk_size: Option<u32>,
+}
+
+impl Default for ParseOptions {
+    fn default() -> Self {
+        ParseOptions {
+            strict_crc: true,
+            allow_trailing_garbage: false,
+            allow_unknown_critical: true,
+            max_chunk_size: None,
+        }
+    }
+}
+
+impl ParseOptions {
+    /// For encoders: nothing unusual is accepted.
+    pub fn strict() -> Self {
+        ParseOptions {
+            allow_unknown_critical: false,
+            max_chunk_size: Some((1 << 31) - 1),
+            ..ParseOptions::default()
+        }
+    }
+    /// For forensics on damaged files: keep everything that can be framed.
+    pub fn permissive() -> Self {
+        ParseOptions {
+            strict_crc: false,
+            allow_trailing_garbage: true,
+            ..ParseOptions::default()
+        }
+    }
+}
+
+/// Why parsing a PNG failed. Carried inside the `anyhow::Error` returned by
+/// [`Png::try_from`]; use `downcast_ref::<PngError>()` to inspect it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PngError {
+    /// The input does not start with [`Png::STANDARD_HEADER`].
+    BadSignature { found: Vec<u8> },
+    /// The chunk starting at file offset `offset` is malformed.
+    BadChunk { offset: usize, reason: String },
+}
+
+impl PngError {
+    /// File offset where parsing failed.
+    pub fn offset(&self) -> usize {
+        match self {
+            PngError::BadSignature { .. } => 0,
+            PngError::BadChunk { offset, .. } => *offset,
+        }
+    }
+}
+
+impl Display for PngError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PngError::BadSignature { found } => write!(
+                f,
+                "Invalid Png String : Standard Header Should Be {:?}, Found {:?}",
+                Png::STANDARD_HEADER,
+                found
+            ),
+            PngError::BadChunk { offset, reason } => {
+                write!(f, "Invalid Png Chunk At Offset {} : {}", offset, reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for PngError {}
+
+/// Result of [`Png::recover`]: the chunks that could be read plus the byte
+/// ranges (file offsets) that had to be skipped to find them.
+pub struct Recovery {
+    pub png: Png,
+    pub skipped: Vec<Range<usize>>,
+}
+
+/// Takes a chunk out of its `Arc`, copying it only if a snapshot still
+/// shares it.
+fn unshare(chunk: Arc<Chunk>) -> Chunk {
+    Arc::try_unwrap(chunk).unwrap_or_else(|shared| (*shared).clone())
+}
+
+fn plausible_chunk(value: &[u8]) -> Option<Chunk> {
+    if value.len() < 12 || !value[4..8].iter().all(|b| b.is_ascii_alphabetic()) {
+        return None;
+    }
+    Chunk::try_from(value).ok()
+}
+
+impl TryFrom<&[u8]> for Png {
+    type Error = anyhow::Error;
+    fn try_from(value: &[u8]) -> std::result::Result<Self, Self::Error> {
+        Png::try_from_with(value, &ParseOptions::default())
     }
 }
 
@@ -106,21 +843,20 @@ impl Display for Png {
 #[cfg(test)]
 mod tests {
     use std::convert::TryFrom;
-    use std::str::FromStr;
 
     use crate::chunk::Chunk;
     use crate::chunk_type::ChunkType;
+    use crate::vfs::{MemFs, StdFs};
+    use std::fs;
 
     use super::*;
 
     fn testing_chunks() -> Vec<Chunk> {
-        let mut chunks = Vec::new();
-
-        chunks.push(chunk_from_strings("FrSt", "I am the first chunk").unwrap());
-        chunks.push(chunk_from_strings("miDl", "I am another chunk").unwrap());
-        chunks.push(chunk_from_strings("LASt", "I am the last chunk").unwrap());
-
-        chunks
+        vec![
+            chunk_from_strings("FrSt", "I am the first chunk").unwrap(),
+            chunk_from_strings("miDl", "I am another chunk").unwrap(),
+            chunk_from_strings("LASt", "I am the last chunk").unwrap(),
+        ]
     }
 
     fn testing_png() -> Png {
@@ -158,9 +894,9 @@ mod tests {
             .copied()
             .collect();
 
-        let png = Png::try_from(bytes.as_ref()).unwrap();
+        let png = Png::try_from(bytes.as_ref());
 
-        //assert!(png.is_ok());
+        assert!(png.is_ok());
     }
 
     #[test]
@@ -203,6 +939,268 @@ mod tests {
         assert!(png.is_err());
     }
 
+    #[test]
+    fn test_error_offsets() {
+        let err = Png::try_from(&b"GIF"[..]).err().unwrap();
+        assert_eq!(
+            err.downcast_ref::<PngError>(),
+            Some(&PngError::BadSignature {
+                found: b"GIF".to_vec()
+            })
+        );
+
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[44] ^= 0xFF;
+        let err = Png::try_from(bytes.as_ref()).err().unwrap();
+        assert_eq!(err.downcast_ref::<PngError>().unwrap().offset(), 33);
+
+        bytes.truncate(40);
+        let err = Png::try_from(bytes.as_ref()).err().unwrap();
+        assert_eq!(err.downcast_ref::<PngError>().unwrap().offset(), 33);
+    }
+
+    #[test]
+    fn test_from_file() {
+        let path = std::env::temp_dir().join(format!("pngme_png_{}.png", std::process::id()));
+        fs::write(&path, PNG_FILE).unwrap();
+        let png = Png::from_file(&StdFs, &path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+        assert!(Png::from_file(&StdFs, &path).is_err());
+
+        let vfs = MemFs::new();
+        vfs.write(Path::new("a.png"), &PNG_FILE).unwrap();
+        let png = Png::from_file(&vfs, "a.png").unwrap();
+        png.save_to_file(&vfs, "b.png").unwrap();
+        assert_eq!(vfs.read(Path::new("b.png")).unwrap(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_warnings() {
+        // The sample file carries a secret message in a critical "RuSt" chunk.
+        let rust = Warning::UnknownCriticalChunk {
+            offset: 4776,
+            chunk_type: "RuSt".to_string(),
+        };
+        let png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert_eq!(png.warnings().len(), 1);
+        assert_eq!(png.warnings()[0], rust);
+
+        let mut bytes = PNG_FILE.to_vec();
+        let end = bytes.len();
+        bytes.extend(chunk_from_strings("teXt", "late").unwrap().as_bytes());
+        let png = Png::try_from(bytes.as_ref()).unwrap();
+        assert_eq!(
+            png.warnings(),
+            &[
+                rust,
+                Warning::AfterEnd {
+                    offset: end,
+                    chunk_type: "teXt".to_string()
+                }
+            ]
+        );
+
+        let png = testing_png();
+        let png = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        let warnings: Vec<String> = png.warnings().iter().map(|w| w.to_string()).collect();
+        assert_eq!(
+            warnings,
+            vec![
+                "Unknown Critical Chunk FrSt At Offset 8 : Decoders May Reject It",
+                "Unknown Critical Chunk LASt At Offset 70 : Decoders May Reject It"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_recover_warnings() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[44] ^= 0xFF;
+        let png = Png::recover(&bytes).png;
+        assert_eq!(
+            png.warnings()[0],
+            Warning::SkippedBytes {
+                offset: 33,
+                len: 13
+            }
+        );
+        assert_eq!(Png::salvage(&bytes).unwrap().warnings().len(), 2);
+    }
+
+    #[test]
+    fn test_header() {
+        let header = Png::try_from(&PNG_FILE[..]).unwrap().header().unwrap();
+        assert_eq!((header.width, header.height), (50, 50));
+        assert_eq!(header.interlace, 0);
+        assert!(testing_png().header().is_err());
+    }
+
+    #[test]
+    fn test_save_to_file() {
+        let dir = std::env::temp_dir().join(format!("pngme_save_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("out.png");
+        fs::write(&path, b"old contents").unwrap();
+
+        Png::try_from(&PNG_FILE[..])
+            .unwrap()
+            .save_to_file(&StdFs, &path)
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), PNG_FILE.to_vec());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+
+        assert!(testing_png()
+            .save_to_file(&StdFs, dir.join("missing/out.png"))
+            .is_err());
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_try_from_cancellable() {
+        let token = CancellationToken::new();
+        assert!(Png::try_from_cancellable(&PNG_FILE, &ParseOptions::default(), &token).is_ok());
+        token.cancel();
+        let err = Png::try_from_cancellable(&PNG_FILE, &ParseOptions::default(), &token)
+            .err()
+            .unwrap();
+        assert!(err.is::<crate::cancel::Cancelled>());
+    }
+
+    #[test]
+    fn test_validate_order_ok() {
+        assert!(Png::try_from(&PNG_FILE[..])
+            .unwrap()
+            .validate_order()
+            .is_empty());
+        assert!(framed_png().validate_order().is_empty());
+    }
+
+    #[test]
+    fn test_validate_order_violations() {
+        let png = Png::from_chunks(
+            [
+                "IDAT", "IHDR", "IDAT", "tEXt", "IDAT", "PLTE", "gAMA", "gAMA",
+            ]
+            .iter()
+            .map(|t| chunk_from_strings(t, "").unwrap())
+            .collect(),
+        );
+        assert_eq!(
+            png.validate_order(),
+            vec![
+                OrderViolation::MissingIhdr,
+                OrderViolation::IhdrNotFirst { index: 1 },
+                OrderViolation::IdatNotConsecutive { index: 2 },
+                OrderViolation::IdatNotConsecutive { index: 4 },
+                OrderViolation::PlteAfterIdat { index: 5 },
+                OrderViolation::Duplicate {
+                    index: 7,
+                    chunk_type: "gAMA".to_string()
+                },
+                OrderViolation::MissingIend,
+            ]
+        );
+
+        let mut png = framed_png();
+        png.append_chunk(chunk_from_strings("IEND", "").unwrap());
+        let violations: Vec<String> = png.validate_order().iter().map(|v| v.to_string()).collect();
+        assert_eq!(
+            violations,
+            vec![
+                "Invalid Chunk Order : IEND At Index 2 Is Not Last",
+                "Invalid Chunk Order : Duplicate IEND At Index 3"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_try_from_with_crc() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[44] ^= 0xFF;
+        assert!(Png::try_from_with(&bytes, &ParseOptions::default()).is_err());
+
+        let png = Png::try_from_with(&bytes, &ParseOptions::permissive()).unwrap();
+        assert!(png.chunk_by_type("sRGB").is_some());
+        assert_eq!(
+            png.warnings()[0],
+            Warning::BadCrc {
+                offset: 33,
+                chunk_type: "sRGB".to_string()
+            }
+        );
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_try_from_with_listener() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes[44] ^= 0xFF;
+        let mut events = Vec::new();
+        let png = Png::try_from_with_listener(
+            &bytes,
+            &ParseOptions::permissive(),
+            &mut |event: &Event| events.push(event.clone()),
+        )
+        .unwrap();
+
+        let progress: Vec<usize> = events
+            .iter()
+            .filter_map(|e| match e {
+                Event::Progress { done, total } => {
+                    assert_eq!(*total, bytes.len());
+                    Some(*done)
+                }
+                _ => None,
+            })
+            .collect();
+        assert_eq!(progress.len(), png.chunks().len());
+        assert_eq!(progress[0], 33);
+        assert_eq!(progress.last(), Some(&bytes.len()));
+
+        let warnings: Vec<&Event> = events
+            .iter()
+            .filter(|e| matches!(e, Event::Warning(_)))
+            .collect();
+        assert_eq!(warnings.len(), png.warnings().len());
+        assert_eq!(events[1], Event::Warning(png.warnings()[0].clone()));
+    }
+
+    #[test]
+    fn test_try_from_with_trailing_garbage() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.extend([1, 2, 3]);
+        assert!(Png::try_from(bytes.as_ref()).is_err());
+
+        let png = Png::try_from_with(&bytes, &ParseOptions::permissive()).unwrap();
+        assert_eq!(
+            png.warnings().last(),
+            Some(&Warning::TrailingData {
+                offset: PNG_FILE.len(),
+                len: 3
+            })
+        );
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_try_from_with_strict() {
+        // The sample file contains an unregistered critical "RuSt" chunk.
+        let err = Png::try_from_with(&PNG_FILE, &ParseOptions::strict())
+            .err()
+            .unwrap();
+        assert_eq!(err.downcast_ref::<PngError>().unwrap().offset(), 4776);
+
+        let opts = ParseOptions {
+            max_chunk_size: Some(100),
+            ..ParseOptions::default()
+        };
+        assert!(Png::try_from_with(&PNG_FILE, &opts).is_err());
+        let framed = framed_png().as_bytes();
+        assert!(Png::try_from_with(&framed, &ParseOptions::strict()).is_ok());
+    }
+
     #[test]
     fn test_list_chunks() {
         let png = testing_png();
@@ -210,6 +1208,28 @@ mod tests {
         assert_eq!(chunks.len(), 3);
     }
 
+    #[test]
+    fn test_iterate_chunks() {
+        let png = testing_png();
+        let found = png.chunks().find(|c| c.chunk_type().is_public()).unwrap();
+        assert_eq!(&found.chunk_type().to_string(), "LASt");
+
+        let mut count = 0;
+        for chunk in &png {
+            assert!(chunk.data_as_string().is_ok());
+            count += 1;
+        }
+        assert_eq!(count, 3);
+        assert_eq!(
+            &png.chunks().next_back().unwrap().chunk_type().to_string(),
+            "LASt"
+        );
+
+        let owned: Vec<Chunk> = png.into_iter().collect();
+        assert_eq!(owned.len(), 3);
+        assert_eq!(&owned[1].chunk_type().to_string(), "miDl");
+    }
+
     #[test]
     fn test_chunk_by_type() {
         let png = testing_png();
@@ -218,6 +1238,23 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "I am the first chunk");
     }
 
+    #[test]
+    fn test_chunks_by_type() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("miDl", "I am a second middle").unwrap());
+        let found = png.chunks_by_type("miDl");
+        assert_eq!(found.len(), 2);
+        assert_eq!(&found[1].data_as_string().unwrap(), "I am a second middle");
+        assert_eq!(
+            &png.first_chunk_by_type("miDl")
+                .unwrap()
+                .data_as_string()
+                .unwrap(),
+            "I am another chunk"
+        );
+        assert!(png.chunks_by_type("NoNe").is_empty());
+    }
+
     #[test]
     fn test_append_chunk() {
         let mut png = testing_png();
@@ -227,15 +1264,150 @@ mod tests {
         assert_eq!(&chunk.data_as_string().unwrap(), "Message");
     }
 
+    fn framed_png() -> Png {
+        Png::from_chunks(vec![
+            chunk_from_strings("IHDR", "header").unwrap(),
+            chunk_from_strings("IDAT", "data").unwrap(),
+            chunk_from_strings("IEND", "").unwrap(),
+        ])
+    }
+
+    fn types(png: &Png) -> Vec<String> {
+        png.chunks().map(|c| c.chunk_type().to_string()).collect()
+    }
+
     #[test]
-    fn test_remove_chunk() {
+    fn test_insert_chunk_at() {
+        let mut png = framed_png();
+        png.insert_chunk_at(1, chunk_from_strings("teSt", "x").unwrap())
+            .unwrap();
+        assert_eq!(types(&png), vec!["IHDR", "teSt", "IDAT", "IEND"]);
+
+        assert!(png
+            .insert_chunk_at(0, chunk_from_strings("teSt", "x").unwrap())
+            .is_err());
+        assert!(png
+            .insert_chunk_at(4, chunk_from_strings("teSt", "x").unwrap())
+            .is_err());
+        assert!(png
+            .insert_chunk_at(5, chunk_from_strings("teSt", "x").unwrap())
+            .is_err());
+        assert_eq!(png.chunks().len(), 4);
+    }
+
+    #[test]
+    fn test_insert_after_header_and_before_end() {
+        let mut png = framed_png();
+        png.insert_after_header(chunk_from_strings("gAMA", "g").unwrap());
+        png.insert_before_end(chunk_from_strings("ruSt", "secret").unwrap());
+        assert_eq!(types(&png), vec!["IHDR", "gAMA", "IDAT", "ruSt", "IEND"]);
+
+        let mut png = testing_png();
+        png.insert_after_header(chunk_from_strings("gAMA", "g").unwrap());
+        png.insert_before_end(chunk_from_strings("ruSt", "secret").unwrap());
+        assert_eq!(types(&png), vec!["gAMA", "FrSt", "miDl", "LASt", "ruSt"]);
+
+        let mut png = framed_png();
+        png.insert_chunk_at(0, chunk_from_strings("CgBI", "flag").unwrap())
+            .unwrap();
+        png.insert_after_header(chunk_from_strings("gAMA", "g").unwrap());
+        assert_eq!(types(&png), vec!["CgBI", "IHDR", "gAMA", "IDAT", "IEND"]);
+        assert!(png.validate_order().is_empty());
+    }
+
+    #[test]
+    fn test_remove_first_chunk() {
         let mut png = testing_png();
         png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
-        png.remove_chunk("TeSt").unwrap();
+        png.remove_first_chunk("TeSt").unwrap();
         let chunk = png.chunk_by_type("TeSt");
         assert!(chunk.is_none());
     }
 
+    #[test]
+    fn test_remove_first_chunk_keeps_later_duplicates() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am a duplicate").unwrap());
+        let removed = png.remove_first_chunk("FrSt").unwrap();
+        assert_eq!(&removed.data_as_string().unwrap(), "I am the first chunk");
+        let chunk = png.chunk_by_type("FrSt").unwrap();
+        assert_eq!(&chunk.data_as_string().unwrap(), "I am a duplicate");
+        assert!(png.remove_first_chunk("NoNe").is_err());
+    }
+
+    #[test]
+    fn test_remove_chunks_by_type() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("FrSt", "I am a duplicate").unwrap());
+        let removed = png.remove_chunks_by_type("FrSt");
+        assert_eq!(removed.len(), 2);
+        assert_eq!(&removed[1].data_as_string().unwrap(), "I am a duplicate");
+        assert!(png.chunk_by_type("FrSt").is_none());
+        assert_eq!(png.chunks().len(), 2);
+        assert!(png.remove_chunks_by_type("NoNe").is_empty());
+    }
+
+    #[test]
+    fn test_remove_chunks_where() {
+        let mut png = testing_png();
+        let removed = png.remove_chunks_where(|c| c.length() < 19);
+        let types: Vec<String> = removed.iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["miDl"]);
+        assert_eq!(png.chunks().len(), 2);
+        assert!(png.remove_chunks_where(|_| false).is_empty());
+    }
+
+    #[test]
+    fn test_remove_chunk_at() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("miDl", "second").unwrap());
+        png.append_chunk(chunk_from_strings("miDl", "third").unwrap());
+        let removed = png.remove_chunk_at("miDl", 1).unwrap();
+        assert_eq!(&removed.data_as_string().unwrap(), "second");
+        let data: Vec<String> = png
+            .chunks_by_type("miDl")
+            .iter()
+            .map(|c| c.data_as_string().unwrap())
+            .collect();
+        assert_eq!(data, vec!["I am another chunk", "third"]);
+        assert!(png.remove_chunk_at("miDl", 2).is_err());
+        assert!(png.remove_chunk_at("NoNe", 0).is_err());
+    }
+
+    #[test]
+    fn test_replace_chunk_at() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("miDl", "second").unwrap());
+        let old = png
+            .replace_chunk_at("miDl", 1, chunk_from_strings("miDl", "edited").unwrap())
+            .unwrap();
+        assert_eq!(&old.data_as_string().unwrap(), "second");
+        let last = png.chunks().next_back().unwrap();
+        assert_eq!(&last.data_as_string().unwrap(), "edited");
+        assert_eq!(png.chunks().len(), 4);
+        let err = png
+            .replace_chunk_at("miDl", 5, chunk_from_strings("miDl", "x").unwrap())
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid Chunk Index 5 : Png Has 2 miDl Chunks"
+        );
+    }
+
+    #[test]
+    fn test_remove_ancillary_chunks() {
+        let mut png = testing_png();
+        png.append_chunk(chunk_from_strings("tEXt", "kept").unwrap());
+        png.append_chunk(chunk_from_strings("IEND", "").unwrap());
+        let removed = png.remove_ancillary_chunks(&["tEXt".to_string()]);
+        let types: Vec<String> = removed.iter().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["miDl"]);
+        let types: Vec<String> = png.chunks().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["FrSt", "LASt", "tEXt", "IEND"]);
+        assert_eq!(png.remove_ancillary_chunks(&[]).len(), 1);
+    }
+
     #[test]
     fn test_png_from_image_file() {
         let png = Png::try_from(&PNG_FILE[..]);
@@ -246,10 +1418,207 @@ mod tests {
     fn test_as_bytes() {
         let png = Png::try_from(&PNG_FILE[..]).unwrap();
         let actual = png.as_bytes();
-        let expected: Vec<u8> = PNG_FILE.iter().copied().collect();
+        let expected: Vec<u8> = PNG_FILE.to_vec();
         assert_eq!(actual, expected);
     }
 
+    #[test]
+    fn test_recover_skips_garbage() {
+        let chunks = testing_chunks();
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        bytes.extend(chunks[0].as_bytes());
+        let garbage_start = bytes.len();
+        bytes.extend([0xDE, 0xAD, 0xBE, 0xEF, 1, 2, 3]);
+        let garbage_end = bytes.len();
+        bytes.extend(chunks[1].as_bytes());
+        bytes.extend(chunks[2].as_bytes());
+
+        let recovery = Png::recover(&bytes);
+        assert_eq!(recovery.png.chunks().len(), 3);
+        assert_eq!(recovery.skipped, vec![garbage_start..garbage_end]);
+    }
+
+    #[test]
+    fn test_recover_bad_crc() {
+        let chunks = testing_chunks();
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        bytes.extend(chunks[0].as_bytes());
+        let broken_start = bytes.len();
+        let mut broken = chunks[1].as_bytes();
+        let last = broken.len() - 1;
+        broken[last] ^= 0xFF;
+        bytes.extend(broken);
+        let broken_end = bytes.len();
+        bytes.extend(chunks[2].as_bytes());
+
+        let recovery = Png::recover(&bytes);
+        let types: Vec<String> = recovery
+            .png
+            .chunks()
+            .map(|c| c.chunk_type().to_string())
+            .collect();
+        assert_eq!(types, vec!["FrSt", "LASt"]);
+        assert_eq!(recovery.skipped, vec![broken_start..broken_end]);
+    }
+
+    #[test]
+    fn test_recover_truncated_tail() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.truncate(bytes.len() - 6);
+
+        let recovery = Png::recover(&bytes);
+        assert!(recovery.png.chunk_by_type("IEND").is_none());
+        assert!(recovery.png.chunk_by_type("IDAT").is_some());
+        assert_eq!(recovery.skipped.len(), 1);
+        assert_eq!(recovery.skipped[0].end, bytes.len());
+    }
+
+    #[test]
+    fn test_salvage_regenerates_iend() {
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.truncate(bytes.len() - 6);
+
+        let png = Png::salvage(&bytes).unwrap();
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_salvage_drops_broken_chunks() {
+        let mut bytes = PNG_FILE.to_vec();
+        // Corrupt the CRC of the sRGB chunk right after IHDR.
+        bytes[44] ^= 0xFF;
+        bytes.extend(
+            chunk_from_strings("TaIl", "after the end")
+                .unwrap()
+                .as_bytes(),
+        );
+
+        let png = Png::salvage(&bytes).unwrap();
+        assert!(png.chunk_by_type("sRGB").is_none());
+        assert!(png.chunk_by_type("TaIl").is_none());
+        assert_eq!(
+            &png.chunks().next().unwrap().chunk_type().to_string(),
+            "IHDR"
+        );
+        assert_eq!(
+            &png.chunks().next_back().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_restore() {
+        let mut png = testing_png();
+        let before = png.snapshot();
+        png.append_chunk(chunk_from_strings("TeSt", "Message").unwrap());
+        png.remove_first_chunk("FrSt").unwrap();
+        let after = png.snapshot();
+
+        png.restore(&before);
+        assert_eq!(png.chunks().len(), 3);
+        assert!(png.chunk_by_type("FrSt").is_some());
+        assert!(png.chunk_by_type("TeSt").is_none());
+
+        png.restore(&after);
+        assert_eq!(after.len(), 3);
+        assert!(png.chunk_by_type("FrSt").is_none());
+        assert!(png.chunk_by_type("TeSt").is_some());
+    }
+
+    #[test]
+    fn test_snapshot_shares_chunks() {
+        let mut png = testing_png();
+        let snapshot = png.snapshot();
+        assert!(Arc::ptr_eq(&png._chunks[1], &snapshot._chunks[1]));
+
+        let removed = png.remove_first_chunk("miDl").unwrap();
+        assert_eq!(&removed.data_as_string().unwrap(), "I am another chunk");
+        assert_eq!(snapshot.len(), 3);
+    }
+
+    #[test]
+    fn test_parse_prefix() {
+        let png = Png::parse_prefix(&PNG_FILE, &ParseOptions::default(), 2).unwrap();
+        let types: Vec<String> = png.chunks().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["IHDR", "sRGB"]);
+
+        // Nothing past the prefix is read, so a broken tail does not matter.
+        let mut bytes = PNG_FILE.to_vec();
+        bytes.truncate(40);
+        assert_eq!(
+            Png::parse_prefix(&bytes, &ParseOptions::default(), 1)
+                .unwrap()
+                .chunks()
+                .len(),
+            1
+        );
+        assert!(Png::parse_prefix(&bytes, &ParseOptions::default(), 2).is_err());
+        assert!(
+            Png::parse_prefix(&PNG_FILE, &ParseOptions::default(), 0)
+                .unwrap()
+                .chunks()
+                .len()
+                == 0
+        );
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let png = Png::parse_metadata(&PNG_FILE).unwrap();
+        assert!(png.chunk_by_type("IHDR").is_some());
+        assert!(png.chunk_by_type("IDAT").is_none());
+        assert_eq!(
+            png.chunks().len(),
+            Png::try_from(&PNG_FILE[..])
+                .unwrap()
+                .chunks()
+                .position(|c| c.chunk_type().to_string() == "IDAT")
+                .unwrap()
+        );
+    }
+
+    fn idat_sizes(png: &Png) -> Vec<usize> {
+        png.chunks()
+            .filter(|c| c.chunk_type().to_string() == "IDAT")
+            .map(|c| c.data().len())
+            .collect()
+    }
+
+    #[test]
+    fn test_resplit_idat() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        let total: usize = idat_sizes(&png).iter().sum();
+
+        png.resplit_idat(Some(1000)).unwrap();
+        let sizes = idat_sizes(&png);
+        assert_eq!(sizes.iter().sum::<usize>(), total);
+        assert!(sizes[..sizes.len() - 1].iter().all(|&s| s == 1000));
+        assert_eq!(
+            &png.chunks().next_back().unwrap().chunk_type().to_string(),
+            "IEND"
+        );
+
+        png.resplit_idat(None).unwrap();
+        assert_eq!(idat_sizes(&png), vec![total]);
+        assert_eq!(png.as_bytes(), PNG_FILE.to_vec());
+    }
+
+    #[test]
+    fn test_resplit_idat_errors() {
+        let mut png = Png::try_from(&PNG_FILE[..]).unwrap();
+        assert!(png.resplit_idat(Some(0)).is_err());
+
+        png.append_chunk(chunk_from_strings("IDAT", "late").unwrap());
+        assert!(png.resplit_idat(None).is_err());
+        assert!(testing_png().resplit_idat(None).is_err());
+    }
+
+    #[test]
+    fn test_salvage_without_header() {
+        let png = Png::salvage(&PNG_FILE[33..]);
+        assert!(png.is_err());
+    }
+
     #[test]
     fn test_png_trait_impls() {
         let chunk_bytes: Vec<u8> = testing_chunks()
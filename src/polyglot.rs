@@ -0,0 +1,200 @@
+use crate::codec::read_u32;
+use crate::png::Png;
+
+/// File formats whose signatures are looked for inside a PNG.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EmbeddedFormat {
+    Png,
+    Zip,
+    Pdf,
+    Gif,
+    Jpeg,
+    Gzip,
+    SevenZip,
+    Rar,
+}
+
+impl EmbeddedFormat {
+    const ALL: [EmbeddedFormat; 8] = [
+        EmbeddedFormat::Png,
+        EmbeddedFormat::Zip,
+        EmbeddedFormat::Pdf,
+        EmbeddedFormat::Gif,
+        EmbeddedFormat::Jpeg,
+        EmbeddedFormat::Gzip,
+        EmbeddedFormat::SevenZip,
+        EmbeddedFormat::Rar,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            EmbeddedFormat::Png => "PNG",
+            EmbeddedFormat::Zip => "ZIP/JAR",
+            EmbeddedFormat::Pdf => "PDF",
+            EmbeddedFormat::Gif => "GIF",
+            EmbeddedFormat::Jpeg => "JPEG",
+            EmbeddedFormat::Gzip => "GZIP",
+            EmbeddedFormat::SevenZip => "7Z",
+            EmbeddedFormat::Rar => "RAR",
+        }
+    }
+    fn magics(&self) -> &'static [&'static [u8]] {
+        match self {
+            EmbeddedFormat::Png => &[&Png::STANDARD_HEADER],
+            EmbeddedFormat::Zip => &[b"PK\x03\x04", b"PK\x05\x06"],
+            EmbeddedFormat::Pdf => &[b"%PDF-"],
+            EmbeddedFormat::Gif => &[b"GIF87a", b"GIF89a"],
+            EmbeddedFormat::Jpeg => &[b"\xFF\xD8\xFF"],
+            EmbeddedFormat::Gzip => &[b"\x1F\x8B\x08"],
+            EmbeddedFormat::SevenZip => &[b"7z\xBC\xAF\x27\x1C"],
+            EmbeddedFormat::Rar => &[b"Rar!\x1A\x07"],
+        }
+    }
+}
+
+/// A foreign signature found at `offset`. `trailing` is set when it lies
+/// after the IEND of the first PNG stream.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Embedded {
+    pub format: EmbeddedFormat,
+    pub offset: usize,
+    pub trailing: bool,
+}
+
+/// Walks chunk framing (lengths only, CRCs are not checked) from a PNG
+/// signature at the start of `value` and returns the offset just past IEND.
+pub fn png_stream_end(value: &[u8]) -> Option<usize> {
+    if !value.starts_with(&Png::STANDARD_HEADER) {
+        return None;
+    }
+    let mut pos = 8;
+    while pos + 12 <= value.len() {
+        let length = read_u32(value, pos).ok()? as usize;
+        let chunk_type = &value[pos + 4..pos + 8];
+        if !chunk_type.iter().all(|b| b.is_ascii_alphabetic()) {
+            return None;
+        }
+        let end = pos.checked_add(length)?.checked_add(12)?;
+        if end > value.len() {
+            return None;
+        }
+        if chunk_type == b"IEND" {
+            return Some(end);
+        }
+        pos = end;
+    }
+    None
+}
+
+/// Reports every known file signature other than the leading PNG
+/// signature: concatenated PNG streams, appended archives and documents,
+/// and prefixes such as a GIF header placed before the PNG.
+pub fn detect(value: &[u8]) -> Vec<Embedded> {
+    let first_png = find(value, &Png::STANDARD_HEADER, 0);
+    let first_end = first_png.and_then(|start| png_stream_end(&value[start..]).map(|e| start + e));
+    let mut found = Vec::new();
+    for offset in 0..value.len() {
+        for format in EmbeddedFormat::ALL {
+            if Some(offset) == first_png && format == EmbeddedFormat::Png {
+                continue;
+            }
+            if format
+                .magics()
+                .iter()
+                .any(|m| value[offset..].starts_with(m))
+            {
+                found.push(Embedded {
+                    format,
+                    offset,
+                    trailing: first_end.is_some_and(|end| offset >= end),
+                });
+            }
+        }
+    }
+    found
+}
+
+pub(crate) fn find(value: &[u8], needle: &[u8], from: usize) -> Option<usize> {
+    if from >= value.len() {
+        return None;
+    }
+    value[from..]
+        .windows(needle.len())
+        .position(|w| w == needle)
+        .map(|p| p + from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn minimal_png() -> Vec<u8> {
+        let chunks = vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ];
+        Png::from_chunks(chunks).as_bytes()
+    }
+
+    #[test]
+    fn test_png_stream_end() {
+        let png = minimal_png();
+        assert_eq!(png_stream_end(&png), Some(png.len()));
+
+        let mut trailing = png.clone();
+        trailing.extend(b"junk");
+        assert_eq!(png_stream_end(&trailing), Some(png.len()));
+
+        assert_eq!(png_stream_end(&png[..png.len() - 1]), None);
+        assert_eq!(png_stream_end(b"not a png"), None);
+    }
+
+    #[test]
+    fn test_detect_clean_png() {
+        assert!(detect(&minimal_png()).is_empty());
+    }
+
+    #[test]
+    fn test_detect_appended_zip() {
+        let mut bytes = minimal_png();
+        let end = bytes.len();
+        bytes.extend(b"PK\x03\x04rest of archive");
+
+        assert_eq!(
+            detect(&bytes),
+            vec![Embedded {
+                format: EmbeddedFormat::Zip,
+                offset: end,
+                trailing: true,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_detect_concatenated_png() {
+        let mut bytes = minimal_png();
+        let end = bytes.len();
+        bytes.extend(minimal_png());
+
+        let found = detect(&bytes);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].format, EmbeddedFormat::Png);
+        assert_eq!(found[0].offset, end);
+    }
+
+    #[test]
+    fn test_detect_gif_prefix() {
+        let mut bytes = b"GIF89a".to_vec();
+        bytes.extend(minimal_png());
+
+        let found = detect(&bytes);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].format, EmbeddedFormat::Gif);
+        assert_eq!(found[0].offset, 0);
+        assert!(!found[0].trailing);
+    }
+}
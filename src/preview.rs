@@ -0,0 +1,233 @@
+use crate::ancillary::{Bkgd, Hist, Phys, Sbit, Splt, Time, Trns};
+use crate::chunk::Chunk;
+use crate::codec::read_u32;
+use crate::extension::{Gifg, Offs, Pcal, Scal, Ster};
+use crate::ihdr::Ihdr;
+use crate::png::Png;
+use crate::proprietary::{Cgbi, Fireworks, Idot};
+use crate::provenance::ProvenanceChunk;
+use crate::text::{ITxt, Text};
+
+const MAX_TEXT: usize = 60;
+
+/// One-line decoded summary of a known chunk (IHDR dimensions, tEXt keyword
+/// and value, tIME date, pHYs DPI, ...). `color_type` comes from IHDR and is
+/// needed for chunks whose layout depends on it. Returns `None` for unknown
+/// chunk types or data that does not parse.
+pub fn decode(chunk: &Chunk, color_type: Option<u8>) -> Option<String> {
+    let preview = match chunk.chunk_type().to_string().as_str() {
+        "IHDR" => {
+            let ihdr = Ihdr::try_from(chunk).ok()?;
+            format!(
+                "{}x{}, bit depth {}, color type {}, interlace {}",
+                ihdr.width, ihdr.height, ihdr.bit_depth, ihdr.color_type, ihdr.interlace
+            )
+        }
+        "PLTE" => format!("{} entries", chunk.data().len() / 3),
+        "IDAT" => format!("{} bytes of compressed image data", chunk.data().len()),
+        "IEND" => "end of image".to_string(),
+        "gAMA" if chunk.data().len() == 4 => {
+            let gamma = read_u32(chunk.data(), 0).ok()?;
+            format!("gamma {:.5}", gamma as f64 / 100000.0)
+        }
+        "sRGB" if chunk.data().len() == 1 => {
+            let intent = match chunk.data()[0] {
+                0 => "perceptual",
+                1 => "relative colorimetric",
+                2 => "saturation",
+                3 => "absolute colorimetric",
+                _ => "unknown",
+            };
+            format!("rendering intent {}", intent)
+        }
+        "tEXt" => {
+            let text = Text::try_from(chunk).ok()?;
+            format!("{}: {}", text.keyword(), shorten(text.text()))
+        }
+        "iTXt" => {
+            let itxt = ITxt::try_from(chunk).ok()?;
+            let text = itxt
+                .text_lossy()
+                .unwrap_or_else(|_| "<compressed>".to_string());
+            if itxt.language_tag().is_empty() {
+                format!("{}: {}", itxt.keyword(), shorten(&text))
+            } else {
+                format!(
+                    "{} [{}]: {}",
+                    itxt.keyword(),
+                    itxt.language_tag(),
+                    shorten(&text)
+                )
+            }
+        }
+        "tIME" => Time::try_from(chunk).ok()?.to_string(),
+        "pHYs" => {
+            let phys = Phys::try_from(chunk).ok()?;
+            match phys.dpi() {
+                Some((x, y)) => format!("{:.0}x{:.0} DPI", x, y),
+                None => format!(
+                    "aspect {}:{}",
+                    phys.pixels_per_unit_x, phys.pixels_per_unit_y
+                ),
+            }
+        }
+        "bKGD" => format!("{:?}", Bkgd::from_chunk(chunk, color_type?).ok()?),
+        "sBIT" => format!("{:?}", Sbit::from_chunk(chunk, color_type?).ok()?.bits),
+        "tRNS" => match Trns::from_chunk(chunk, color_type?).ok()? {
+            Trns::Palette(alphas) => format!("{} palette alpha entries", alphas.len()),
+            other => format!("transparent {:?}", other),
+        },
+        "hIST" => format!("{} entries", Hist::try_from(chunk).ok()?.frequencies.len()),
+        "sPLT" => {
+            let splt = Splt::try_from(chunk).ok()?;
+            format!(
+                "{}: {} entries, depth {}",
+                splt.name,
+                splt.entries.len(),
+                splt.sample_depth
+            )
+        }
+        "oFFs" => {
+            let offs = Offs::try_from(chunk).ok()?;
+            let unit = if offs.unit == 1 { "um" } else { "px" };
+            format!("offset ({}, {}) {}", offs.x, offs.y, unit)
+        }
+        "sCAL" => {
+            let scal = Scal::try_from(chunk).ok()?;
+            let unit = if scal.unit == 1 { "m" } else { "rad" };
+            format!("{} x {} {} per pixel", scal.width, scal.height, unit)
+        }
+        "pCAL" => {
+            let pcal = Pcal::try_from(chunk).ok()?;
+            format!(
+                "{}: {}..{} equation {} ({})",
+                pcal.name, pcal.x0, pcal.x1, pcal.equation_type, pcal.unit
+            )
+        }
+        "sTER" => format!("{:?}", Ster::try_from(chunk).ok()?),
+        "gIFg" => {
+            let gifg = Gifg::try_from(chunk).ok()?;
+            format!(
+                "disposal {}, delay {}cs",
+                gifg.disposal_method, gifg.delay_time
+            )
+        }
+        "prOV" => format!(
+            "provenance for {} chunks",
+            ProvenanceChunk::try_from(chunk).ok()?.records.len()
+        ),
+        "CgBI" => format!(
+            "iOS-optimized, flags {:#010x}",
+            Cgbi::try_from(chunk).ok()?.flags
+        ),
+        "iDOT" => format!(
+            "{} decoding segments",
+            Idot::try_from(chunk).ok()?.segments.len()
+        ),
+        "prVW" | "mkBF" => format!("{} bytes", Fireworks::try_from(chunk).ok()?.data.len()),
+        _ => return None,
+    };
+    Some(preview)
+}
+
+/// Decoded previews for every chunk of `png`, in order, using the color type
+/// of its IHDR.
+pub fn decode_all(png: &Png) -> Vec<Option<String>> {
+    let color_type = png.header().ok().map(|ihdr| ihdr.color_type);
+    png.chunks().map(|c| decode(c, color_type)).collect()
+}
+
+fn shorten(text: &str) -> String {
+    if text.chars().count() > MAX_TEXT {
+        format!("{}...", text.chars().take(MAX_TEXT).collect::<String>())
+    } else {
+        text.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    fn ihdr() -> Chunk {
+        chunk("IHDR", &[0, 0, 0, 50, 0, 0, 0, 40, 8, 6, 0, 0, 0])
+    }
+
+    #[test]
+    fn test_decode_known_chunks() {
+        assert_eq!(
+            decode(&ihdr(), None).unwrap(),
+            "50x40, bit depth 8, color type 6, interlace 0"
+        );
+        assert_eq!(
+            decode(&Text::new("Author", "me").unwrap().to_chunk(), None).unwrap(),
+            "Author: me"
+        );
+        assert_eq!(
+            decode(&chunk("tIME", &[7, 230, 2, 12, 13, 5, 59]), None).unwrap(),
+            "2022-02-12T13:05:59Z"
+        );
+        assert_eq!(
+            decode(&chunk("pHYs", &[0, 0, 14, 195, 0, 0, 14, 195, 1]), None).unwrap(),
+            "96x96 DPI"
+        );
+        assert_eq!(
+            decode(&ProvenanceChunk::default().to_chunk(), None).unwrap(),
+            "provenance for 0 chunks"
+        );
+    }
+
+    #[test]
+    fn test_decode_proprietary_chunks() {
+        assert_eq!(
+            decode(&chunk("CgBI", &[0x50, 0, 0x20, 6]), None).unwrap(),
+            "iOS-optimized, flags 0x50002006"
+        );
+        assert_eq!(
+            decode(&chunk("iDOT", &[0, 0, 0, 0]), None).unwrap(),
+            "0 decoding segments"
+        );
+        assert_eq!(decode(&chunk("mkBF", &[1, 2]), None).unwrap(), "2 bytes");
+    }
+
+    #[test]
+    fn test_decode_long_text_is_shortened() {
+        let long = "x".repeat(100);
+        let preview = decode(&Text::new("Comment", &long).unwrap().to_chunk(), None).unwrap();
+        assert_eq!(preview.len(), "Comment: ".len() + MAX_TEXT + 3);
+    }
+
+    #[test]
+    fn test_decode_needs_color_type() {
+        let bkgd = chunk("bKGD", &[0, 1, 0, 2, 0, 3]);
+        assert!(decode(&bkgd, None).is_none());
+        assert_eq!(decode(&bkgd, Some(6)).unwrap(), "Rgb(1, 2, 3)");
+    }
+
+    #[test]
+    fn test_decode_unknown_or_invalid() {
+        assert!(decode(&chunk("ruSt", b"secret"), None).is_none());
+        assert!(decode(&chunk("tIME", &[0]), None).is_none());
+    }
+
+    #[test]
+    fn test_decode_all() {
+        let png = Png::from_chunks(vec![
+            ihdr(),
+            chunk("bKGD", &[0, 1, 0, 2, 0, 3]),
+            chunk("ruSt", b"secret"),
+            chunk("IEND", &[]),
+        ]);
+        let previews = decode_all(&png);
+        assert_eq!(previews.len(), 4);
+        assert_eq!(previews[1].as_deref(), Some("Rgb(1, 2, 3)"));
+        assert_eq!(previews[2], None);
+        assert_eq!(previews[3].as_deref(), Some("end of image"));
+    }
+}
@@ -0,0 +1,288 @@
+use crate::ancillary::{check_type, new_chunk};
+use crate::chunk::Chunk;
+use crate::codec::{read_u32, write_u32};
+use crate::png::Png;
+use anyhow::{anyhow, Result};
+
+/// Apple's marker for iOS-optimized PNGs (`CgBI`). It comes before IHDR and
+/// means the IDAT stream is raw deflate without a zlib header, and pixels
+/// are stored as premultiplied BGRA; see [`bgra_to_rgba`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cgbi {
+    /// Undocumented flags, kept as stored.
+    pub flags: u32,
+}
+
+impl Cgbi {
+    pub const CHUNK_TYPE: &'static str = "CgBI";
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        write_u32(&mut data, self.flags);
+        new_chunk(Cgbi::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Cgbi {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Cgbi::CHUNK_TYPE)?;
+        let data = chunk.data();
+        if data.len() != 4 {
+            return Err(anyhow!(
+                "Invalid CgBI Chunk : Length {} , Should Be 4",
+                data.len()
+            ));
+        }
+        Ok(Cgbi {
+            flags: read_u32(data, 0)?,
+        })
+    }
+}
+
+/// True if `png` starts with a CgBI chunk.
+pub fn is_cgbi(png: &Png) -> bool {
+    png.chunks()
+        .next()
+        .is_some_and(|c| c.chunk_type().to_string() == Cgbi::CHUNK_TYPE)
+}
+
+/// Swaps the blue and red channels of 8-bit BGRA pixels in place, undoing
+/// the CgBI channel order once the image data has been inflated and
+/// unfiltered. Alpha is left premultiplied.
+pub fn bgra_to_rgba(pixels: &mut [u8]) {
+    for pixel in pixels.chunks_exact_mut(4) {
+        pixel.swap(0, 2);
+    }
+}
+
+/// One band of rows in an [`Idot`] chunk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdotSegment {
+    pub first_row: u32,
+    pub rows: u32,
+    /// Offset of the band's first IDAT chunk, counted from the start of the
+    /// iDOT chunk's length field.
+    pub idat_offset: u32,
+}
+
+/// Apple's table for decoding the image in parallel bands (`iDOT`). The
+/// offsets break if chunks are inserted or resized between iDOT and the
+/// IDAT chunks; [`Idot::check_offsets`] detects that.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Idot {
+    pub segments: Vec<IdotSegment>,
+}
+
+impl Idot {
+    pub const CHUNK_TYPE: &'static str = "iDOT";
+
+    /// Checks that every segment offset lands on an IDAT chunk of `png`,
+    /// measured from the first iDOT chunk.
+    pub fn check_offsets(&self, png: &Png) -> Result<()> {
+        let mut starts = Vec::new();
+        let mut pos = 8;
+        for chunk in png.chunks() {
+            starts.push((pos, chunk.chunk_type().to_string()));
+            pos += chunk.length() as usize + 12;
+        }
+        let base = starts
+            .iter()
+            .find(|(_, t)| t == Idot::CHUNK_TYPE)
+            .map(|(p, _)| *p)
+            .ok_or_else(|| anyhow!("Invalid iDOT Check : No iDOT Chunk in Png"))?;
+        for (i, segment) in self.segments.iter().enumerate() {
+            let target = base + segment.idat_offset as usize;
+            if !starts.iter().any(|(p, t)| *p == target && t == "IDAT") {
+                return Err(anyhow!(
+                    "Invalid iDOT Chunk : Segment {} Offset {} Does Not Point At IDAT",
+                    i,
+                    segment.idat_offset
+                ));
+            }
+        }
+        Ok(())
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        write_u32(&mut data, self.segments.len() as u32);
+        for segment in &self.segments {
+            write_u32(&mut data, segment.first_row);
+            write_u32(&mut data, segment.rows);
+            write_u32(&mut data, segment.idat_offset);
+        }
+        new_chunk(Idot::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for Idot {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, Idot::CHUNK_TYPE)?;
+        let data = chunk.data();
+        let count = read_u32(data, 0)? as usize;
+        if data.len() != 4 + count * 12 {
+            return Err(anyhow!(
+                "Invalid iDOT Chunk : Length {} , Should Be {}",
+                data.len(),
+                4 + count * 12
+            ));
+        }
+        let segments = (0..count)
+            .map(|i| {
+                let pos = 4 + i * 12;
+                Ok(IdotSegment {
+                    first_row: read_u32(data, pos)?,
+                    rows: read_u32(data, pos + 4)?,
+                    idat_offset: read_u32(data, pos + 8)?,
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Idot { segments })
+    }
+}
+
+/// Which Adobe Fireworks chunk a [`Fireworks`] value came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FireworksKind {
+    /// `prVW`: a thumbnail preview.
+    Preview,
+    /// `mkBF`: the editable document data.
+    Document,
+}
+
+/// A Fireworks chunk (`prVW`, `mkBF`). The contents are undocumented, so
+/// they are kept as opaque bytes and written back unchanged.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Fireworks {
+    pub kind: FireworksKind,
+    pub data: Vec<u8>,
+}
+
+impl Fireworks {
+    pub const PREVIEW_CHUNK_TYPE: &'static str = "prVW";
+    pub const DOCUMENT_CHUNK_TYPE: &'static str = "mkBF";
+
+    pub fn to_chunk(&self) -> Chunk {
+        let chunk_type = match self.kind {
+            FireworksKind::Preview => Fireworks::PREVIEW_CHUNK_TYPE,
+            FireworksKind::Document => Fireworks::DOCUMENT_CHUNK_TYPE,
+        };
+        new_chunk(chunk_type, self.data.clone())
+    }
+}
+
+impl TryFrom<&Chunk> for Fireworks {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        let kind = match chunk.chunk_type().to_string().as_str() {
+            Fireworks::PREVIEW_CHUNK_TYPE => FireworksKind::Preview,
+            Fireworks::DOCUMENT_CHUNK_TYPE => FireworksKind::Document,
+            other => {
+                return Err(anyhow!(
+                    "Invalid Fireworks Chunk : Found Chunk Type {}",
+                    other
+                ))
+            }
+        };
+        Ok(Fireworks {
+            kind,
+            data: chunk.data().to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        new_chunk(chunk_type, data.to_vec())
+    }
+
+    fn idot_png(gap: Option<Chunk>) -> Png {
+        let idot = Idot {
+            segments: vec![
+                IdotSegment {
+                    first_row: 0,
+                    rows: 1,
+                    idat_offset: 40,
+                },
+                IdotSegment {
+                    first_row: 1,
+                    rows: 1,
+                    idat_offset: 55,
+                },
+            ],
+        };
+        let mut chunks = vec![
+            Cgbi { flags: 0x50002006 }.to_chunk(),
+            chunk("IHDR", &[0, 0, 0, 1, 0, 0, 0, 2, 8, 6, 0, 0, 0]),
+            idot.to_chunk(),
+            chunk("IDAT", &[1, 2, 3]),
+            chunk("IDAT", &[4, 5, 6]),
+            chunk("IEND", &[]),
+        ];
+        if let Some(gap) = gap {
+            chunks.insert(3, gap);
+        }
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_cgbi() {
+        let cgbi = Cgbi { flags: 0x50002006 };
+        assert_eq!(Cgbi::try_from(&cgbi.to_chunk()).unwrap(), cgbi);
+        assert!(Cgbi::try_from(&chunk("CgBI", &[0; 3])).is_err());
+
+        let png = idot_png(None);
+        assert!(is_cgbi(&png));
+        assert_eq!(png.header().unwrap().height, 2);
+        assert!(png.validate_order().is_empty());
+    }
+
+    #[test]
+    fn test_bgra_to_rgba() {
+        let mut pixels = [1, 2, 3, 4, 5, 6, 7, 8, 9];
+        bgra_to_rgba(&mut pixels);
+        assert_eq!(pixels, [3, 2, 1, 4, 7, 6, 5, 8, 9]);
+    }
+
+    #[test]
+    fn test_idot_round_trip() {
+        let png = idot_png(None);
+        let idot = Idot::try_from(png.chunk_by_type("iDOT").unwrap()).unwrap();
+        assert_eq!(idot.segments.len(), 2);
+        assert_eq!(idot.to_chunk().length(), 28);
+        idot.check_offsets(&png).unwrap();
+
+        let reparsed = Png::try_from(png.as_bytes().as_ref()).unwrap();
+        assert!(reparsed.warnings().is_empty());
+        idot.check_offsets(&reparsed).unwrap();
+
+        assert!(Idot::try_from(&chunk("iDOT", &[0, 0, 0, 2, 0, 0, 0, 0])).is_err());
+    }
+
+    #[test]
+    fn test_idot_offsets_broken() {
+        let png = idot_png(Some(chunk("tEXt", b"a\0b")));
+        let idot = Idot::try_from(png.chunk_by_type("iDOT").unwrap()).unwrap();
+        assert!(idot.check_offsets(&png).is_err());
+    }
+
+    #[test]
+    fn test_fireworks() {
+        let fireworks = Fireworks::try_from(&chunk("mkBF", &[1, 2, 3])).unwrap();
+        assert_eq!(fireworks.kind, FireworksKind::Document);
+        assert_eq!(
+            fireworks.to_chunk().as_bytes(),
+            chunk("mkBF", &[1, 2, 3]).as_bytes()
+        );
+        let preview = Fireworks::try_from(&chunk("prVW", &[])).unwrap();
+        assert_eq!(preview.kind, FireworksKind::Preview);
+        assert!(Fireworks::try_from(&chunk("tEXt", &[])).is_err());
+    }
+}
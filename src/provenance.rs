@@ -0,0 +1,229 @@
+use crate::ancillary::{check_type, new_chunk};
+use crate::chunk::Chunk;
+use crate::codec::{read_u32, read_u64, write_u32, write_u64};
+use crate::png::Png;
+use crate::text::split_null;
+use anyhow::{anyhow, Result};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Who added a chunk, with what, when and why.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Provenance {
+    pub tool_version: String,
+    /// Seconds since the Unix epoch.
+    pub timestamp: u64,
+    pub operator: String,
+    pub reason: String,
+}
+
+impl Provenance {
+    /// A record for this version of the tool, stamped with the current time.
+    pub fn new(operator: &str, reason: &str) -> Self {
+        Provenance {
+            tool_version: format!("pngme {}", env!("CARGO_PKG_VERSION")),
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+            operator: operator.to_string(),
+            reason: reason.to_string(),
+        }
+    }
+}
+
+/// A [`Provenance`] attached to the chunk with the given type and CRC.
+/// Chunks are identified by content rather than position so that records
+/// survive reordering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProvenanceRecord {
+    pub chunk_type: [u8; 4],
+    pub crc: u32,
+    pub provenance: Provenance,
+}
+
+/// The private `prOV` sidecar chunk holding every provenance record of a
+/// file. Each record is the chunk type, CRC and timestamp followed by the
+/// NUL-terminated tool version, operator and reason in UTF-8.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ProvenanceChunk {
+    pub records: Vec<ProvenanceRecord>,
+}
+
+impl ProvenanceChunk {
+    pub const CHUNK_TYPE: &'static str = "prOV";
+
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = Vec::new();
+        for record in &self.records {
+            data.extend_from_slice(&record.chunk_type);
+            write_u32(&mut data, record.crc);
+            write_u64(&mut data, record.provenance.timestamp);
+            for field in [
+                &record.provenance.tool_version,
+                &record.provenance.operator,
+                &record.provenance.reason,
+            ] {
+                data.extend_from_slice(field.as_bytes());
+                data.push(0);
+            }
+        }
+        new_chunk(ProvenanceChunk::CHUNK_TYPE, data)
+    }
+}
+
+impl TryFrom<&Chunk> for ProvenanceChunk {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        check_type(chunk, ProvenanceChunk::CHUNK_TYPE)?;
+        let mut rest = chunk.data();
+        let mut records = Vec::new();
+        while !rest.is_empty() {
+            if rest.len() < 16 {
+                return Err(anyhow!("Invalid prOV Chunk : Truncated Record"));
+            }
+            let (head, tail) = rest.split_at(16);
+            rest = tail;
+            let mut fields = Vec::new();
+            for _ in 0..3 {
+                let (field, tail) =
+                    split_null(rest).ok_or_else(|| anyhow!("Invalid prOV Chunk : Missing Null"))?;
+                fields.push(String::from_utf8(field.to_vec())?);
+                rest = tail;
+            }
+            let reason = fields.pop().unwrap();
+            let operator = fields.pop().unwrap();
+            let tool_version = fields.pop().unwrap();
+            records.push(ProvenanceRecord {
+                chunk_type: head[..4].try_into().unwrap(),
+                crc: read_u32(head, 4)?,
+                provenance: Provenance {
+                    tool_version,
+                    timestamp: read_u64(head, 8)?,
+                    operator,
+                    reason,
+                },
+            });
+        }
+        Ok(ProvenanceChunk { records })
+    }
+}
+
+/// Records `provenance` for `chunk` in the file's `prOV` chunk, creating it
+/// before IEND if needed. Any earlier record for the same chunk is replaced.
+pub fn annotate(png: &mut Png, chunk: &Chunk, provenance: Provenance) -> Result<()> {
+    for field in [
+        &provenance.tool_version,
+        &provenance.operator,
+        &provenance.reason,
+    ] {
+        if field.contains('\0') {
+            return Err(anyhow!(
+                "Invalid Provenance Field {:?} : Contains Null",
+                field
+            ));
+        }
+    }
+    let mut sidecar = match png.first_chunk_by_type(ProvenanceChunk::CHUNK_TYPE) {
+        Some(existing) => ProvenanceChunk::try_from(existing)?,
+        None => ProvenanceChunk::default(),
+    };
+    let chunk_type = chunk.chunk_type().bytes();
+    sidecar
+        .records
+        .retain(|r| r.chunk_type != chunk_type || r.crc != chunk.crc());
+    sidecar.records.push(ProvenanceRecord {
+        chunk_type,
+        crc: chunk.crc(),
+        provenance,
+    });
+    png.remove_first_chunk(ProvenanceChunk::CHUNK_TYPE).ok();
+    png.insert_before_end(sidecar.to_chunk());
+    Ok(())
+}
+
+/// The provenance recorded for `chunk`, if any.
+pub fn lookup(png: &Png, chunk: &Chunk) -> Option<Provenance> {
+    let sidecar =
+        ProvenanceChunk::try_from(png.first_chunk_by_type(ProvenanceChunk::CHUNK_TYPE)?).ok()?;
+    sidecar
+        .records
+        .into_iter()
+        .find(|r| r.chunk_type == chunk.chunk_type().bytes() && r.crc == chunk.crc())
+        .map(|r| r.provenance)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn provenance(reason: &str) -> Provenance {
+        Provenance {
+            tool_version: "pngme 0.1.0".to_string(),
+            timestamp: 1644671159,
+            operator: "examiner".to_string(),
+            reason: reason.to_string(),
+        }
+    }
+
+    fn png() -> Png {
+        Png::from_chunks(vec![
+            new_chunk("IHDR", vec![0; 13]),
+            new_chunk("IEND", Vec::new()),
+        ])
+    }
+
+    #[test]
+    fn test_provenance_chunk_round_trip() {
+        let sidecar = ProvenanceChunk {
+            records: vec![
+                ProvenanceRecord {
+                    chunk_type: *b"ruSt",
+                    crc: 7,
+                    provenance: provenance("hide message"),
+                },
+                ProvenanceRecord {
+                    chunk_type: *b"tEXt",
+                    crc: 8,
+                    provenance: provenance(""),
+                },
+            ],
+        };
+        let chunk = sidecar.to_chunk();
+        assert_eq!(ProvenanceChunk::try_from(&chunk).unwrap(), sidecar);
+    }
+
+    #[test]
+    fn test_provenance_chunk_invalid() {
+        assert!(ProvenanceChunk::try_from(&new_chunk("prOV", vec![0; 10])).is_err());
+        assert!(ProvenanceChunk::try_from(&new_chunk("prOV", vec![0; 17])).is_err());
+        assert!(ProvenanceChunk::try_from(&new_chunk("tEXt", Vec::new())).is_err());
+    }
+
+    #[test]
+    fn test_annotate_and_lookup() {
+        let mut png = png();
+        let secret = new_chunk("ruSt", b"secret".to_vec());
+        png.insert_before_end(secret.clone());
+        assert!(lookup(&png, &secret).is_none());
+
+        annotate(&mut png, &secret, provenance("first")).unwrap();
+        annotate(&mut png, &secret, provenance("second")).unwrap();
+        let other = new_chunk("ruSt", b"other".to_vec());
+        annotate(&mut png, &other, provenance("other")).unwrap();
+
+        assert_eq!(lookup(&png, &secret).unwrap().reason, "second");
+        assert_eq!(lookup(&png, &other).unwrap().reason, "other");
+        assert_eq!(png.chunks_by_type("prOV").len(), 1);
+        let types: Vec<String> = png.chunks().map(|c| c.chunk_type().to_string()).collect();
+        assert_eq!(types, vec!["IHDR", "ruSt", "prOV", "IEND"]);
+
+        assert!(annotate(&mut png, &secret, provenance("a\0b")).is_err());
+    }
+
+    #[test]
+    fn test_provenance_new() {
+        let provenance = Provenance::new("me", "test");
+        assert!(provenance.tool_version.starts_with("pngme "));
+        assert!(provenance.timestamp > 0);
+    }
+}
@@ -0,0 +1,192 @@
+use crate::chunk_type::ChunkType;
+
+/// Where a well-known chunk type is defined.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Registration {
+    /// Defined by the PNG specification itself.
+    Standard,
+    /// Registered public extension (PNG extensions document, APNG).
+    Extension,
+    /// Private chunk written by a specific vendor's tools.
+    Proprietary,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KnownChunk {
+    pub chunk_type: &'static str,
+    pub description: &'static str,
+    pub registration: Registration,
+}
+
+const fn standard(chunk_type: &'static str, description: &'static str) -> KnownChunk {
+    KnownChunk {
+        chunk_type,
+        description,
+        registration: Registration::Standard,
+    }
+}
+
+const fn extension(chunk_type: &'static str, description: &'static str) -> KnownChunk {
+    KnownChunk {
+        chunk_type,
+        description,
+        registration: Registration::Extension,
+    }
+}
+
+const fn proprietary(chunk_type: &'static str, description: &'static str) -> KnownChunk {
+    KnownChunk {
+        chunk_type,
+        description,
+        registration: Registration::Proprietary,
+    }
+}
+
+pub const KNOWN_CHUNKS: &[KnownChunk] = &[
+    standard("IHDR", "image header"),
+    standard("PLTE", "palette"),
+    standard("IDAT", "image data"),
+    standard("IEND", "image trailer"),
+    standard("cHRM", "primary chromaticities and white point"),
+    standard("gAMA", "image gamma"),
+    standard("iCCP", "embedded ICC profile"),
+    standard("sBIT", "significant bits"),
+    standard("sRGB", "standard RGB color space"),
+    standard("cICP", "coding-independent code points"),
+    standard("mDCV", "mastering display color volume"),
+    standard("cLLI", "content light level information"),
+    standard("tEXt", "textual metadata"),
+    standard("zTXt", "compressed textual metadata"),
+    standard("iTXt", "international textual metadata"),
+    standard("bKGD", "background color"),
+    standard("hIST", "palette histogram"),
+    standard("pHYs", "physical pixel dimensions"),
+    standard("sPLT", "suggested palette"),
+    standard("eXIf", "EXIF metadata"),
+    standard("tIME", "last modification time"),
+    standard("tRNS", "transparency"),
+    standard("acTL", "animation control"),
+    standard("fcTL", "animation frame control"),
+    standard("fdAT", "animation frame data"),
+    extension("oFFs", "image offset"),
+    extension("pCAL", "pixel value calibration"),
+    extension("sCAL", "physical scale of image subject"),
+    extension("sTER", "stereo image indicator"),
+    extension("gIFg", "GIF graphic control extension"),
+    extension("gIFx", "GIF application extension"),
+    extension("gIFt", "GIF plain text extension"),
+    extension("dSIG", "digital signature"),
+    extension("fRAc", "fractal image parameters"),
+    proprietary("CgBI", "Apple iOS-optimized image"),
+    proprietary("iDOT", "Apple parallel decoding offsets"),
+    proprietary("prVW", "Fireworks preview"),
+    proprietary("mkBF", "Fireworks document data"),
+];
+
+pub fn lookup(chunk_type: &str) -> Option<&'static KnownChunk> {
+    KNOWN_CHUNKS.iter().find(|k| k.chunk_type == chunk_type)
+}
+
+pub fn describe(chunk_type: &str) -> Option<&'static str> {
+    lookup(chunk_type).map(|k| k.description)
+}
+
+/// True for types defined by the PNG specification or a registered public
+/// extension, which readers and editors are expected to handle.
+pub fn is_registered_public(chunk_type: &str) -> bool {
+    lookup(chunk_type).is_some_and(|k| k.registration != Registration::Proprietary)
+}
+
+/// Why a chunk of this type is worth a closer look when hunting for hidden
+/// data: not registered, vendor-specific, private, unsafe to copy or with
+/// the reserved bit set. Empty for registered public types.
+pub fn oddities(chunk_type: &ChunkType) -> Vec<&'static str> {
+    let name = chunk_type.to_string();
+    if is_registered_public(&name) {
+        return Vec::new();
+    }
+    let mut oddities = Vec::new();
+    match lookup(&name) {
+        Some(_) => oddities.push("proprietary"),
+        None => oddities.push("unregistered"),
+    }
+    if !chunk_type.is_public() {
+        oddities.push("private");
+    }
+    if !chunk_type.is_safe_to_copy() {
+        oddities.push("unsafe to copy");
+    }
+    if !chunk_type.is_reserved_bit_valid() {
+        oddities.push("reserved bit set");
+    }
+    oddities
+}
+
+/// Short label for listings, e.g. `pHYs — physical pixel dimensions`, or
+/// `ruSt — unknown chunk` for types not in the registry.
+pub fn label(chunk_type: &str) -> String {
+    format!(
+        "{} — {}",
+        chunk_type,
+        describe(chunk_type).unwrap_or("unknown chunk")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup() {
+        let known = lookup("pHYs").unwrap();
+        assert_eq!(known.description, "physical pixel dimensions");
+        assert_eq!(known.registration, Registration::Standard);
+        assert_eq!(
+            lookup("sCAL").unwrap().registration,
+            Registration::Extension
+        );
+        assert_eq!(
+            lookup("CgBI").unwrap().registration,
+            Registration::Proprietary
+        );
+        assert!(lookup("ruSt").is_none());
+        assert!(lookup("phys").is_none());
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label("tEXt"), "tEXt — textual metadata");
+        assert_eq!(label("ruSt"), "ruSt — unknown chunk");
+    }
+
+    #[test]
+    fn test_oddities() {
+        use std::str::FromStr;
+
+        let oddities_of = |t: &str| oddities(&ChunkType::from_str(t).unwrap());
+        assert!(is_registered_public("tEXt"));
+        assert!(is_registered_public("sCAL"));
+        assert!(!is_registered_public("CgBI"));
+        assert!(oddities_of("IDAT").is_empty());
+        assert!(oddities_of("fRAc").is_empty());
+        assert_eq!(oddities_of("ruSt"), vec!["unregistered", "private"]);
+        assert_eq!(
+            oddities_of("CgBI"),
+            vec!["proprietary", "private", "unsafe to copy"]
+        );
+        assert_eq!(
+            oddities_of("RUsT"),
+            vec!["unregistered", "unsafe to copy", "reserved bit set"]
+        );
+    }
+
+    #[test]
+    fn test_known_chunks_unique() {
+        for (i, a) in KNOWN_CHUNKS.iter().enumerate() {
+            assert_eq!(a.chunk_type.len(), 4);
+            assert!(KNOWN_CHUNKS[i + 1..]
+                .iter()
+                .all(|b| b.chunk_type != a.chunk_type));
+        }
+    }
+}
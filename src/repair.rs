@@ -0,0 +1,193 @@
+use crate::checksum::{Checksum, PNG_CRC};
+use crate::codec::{read_u32, write_u32};
+use crate::png::Png;
+use crate::registry;
+use anyhow::{anyhow, Result};
+
+/// Knobs for [`repair`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct RepairOptions {
+    /// When a length field does not lead to another chunk header, look for
+    /// the next known chunk type and take the length from its position.
+    pub fix_lengths: bool,
+}
+
+/// One change made by [`repair`]. `offset` is where the chunk starts in the
+/// input.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    Crc {
+        offset: usize,
+        chunk_type: String,
+        old: u32,
+        new: u32,
+    },
+    Length {
+        offset: usize,
+        chunk_type: String,
+        old: u32,
+        new: u32,
+    },
+}
+
+/// Output of [`repair`]: the fixed file and what was changed, in file order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repair {
+    pub bytes: Vec<u8>,
+    pub fixes: Vec<Fix>,
+}
+
+/// Rewrites every chunk whose stored CRC does not match its data, and with
+/// [`RepairOptions::fix_lengths`] every length field that runs past the
+/// next chunk. Bytes after IEND are copied unchanged. Fails on a bad
+/// signature or on framing it cannot follow.
+pub fn repair(value: &[u8], opts: &RepairOptions) -> Result<Repair> {
+    if !value.starts_with(&Png::STANDARD_HEADER) {
+        return Err(anyhow!("Invalid Repair : Not A Png Signature"));
+    }
+    let mut bytes = Png::STANDARD_HEADER.to_vec();
+    let mut fixes = Vec::new();
+    let mut pos = 8;
+    while pos < value.len() {
+        if pos + 12 > value.len() {
+            return Err(anyhow!(
+                "Invalid Repair At Offset {} : Truncated Chunk",
+                pos
+            ));
+        }
+        let chunk_type: [u8; 4] = value[pos + 4..pos + 8].try_into().unwrap();
+        let type_name = String::from_utf8_lossy(&chunk_type).into_owned();
+        let mut length = read_u32(value, pos)?;
+        let end = pos + 12 + length as usize;
+        let is_last = &chunk_type == b"IEND" && end <= value.len();
+        if !is_last && !is_chunk_end(value, end) {
+            if !opts.fix_lengths {
+                return Err(anyhow!(
+                    "Invalid Repair At Offset {} : Bad Length {}",
+                    pos,
+                    length
+                ));
+            }
+            let end = (pos + 12..=value.len())
+                .find(|end| *end == value.len() || is_known_header(value, *end))
+                .unwrap();
+            let new = (end - pos - 12) as u32;
+            fixes.push(Fix::Length {
+                offset: pos,
+                chunk_type: type_name.clone(),
+                old: length,
+                new,
+            });
+            length = new;
+        }
+        let end = pos + 12 + length as usize;
+        let data = &value[pos + 8..end - 4];
+        let stored = read_u32(value, end - 4)?;
+        let computed = PNG_CRC.checksum(&chunk_type, data);
+        if stored != computed {
+            fixes.push(Fix::Crc {
+                offset: pos,
+                chunk_type: type_name,
+                old: stored,
+                new: computed,
+            });
+        }
+        write_u32(&mut bytes, length);
+        bytes.extend_from_slice(&chunk_type);
+        bytes.extend_from_slice(data);
+        write_u32(&mut bytes, computed);
+        pos = end;
+        if &chunk_type == b"IEND" {
+            bytes.extend_from_slice(&value[pos..]);
+            break;
+        }
+    }
+    Ok(Repair { bytes, fixes })
+}
+
+/// True if a chunk ending at `end` is followed by the end of the input or by
+/// something shaped like a chunk header.
+fn is_chunk_end(value: &[u8], end: usize) -> bool {
+    end == value.len()
+        || (end + 8 <= value.len() && value[end + 4..end + 8].iter().all(u8::is_ascii_alphabetic))
+}
+
+/// True if a chunk of a registered type starts at `pos`.
+fn is_known_header(value: &[u8], pos: usize) -> bool {
+    value
+        .get(pos + 4..pos + 8)
+        .and_then(|t| std::str::from_utf8(t).ok())
+        .is_some_and(|t| registry::lookup(t).is_some())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn minimal_png() -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes()
+    }
+
+    #[test]
+    fn test_repair_nothing_to_do() {
+        let png = minimal_png();
+        let repair = repair(&png, &RepairOptions::default()).unwrap();
+        assert_eq!(repair.bytes, png);
+        assert!(repair.fixes.is_empty());
+    }
+
+    #[test]
+    fn test_repair_crc() {
+        let png = minimal_png();
+        let mut bad = png.clone();
+        bad[33 + 12] ^= 0xff;
+        bad.push(7);
+        let mut expected = png.clone();
+        expected.push(7);
+
+        let repair = repair(&bad, &RepairOptions::default()).unwrap();
+        assert_eq!(repair.bytes, expected);
+        assert_eq!(repair.fixes.len(), 1);
+        assert!(matches!(
+            &repair.fixes[0],
+            Fix::Crc { offset: 33, chunk_type, .. } if chunk_type == "IDAT"
+        ));
+    }
+
+    #[test]
+    fn test_repair_length() {
+        let png = minimal_png();
+        let mut bad = png.clone();
+        bad[33 + 3] = 9;
+        assert!(repair(&bad, &RepairOptions::default()).is_err());
+
+        let opts = RepairOptions { fix_lengths: true };
+        let repair = repair(&bad, &opts).unwrap();
+        assert_eq!(repair.bytes, png);
+        assert_eq!(
+            repair.fixes[0],
+            Fix::Length {
+                offset: 33,
+                chunk_type: "IDAT".to_string(),
+                old: 9,
+                new: 3
+            }
+        );
+        assert!(Png::try_from(repair.bytes.as_ref()).is_ok());
+    }
+
+    #[test]
+    fn test_repair_errors() {
+        assert!(repair(b"GIF89a", &RepairOptions::default()).is_err());
+        let png = minimal_png();
+        assert!(repair(&png[..png.len() - 6], &RepairOptions::default()).is_err());
+    }
+}
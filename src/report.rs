@@ -0,0 +1,207 @@
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::events::{Event, Listener};
+use crate::png::{ParseOptions, Png};
+use crate::polyglot;
+use crate::registry;
+use crate::scan::{find_pngs, PngFiles};
+use crate::verify::{full_check, FullCheck};
+use crate::vfs::Vfs;
+use anyhow::Result;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Aggregate statistics over the `.png` files under a directory, built by
+/// [`corpus_report`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CorpusReport {
+    /// Files found, including those that could not be read or parsed.
+    pub files: usize,
+    /// How often each chunk type occurs, most frequent first, then by name.
+    pub chunk_types: Vec<(String, usize)>,
+    /// Mean share of each parsed file taken up by ancillary chunks, framing
+    /// included, from 0 to 1.
+    pub ancillary_overhead: f64,
+    /// Files and directories failing validation, with the reason.
+    pub failed: Vec<(PathBuf, String)>,
+    /// Files with suspicious findings, with a description of each.
+    pub suspicious: Vec<(PathBuf, Vec<String>)>,
+}
+
+/// Reads every `.png` file under `dir` from `vfs`, validates it with
+/// [`full_check`] and parses it permissively to count chunk types and
+/// ancillary overhead. Non-standard chunks and embedded formats are
+/// suspicious findings. `listener` gets a progress event per file. Once
+/// `token` is cancelled the report stops and fails with [`Cancelled`].
+pub fn corpus_report(
+    vfs: &dyn Vfs,
+    dir: &Path,
+    token: &CancellationToken,
+    listener: &mut dyn Listener,
+) -> Result<CorpusReport> {
+    let PngFiles {
+        files,
+        unlisted: mut failed,
+    } = find_pngs(dir)?;
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut overheads = Vec::new();
+    let mut suspicious = Vec::new();
+    let total = files.len();
+    for (i, path) in files.iter().enumerate() {
+        token.check()?;
+        let bytes = match vfs.read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                failed.push((path.clone(), e.to_string()));
+                continue;
+            }
+        };
+        let check = full_check(&bytes);
+        if !check.is_ok() {
+            failed.push((path.clone(), failure(&check)));
+        }
+        let mut findings: Vec<String> = polyglot::detect(&bytes)
+            .iter()
+            .map(|e| format!("embedded {} at offset {}", e.format.name(), e.offset))
+            .collect();
+        match Png::try_from_cancellable(&bytes, &ParseOptions::permissive(), token) {
+            Ok(png) => {
+                for chunk in png.chunks() {
+                    *counts.entry(chunk.chunk_type().to_string()).or_insert(0) += 1;
+                    let oddities = registry::oddities(chunk.chunk_type());
+                    if !oddities.is_empty() {
+                        findings.push(format!("{}: {}", chunk.chunk_type(), oddities.join(", ")));
+                    }
+                }
+                overheads.push(ancillary_overhead(&png));
+            }
+            Err(e) if e.is::<Cancelled>() => return Err(e),
+            Err(e) if check.is_ok() => failed.push((path.clone(), e.to_string())),
+            Err(_) => {}
+        }
+        if !findings.is_empty() {
+            suspicious.push((path.clone(), findings));
+        }
+        listener.on_event(&Event::Progress { done: i + 1, total });
+    }
+    let mut chunk_types: Vec<(String, usize)> = counts.into_iter().collect();
+    chunk_types.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    let ancillary_overhead = if overheads.is_empty() {
+        0.0
+    } else {
+        overheads.iter().sum::<f64>() / overheads.len() as f64
+    };
+    Ok(CorpusReport {
+        files: total,
+        chunk_types,
+        ancillary_overhead,
+        failed,
+        suspicious,
+    })
+}
+
+/// Share of the serialized file taken up by ancillary chunks.
+fn ancillary_overhead(png: &Png) -> f64 {
+    let ancillary: usize = png
+        .chunks()
+        .filter(|c| !c.chunk_type().is_critical())
+        .map(|c| c.length() as usize + 12)
+        .sum();
+    let size = Png::STANDARD_HEADER.len()
+        + png
+            .chunks()
+            .map(|c| c.length() as usize + 12)
+            .sum::<usize>();
+    ancillary as f64 / size as f64
+}
+
+fn failure(check: &FullCheck) -> String {
+    if !check.framing.is_ok() {
+        check.framing.to_string()
+    } else {
+        let bad = check.chunks.iter().filter(|c| !c.is_ok()).count();
+        format!("{} chunk CRC mismatches", bad)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::vfs::StdFs;
+    use std::fs;
+    use std::str::FromStr;
+
+    fn chunk(chunk_type: &str, data: &[u8]) -> Chunk {
+        Chunk::new(ChunkType::from_str(chunk_type).unwrap(), data.to_vec())
+    }
+
+    #[test]
+    fn test_corpus_report() {
+        let dir = std::env::temp_dir().join(format!("pngme_report_{}", std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        let plain = Png::from_chunks(vec![
+            chunk("IHDR", &[0; 13]),
+            chunk("IDAT", &[1; 39]),
+            chunk("IEND", &[]),
+        ]);
+        let tagged = Png::from_chunks(vec![
+            chunk("IHDR", &[0; 13]),
+            chunk("ruSt", &[2; 20]),
+            chunk("IDAT", &[1; 39]),
+            chunk("IEND", &[]),
+        ]);
+        fs::write(dir.join("a.png"), plain.as_bytes()).unwrap();
+        fs::write(dir.join("sub/b.png"), tagged.as_bytes()).unwrap();
+        let mut broken = plain.as_bytes();
+        broken.truncate(40);
+        fs::write(dir.join("c.png"), broken).unwrap();
+
+        let mut progress = Vec::new();
+        let report = corpus_report(&StdFs, &dir, &CancellationToken::new(), &mut |e: &Event| {
+            progress.push(e.clone())
+        })
+        .unwrap();
+        assert_eq!(report.files, 3);
+        assert_eq!(
+            report.chunk_types,
+            vec![
+                ("IDAT".to_string(), 2),
+                ("IEND".to_string(), 2),
+                ("IHDR".to_string(), 2),
+                ("ruSt".to_string(), 1)
+            ]
+        );
+        // 32 of 128 bytes in the tagged file, none in the plain one.
+        assert_eq!(report.ancillary_overhead, 0.125);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].0, dir.join("c.png"));
+        assert_eq!(report.failed[0].1, "truncated at offset 33");
+        assert_eq!(report.suspicious.len(), 1);
+        assert_eq!(report.suspicious[0].0, dir.join("sub/b.png"));
+        assert_eq!(
+            report.suspicious[0].1,
+            vec!["ruSt: unregistered, private".to_string()]
+        );
+        assert_eq!(
+            progress.last(),
+            Some(&Event::Progress { done: 3, total: 3 })
+        );
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_corpus_report_cancelled() {
+        let dir =
+            std::env::temp_dir().join(format!("pngme_report_cancelled_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("a.png"), b"not a png").unwrap();
+        let token = CancellationToken::new();
+        token.cancel();
+        let err = corpus_report(&StdFs, &dir, &token, &mut |_: &Event| {})
+            .err()
+            .unwrap();
+        assert!(err.is::<Cancelled>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
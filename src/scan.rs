@@ -0,0 +1,367 @@
+use crate::cancel::{CancellationToken, Cancelled};
+use crate::chunk::Chunk;
+use crate::events::{Event, Listener};
+use crate::png::{ParseOptions, Png};
+use crate::polyglot::{self, Embedded};
+use crate::vfs::Vfs;
+use anyhow::Result;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Which chunks [`scan_dir`] looks for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScanTarget {
+    /// Chunks of exactly this type.
+    ChunkType(String),
+    /// Chunks with the private bit set, which no specification defines.
+    Private,
+}
+
+impl ScanTarget {
+    fn matches(&self, png: &Png) -> Vec<ChunkMatch> {
+        png.chunks()
+            .filter(|c| match self {
+                ScanTarget::ChunkType(t) => c.chunk_type().to_string() == *t,
+                ScanTarget::Private => !c.chunk_type().is_public(),
+            })
+            .map(ChunkMatch::new)
+            .collect()
+    }
+}
+
+/// A chunk matching the [`ScanTarget`], with metrics hinting whether its
+/// data is compressed or encrypted.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkMatch {
+    pub chunk_type: String,
+    /// Length of the data.
+    pub length: u32,
+    /// [`Chunk::entropy`] of the data.
+    pub entropy: f64,
+    /// [`Chunk::compress_ratio_estimate`] of the data.
+    pub compress_ratio: f64,
+}
+
+impl ChunkMatch {
+    pub fn new(chunk: &Chunk) -> Self {
+        ChunkMatch {
+            chunk_type: chunk.chunk_type().to_string(),
+            length: chunk.length(),
+            entropy: chunk.entropy(),
+            compress_ratio: chunk.compress_ratio_estimate(),
+        }
+    }
+}
+
+/// What [`scan_dir`] found in one file.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ScanOutcome {
+    /// The matching chunks in file order; empty if there were none.
+    Matches(Vec<ChunkMatch>),
+    /// The file could not be read or parsed.
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanEntry {
+    pub path: PathBuf,
+    pub outcome: ScanOutcome,
+    /// Foreign signatures and extra PNG streams found in the raw bytes, even
+    /// when the file did not parse.
+    pub embedded: Vec<Embedded>,
+}
+
+/// Walks `dir` recursively, in name order, and parses every file with a
+/// `.png` extension (any case), looking for `target`. A file or
+/// subdirectory that cannot be read is recorded as
+/// [`ScanOutcome::Failed`] and the walk carries on; only an unreadable
+/// `dir` fails the whole scan. `listener` gets a progress event per file.
+/// The directories are listed from disk; the files are read from `vfs`.
+/// Files are parsed with `opts`. Once `token` is cancelled the scan stops
+/// and fails with [`Cancelled`].
+pub fn scan_dir(
+    vfs: &dyn Vfs,
+    dir: &Path,
+    target: &ScanTarget,
+    opts: &ParseOptions,
+    token: &CancellationToken,
+    listener: &mut dyn Listener,
+) -> Result<Vec<ScanEntry>> {
+    let PngFiles { files, unlisted } = find_pngs(dir)?;
+    let mut entries: Vec<ScanEntry> = unlisted
+        .into_iter()
+        .map(|(path, e)| ScanEntry {
+            path,
+            outcome: ScanOutcome::Failed(e),
+            embedded: Vec::new(),
+        })
+        .collect();
+    let total = files.len();
+    for (i, path) in files.into_iter().enumerate() {
+        token.check()?;
+        entries.push(scan_file(vfs, path, target, opts, token)?);
+        listener.on_event(&Event::Progress { done: i + 1, total });
+    }
+    Ok(entries)
+}
+
+/// Reads and parses one file. Only cancellation is returned as an error;
+/// anything else is recorded in the entry.
+fn scan_file(
+    vfs: &dyn Vfs,
+    path: PathBuf,
+    target: &ScanTarget,
+    opts: &ParseOptions,
+    token: &CancellationToken,
+) -> Result<ScanEntry> {
+    let bytes = match vfs.read(&path) {
+        Ok(bytes) => bytes,
+        Err(e) => {
+            let outcome =
+                ScanOutcome::Failed(format!("Invalid Png File {} : {}", path.display(), e));
+            return Ok(ScanEntry {
+                path,
+                outcome,
+                embedded: Vec::new(),
+            });
+        }
+    };
+    let outcome = match Png::try_from_cancellable(&bytes, opts, token) {
+        Ok(png) => ScanOutcome::Matches(target.matches(&png)),
+        Err(e) if e.is::<Cancelled>() => return Err(e),
+        Err(e) => ScanOutcome::Failed(e.to_string()),
+    };
+    Ok(ScanEntry {
+        path,
+        outcome,
+        embedded: polyglot::detect(&bytes),
+    })
+}
+
+/// What [`find_pngs`] found under a directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PngFiles {
+    /// Files with a `.png` extension (any case), sorted by path.
+    pub files: Vec<PathBuf>,
+    /// Subdirectories that could not be listed, with the reason.
+    pub unlisted: Vec<(PathBuf, String)>,
+}
+
+/// Walks `dir` recursively for PNG files. Only an unreadable `dir` is an
+/// error.
+pub fn find_pngs(dir: &Path) -> Result<PngFiles> {
+    let mut files = Vec::new();
+    let mut unlisted = Vec::new();
+    collect_pngs(read_dir_sorted(dir)?, &mut files, &mut unlisted);
+    files.sort();
+    Ok(PngFiles { files, unlisted })
+}
+
+/// Adds the `.png` files among `children` to `files`, descending into
+/// subdirectories; those that cannot be listed go to `failed`.
+fn collect_pngs(
+    children: Vec<PathBuf>,
+    files: &mut Vec<PathBuf>,
+    failed: &mut Vec<(PathBuf, String)>,
+) {
+    for path in children {
+        if path.is_dir() {
+            match read_dir_sorted(&path) {
+                Ok(children) => collect_pngs(children, files, failed),
+                Err(e) => failed.push((path, e.to_string())),
+            }
+        } else if is_png_name(&path) {
+            files.push(path);
+        }
+    }
+}
+
+fn read_dir_sorted(dir: &Path) -> Result<Vec<PathBuf>> {
+    let mut children = fs::read_dir(dir)?
+        .map(|entry| Ok(entry?.path()))
+        .collect::<Result<Vec<_>>>()?;
+    children.sort();
+    Ok(children)
+}
+
+fn is_png_name(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|e| e.to_string_lossy().eq_ignore_ascii_case("png"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk_type::ChunkType;
+    use crate::polyglot::EmbeddedFormat;
+    use crate::vfs::StdFs;
+    use std::str::FromStr;
+    use std::time::Duration;
+
+    fn png_with(chunk_type: &str) -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str(chunk_type).unwrap(), b"x".to_vec()),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes()
+    }
+
+    fn rust_match() -> ScanOutcome {
+        ScanOutcome::Matches(vec![ChunkMatch {
+            chunk_type: "ruSt".to_string(),
+            length: 1,
+            entropy: 0.0,
+            compress_ratio: 0.0,
+        }])
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("pngme_scan_{}_{}", name, std::process::id()));
+        fs::create_dir_all(dir.join("sub")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scan_dir() {
+        let dir = temp_dir("dir");
+        fs::write(dir.join("a.png"), png_with("ruSt")).unwrap();
+        fs::write(dir.join("b.PNG"), png_with("tEXt")).unwrap();
+        fs::write(dir.join("notes.txt"), b"not a png").unwrap();
+        fs::write(dir.join("sub/c.png"), b"corrupt").unwrap();
+        fs::write(dir.join("sub/d.png"), png_with("ruSt")).unwrap();
+
+        let mut progress = Vec::new();
+        let entries = scan_dir(
+            &StdFs,
+            &dir,
+            &ScanTarget::ChunkType("ruSt".to_string()),
+            &ParseOptions::default(),
+            &CancellationToken::new(),
+            &mut |e: &Event| progress.push(e.clone()),
+        )
+        .unwrap();
+        let names: Vec<String> = entries
+            .iter()
+            .map(|e| e.path.strip_prefix(&dir).unwrap().display().to_string())
+            .collect();
+        assert_eq!(names, vec!["a.png", "b.PNG", "sub/c.png", "sub/d.png"]);
+        assert_eq!(entries[0].outcome, rust_match());
+        assert_eq!(entries[1].outcome, ScanOutcome::Matches(Vec::new()));
+        assert!(matches!(entries[2].outcome, ScanOutcome::Failed(_)));
+        assert_eq!(entries[3].outcome, rust_match());
+        assert_eq!(
+            progress.last(),
+            Some(&Event::Progress { done: 4, total: 4 })
+        );
+
+        let entries = scan_dir(
+            &StdFs,
+            &dir,
+            &ScanTarget::Private,
+            &ParseOptions::default(),
+            &CancellationToken::new(),
+            &mut |_: &Event| {},
+        )
+        .unwrap();
+        assert_eq!(entries[1].outcome, ScanOutcome::Matches(Vec::new()));
+        assert_eq!(entries[3].outcome, rust_match());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_entropy() {
+        let dir = temp_dir("entropy");
+        let bytes = Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), (0..=255).collect()),
+            Chunk::new(ChunkType::from_str("ruSt").unwrap(), vec![7; 64]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes();
+        fs::write(dir.join("a.png"), bytes).unwrap();
+        let entries = scan_dir(
+            &StdFs,
+            &dir,
+            &ScanTarget::Private,
+            &ParseOptions::default(),
+            &CancellationToken::new(),
+            &mut |_: &Event| {},
+        )
+        .unwrap();
+        match &entries[0].outcome {
+            ScanOutcome::Matches(matches) => {
+                assert_eq!(matches.len(), 2);
+                assert_eq!(matches[0].entropy, 8.0);
+                assert_eq!(matches[0].compress_ratio, 1.0);
+                assert_eq!(matches[1].entropy, 0.0);
+                assert_eq!(matches[1].compress_ratio, 0.0);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_polyglot() {
+        let dir = temp_dir("polyglot");
+        let mut bytes = png_with("tEXt");
+        let second = bytes.len();
+        bytes.extend(png_with("ruSt"));
+        let zip = bytes.len();
+        bytes.extend(b"PK\x03\x04rest of the archive");
+        fs::write(dir.join("a.png"), &bytes).unwrap();
+
+        let entries = scan_dir(
+            &StdFs,
+            &dir,
+            &ScanTarget::Private,
+            &ParseOptions::permissive(),
+            &CancellationToken::new(),
+            &mut |_: &Event| {},
+        )
+        .unwrap();
+        let embedded = &entries[0].embedded;
+        assert_eq!(embedded.len(), 2);
+        assert_eq!(embedded[0].format, EmbeddedFormat::Png);
+        assert_eq!(embedded[0].offset, second);
+        assert!(embedded[0].trailing);
+        assert_eq!(embedded[1].format, EmbeddedFormat::Zip);
+        assert_eq!(embedded[1].offset, zip);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_cancelled() {
+        let dir = temp_dir("cancelled");
+        fs::write(dir.join("a.png"), png_with("ruSt")).unwrap();
+        let token = CancellationToken::with_timeout(Duration::ZERO);
+        let err = scan_dir(
+            &StdFs,
+            &dir,
+            &ScanTarget::Private,
+            &ParseOptions::default(),
+            &token,
+            &mut |_: &Event| {},
+        )
+        .err()
+        .unwrap();
+        assert!(err.is::<Cancelled>());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_scan_missing_dir() {
+        let missing = std::env::temp_dir().join("pngme_scan_missing_dir");
+        let token = CancellationToken::new();
+        assert!(scan_dir(
+            &StdFs,
+            &missing,
+            &ScanTarget::Private,
+            &ParseOptions::default(),
+            &token,
+            &mut |_: &Event| {}
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,303 @@
+use crate::json::Json;
+
+/// Version of the `--json` document layouts, written as `schema_version`
+/// at the top of every document. Within a version fields are only ever
+/// added; removing, renaming or retyping one bumps it.
+pub const VERSION: u64 = 1;
+
+/// A command whose `--json` output has a published schema.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    Print,
+    Scan,
+    Report,
+    List,
+}
+
+impl Output {
+    pub const ALL: [Output; 4] = [Output::Print, Output::Scan, Output::Report, Output::List];
+    /// The name of the command, used on the command line.
+    pub fn name(self) -> &'static str {
+        match self {
+            Output::Print => "print",
+            Output::Scan => "scan",
+            Output::Report => "report",
+            Output::List => "list",
+        }
+    }
+    /// The output called `name` by [`Output::name`].
+    pub fn from_name(name: &str) -> Option<Output> {
+        Output::ALL.into_iter().find(|o| o.name() == name)
+    }
+}
+
+/// JSON Schema (draft 2020-12) of the `--json` document of `output`.
+/// Objects do not forbid extra properties, so documents from a later
+/// release with the same [`VERSION`] still validate.
+pub fn schema(output: Output) -> Json {
+    let (title, properties) = match output {
+        Output::Print => ("Chunks of a PNG file", print()),
+        Output::Scan => ("PNG files under a directory holding a chunk type", scan()),
+        Output::Report => ("Statistics over the PNG files under a directory", report()),
+        Output::List => ("Non-standard or matching chunks of a PNG file", list()),
+    };
+    let mut fields = vec![
+        (
+            "$schema",
+            Json::string("https://json-schema.org/draft/2020-12/schema"),
+        ),
+        (
+            "$id",
+            Json::string(format!("urn:pngme:{}:v{}", output.name(), VERSION)),
+        ),
+        ("title", Json::string(title)),
+    ];
+    match object(properties) {
+        Json::Object(body) => fields.extend(body),
+        _ => unreachable!("object builds a JSON object"),
+    }
+    Json::Object(fields)
+}
+
+fn print() -> Vec<(&'static str, Json)> {
+    let provenance = object(vec![
+        ("tool_version", typed("string")),
+        ("timestamp", typed("integer")),
+        ("operator", typed("string")),
+        ("reason", typed("string")),
+    ]);
+    let chunk = object(vec![
+        ("index", typed("integer")),
+        ("type", typed("string")),
+        ("offset", typed("integer")),
+        ("length", typed("integer")),
+        ("crc", typed("string")),
+        ("crc_ok", typed("boolean")),
+        ("critical", typed("boolean")),
+        ("public", typed("boolean")),
+        ("safe_to_copy", typed("boolean")),
+        ("description", nullable("string")),
+        ("preview", nullable("string")),
+        (
+            "provenance",
+            Json::Object(vec![(
+                "anyOf",
+                Json::Array(vec![provenance, typed("null")]),
+            )]),
+        ),
+    ]);
+    vec![
+        ("schema_version", version()),
+        ("file", typed("string")),
+        ("chunks", array(chunk)),
+        ("warnings", array(typed("string"))),
+    ]
+}
+
+fn scan() -> Vec<(&'static str, Json)> {
+    let matched = object(vec![
+        ("type", typed("string")),
+        ("length", typed("integer")),
+        ("entropy", typed("number")),
+        ("compress_ratio", typed("number")),
+    ]);
+    let embedded = object(vec![
+        ("format", typed("string")),
+        ("offset", typed("integer")),
+        ("trailing", typed("boolean")),
+    ]);
+    let file = object(vec![
+        ("file", typed("string")),
+        ("error", nullable("string")),
+        ("matches", array(matched)),
+        ("embedded", array(embedded)),
+    ]);
+    vec![
+        ("schema_version", version()),
+        ("dir", typed("string")),
+        ("files", array(file)),
+    ]
+}
+
+fn report() -> Vec<(&'static str, Json)> {
+    let chunk_type = object(vec![("type", typed("string")), ("count", typed("integer"))]);
+    let failed = object(vec![("file", typed("string")), ("reason", typed("string"))]);
+    let suspicious = object(vec![
+        ("file", typed("string")),
+        ("findings", array(typed("string"))),
+    ]);
+    vec![
+        ("schema_version", version()),
+        ("dir", typed("string")),
+        ("files", typed("integer")),
+        ("ancillary_overhead", typed("number")),
+        ("chunk_types", array(chunk_type)),
+        ("failed", array(failed)),
+        ("suspicious", array(suspicious)),
+    ]
+}
+
+fn list() -> Vec<(&'static str, Json)> {
+    let chunk = object(vec![
+        ("index", typed("integer")),
+        ("type", typed("string")),
+        ("offset", typed("integer")),
+        ("length", typed("integer")),
+        ("oddities", array(typed("string"))),
+    ]);
+    vec![
+        ("schema_version", version()),
+        ("file", typed("string")),
+        ("filter", nullable("string")),
+        ("chunks", array(chunk)),
+        ("total", typed("integer")),
+    ]
+}
+
+/// An object with all of `properties` required.
+fn object(properties: Vec<(&'static str, Json)>) -> Json {
+    let required = properties
+        .iter()
+        .map(|(name, _)| Json::string(*name))
+        .collect();
+    Json::Object(vec![
+        ("type", Json::string("object")),
+        ("properties", Json::Object(properties)),
+        ("required", Json::Array(required)),
+    ])
+}
+
+fn array(items: Json) -> Json {
+    Json::Object(vec![("type", Json::string("array")), ("items", items)])
+}
+
+fn typed(name: &str) -> Json {
+    Json::Object(vec![("type", Json::string(name))])
+}
+
+fn nullable(name: &str) -> Json {
+    Json::Object(vec![(
+        "type",
+        Json::Array(vec![Json::string(name), Json::string("null")]),
+    )])
+}
+
+fn version() -> Json {
+    Json::Object(vec![("const", Json::Number(VERSION))])
+}
+
+/// Whether `value` satisfies `schema`, for the subset of JSON Schema that
+/// [`schema`] uses.
+#[cfg(test)]
+pub fn conforms(value: &Json, schema: &Json) -> bool {
+    let Json::Object(keywords) = schema else {
+        return false;
+    };
+    keywords
+        .iter()
+        .all(|(keyword, expected)| holds(value, keyword, expected))
+}
+
+/// Whether `value` satisfies the schema keyword `keyword` with argument
+/// `expected`. Unknown keywords always hold.
+#[cfg(test)]
+fn holds(value: &Json, keyword: &str, expected: &Json) -> bool {
+    match keyword {
+        "type" => match expected {
+            Json::String(name) => has_type(value, name),
+            Json::Array(names) => names
+                .iter()
+                .any(|n| matches!(n, Json::String(name) if has_type(value, name))),
+            _ => false,
+        },
+        "const" => value == expected,
+        "anyOf" => match expected {
+            Json::Array(options) => options.iter().any(|o| conforms(value, o)),
+            _ => false,
+        },
+        "properties" => match (value, expected) {
+            (Json::Object(fields), Json::Object(properties)) => {
+                properties.iter().all(|(name, schema)| {
+                    fields
+                        .iter()
+                        .find(|(key, _)| key == name)
+                        .map_or(true, |(_, field)| conforms(field, schema))
+                })
+            }
+            _ => true,
+        },
+        "required" => match (value, expected) {
+            (Json::Object(fields), Json::Array(names)) => names.iter().all(|name| {
+                matches!(name, Json::String(name) if fields.iter().any(|(key, _)| key == name))
+            }),
+            _ => true,
+        },
+        "items" => match value {
+            Json::Array(items) => items.iter().all(|item| conforms(item, expected)),
+            _ => true,
+        },
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+fn has_type(value: &Json, name: &str) -> bool {
+    matches!(
+        (value, name),
+        (Json::Null, "null")
+            | (Json::Bool(_), "boolean")
+            | (Json::Number(_), "integer" | "number")
+            | (Json::Float(_), "number")
+            | (Json::String(_), "string")
+            | (Json::Array(_), "array")
+            | (Json::Object(_), "object")
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_output_names() {
+        for output in Output::ALL {
+            assert_eq!(Output::from_name(output.name()), Some(output));
+        }
+        assert_eq!(Output::from_name("decode"), None);
+    }
+
+    #[test]
+    fn test_schema() {
+        let schema = schema(Output::List).to_string();
+        assert!(schema.starts_with(concat!(
+            r#"{"$schema":"https://json-schema.org/draft/2020-12/schema","#,
+            r#""$id":"urn:pngme:list:v1","#
+        )));
+        assert!(schema.contains(r#""schema_version":{"const":1}"#));
+        assert!(
+            schema.ends_with(r#""required":["schema_version","file","filter","chunks","total"]}"#)
+        );
+    }
+
+    #[test]
+    fn test_conforms() {
+        let schema = object(vec![
+            ("schema_version", version()),
+            ("name", nullable("string")),
+            ("sizes", array(typed("integer"))),
+        ]);
+        let document = |version, name| {
+            Json::Object(vec![
+                ("schema_version", Json::Number(version)),
+                ("name", name),
+                ("sizes", Json::Array(vec![Json::Number(1)])),
+                ("extra", Json::Bool(true)),
+            ])
+        };
+        assert!(conforms(&document(VERSION, Json::Null), &schema));
+        assert!(conforms(&document(VERSION, Json::string("a")), &schema));
+        assert!(!conforms(&document(VERSION + 1, Json::Null), &schema));
+        assert!(!conforms(&document(VERSION, Json::Number(1)), &schema));
+        assert!(!conforms(&Json::Object(Vec::new()), &schema));
+    }
+}
@@ -0,0 +1,286 @@
+use crate::cancel::CancellationToken;
+use crate::checksum::{Checksum, PNG_CRC};
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use crate::codec::read_u32;
+use crate::png::{Png, PngError};
+use anyhow::{anyhow, Result};
+use std::io::{self, Read, Write};
+
+/// Largest chunk length the specification allows.
+const MAX_CHUNK_LENGTH: u32 = (1 << 31) - 1;
+
+/// Reads a PNG one chunk at a time from any reader, checking the signature
+/// first and each chunk's length and CRC as it goes. Only one chunk is held
+/// in memory at a time.
+pub struct PngReader<R: Read> {
+    _reader: R,
+    _offset: usize,
+    _done: bool,
+    _token: Option<CancellationToken>,
+}
+
+impl<R: Read> PngReader<R> {
+    pub fn new(reader: R) -> Self {
+        PngReader {
+            _reader: reader,
+            _offset: 0,
+            _done: false,
+            _token: None,
+        }
+    }
+    /// Makes every subsequent read fail with
+    /// [`Cancelled`](crate::cancel::Cancelled) once `token` is cancelled.
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self._token = Some(token);
+        self
+    }
+    /// File offset of the next chunk to be read.
+    pub fn offset(&self) -> usize {
+        self._offset
+    }
+    pub fn into_inner(self) -> R {
+        self._reader
+    }
+    /// Returns the next chunk, or `None` once the input ends cleanly on a
+    /// chunk boundary. Errors carry a [`PngError`] with the failing offset.
+    pub fn next_chunk(&mut self) -> Result<Option<Chunk>> {
+        if self._done {
+            return Ok(None);
+        }
+        let chunk = self.read_chunk();
+        if !matches!(chunk, Ok(Some(_))) {
+            self._done = true;
+        }
+        chunk
+    }
+    fn read_chunk(&mut self) -> Result<Option<Chunk>> {
+        if let Some(token) = &self._token {
+            token.check()?;
+        }
+        if self._offset == 0 {
+            let mut signature = [0u8; 8];
+            let n = read_full(&mut self._reader, &mut signature)?;
+            if signature[..n] != Png::STANDARD_HEADER {
+                return Err(PngError::BadSignature {
+                    found: signature[..n].to_vec(),
+                }
+                .into());
+            }
+            self._offset = 8;
+        }
+        let offset = self._offset;
+        let bad = |reason: String| PngError::BadChunk { offset, reason };
+
+        let mut head = [0u8; 8];
+        match read_full(&mut self._reader, &mut head)? {
+            0 => return Ok(None),
+            8 => {}
+            n => return Err(bad(format!("Truncated Header : {} Of 8 Bytes", n)).into()),
+        }
+        let length = read_u32(&head, 0)?;
+        if length > MAX_CHUNK_LENGTH {
+            return Err(bad(format!("Length {} : Exceeds {}", length, MAX_CHUNK_LENGTH)).into());
+        }
+        let mut data = Vec::new();
+        (&mut self._reader)
+            .take(length as u64)
+            .read_to_end(&mut data)?;
+        let mut crc = [0u8; 4];
+        if data.len() < length as usize || read_full(&mut self._reader, &mut crc)? < 4 {
+            return Err(bad(format!("Truncated Data : Length {}", length)).into());
+        }
+        let crc = read_u32(&crc, 0)?;
+        let computed = PNG_CRC.checksum(&[head[4], head[5], head[6], head[7]], &data);
+        if crc != computed {
+            return Err(bad(format!("Wrong CRC {} , Should Be {}", crc, computed)).into());
+        }
+        let chunk_type = ChunkType::try_from([head[4], head[5], head[6], head[7]]).unwrap();
+        self._offset += length as usize + 12;
+        Ok(Some(Chunk::new_raw(length, chunk_type, data, crc)))
+    }
+}
+
+impl<R: Read> Iterator for PngReader<R> {
+    type Item = Result<Chunk>;
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_chunk().transpose()
+    }
+}
+
+/// Writes a PNG to any writer chunk by chunk: the signature on creation,
+/// then each chunk as it is passed in, without building the whole file.
+pub struct PngWriter<W: Write> {
+    _writer: W,
+    _ended: bool,
+}
+
+impl<W: Write> PngWriter<W> {
+    /// Writes the signature and returns the writer.
+    pub fn new(mut writer: W) -> Result<Self> {
+        writer.write_all(&Png::STANDARD_HEADER)?;
+        Ok(PngWriter {
+            _writer: writer,
+            _ended: false,
+        })
+    }
+    pub fn write_chunk(&mut self, chunk: &Chunk) -> Result<()> {
+        self.write_data(chunk.chunk_type(), chunk.data())
+    }
+    /// Writes a chunk of type `chunk_type` holding `data`, computing its
+    /// length and CRC.
+    pub fn write_data(&mut self, chunk_type: &ChunkType, data: &[u8]) -> Result<()> {
+        let length = u32::try_from(data.len())
+            .ok()
+            .filter(|l| *l <= MAX_CHUNK_LENGTH)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Invalid Chunk Length {} : Exceeds {}",
+                    data.len(),
+                    MAX_CHUNK_LENGTH
+                )
+            })?;
+        let crc = PNG_CRC.checksum(&chunk_type.bytes(), data);
+        self._writer.write_all(&length.to_be_bytes())?;
+        self._writer.write_all(&chunk_type.bytes())?;
+        self._writer.write_all(data)?;
+        self._writer.write_all(&crc.to_be_bytes())?;
+        if chunk_type.bytes() == *b"IEND" {
+            self._ended = true;
+        }
+        Ok(())
+    }
+    /// Writes IEND unless one was already written, flushes and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> Result<W> {
+        if !self._ended {
+            self.write_data(&ChunkType::try_from(*b"IEND").unwrap(), &[])?;
+        }
+        self._writer.flush()?;
+        Ok(self._writer)
+    }
+}
+
+/// Like `read_exact`, but returns how many bytes were read before EOF
+/// instead of failing.
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match reader.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(read) => n += read,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+    use std::str::FromStr;
+
+    fn png_bytes() -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes()
+    }
+
+    fn error_offset(err: anyhow::Error) -> usize {
+        err.downcast_ref::<PngError>().unwrap().offset()
+    }
+
+    #[test]
+    fn test_read_chunks() {
+        let bytes = png_bytes();
+        let mut reader = PngReader::new(Cursor::new(&bytes));
+        let ihdr = reader.next_chunk().unwrap().unwrap();
+        assert_eq!(&ihdr.chunk_type().to_string(), "IHDR");
+        assert_eq!(reader.offset(), 33);
+
+        let rest: Vec<Chunk> = reader.map(|c| c.unwrap()).collect();
+        assert_eq!(rest.len(), 2);
+        assert_eq!(rest[0].data(), &[1, 2, 3]);
+        let png = Png::from_chunks(std::iter::once(ihdr).chain(rest).collect());
+        assert_eq!(png.as_bytes(), bytes);
+    }
+
+    #[test]
+    fn test_read_cancelled() {
+        let bytes = png_bytes();
+        let token = CancellationToken::new();
+        let mut reader = PngReader::new(Cursor::new(&bytes)).with_cancellation(token.clone());
+        assert!(reader.next_chunk().unwrap().is_some());
+        token.cancel();
+        let err = reader.next_chunk().err().unwrap();
+        assert!(err.is::<crate::cancel::Cancelled>());
+        assert!(reader.next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_bad_signature() {
+        let mut reader = PngReader::new(Cursor::new(b"GIF89a"));
+        let err = reader.next_chunk().err().unwrap();
+        assert_eq!(
+            err.downcast_ref::<PngError>(),
+            Some(&PngError::BadSignature {
+                found: b"GIF89a".to_vec()
+            })
+        );
+        assert!(reader.next_chunk().unwrap().is_none());
+    }
+
+    #[test]
+    fn test_read_truncated_and_corrupt() {
+        let bytes = png_bytes();
+        for (cut, offset) in [(36, 33), (40, 33), (bytes.len() - 2, 48)] {
+            let errors: Vec<anyhow::Error> = PngReader::new(Cursor::new(&bytes[..cut]))
+                .filter_map(|c| c.err())
+                .collect();
+            assert_eq!(errors.len(), 1);
+            assert_eq!(error_offset(errors.into_iter().next().unwrap()), offset);
+        }
+
+        let mut corrupt = bytes.clone();
+        corrupt[35] ^= 1;
+        let mut reader = PngReader::new(Cursor::new(&corrupt));
+        assert!(reader.next_chunk().unwrap().is_some());
+        assert_eq!(error_offset(reader.next_chunk().err().unwrap()), 33);
+    }
+
+    #[test]
+    fn test_write_chunks() {
+        let png = Png::try_from(png_bytes().as_ref()).unwrap();
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        for chunk in &png {
+            writer.write_chunk(chunk).unwrap();
+        }
+        assert_eq!(writer.finish().unwrap(), png_bytes());
+    }
+
+    #[test]
+    fn test_write_adds_iend() {
+        let mut writer = PngWriter::new(Vec::new()).unwrap();
+        writer
+            .write_data(&ChunkType::from_str("IHDR").unwrap(), &[0; 13])
+            .unwrap();
+        writer
+            .write_data(&ChunkType::from_str("IDAT").unwrap(), &[1, 2, 3])
+            .unwrap();
+        assert_eq!(writer.finish().unwrap(), png_bytes());
+    }
+
+    #[test]
+    fn test_read_oversized_length() {
+        let mut bytes = Png::STANDARD_HEADER.to_vec();
+        bytes.extend([0xff, 0xff, 0xff, 0xff]);
+        bytes.extend(b"IDAT");
+        let mut reader = PngReader::new(Cursor::new(&bytes));
+        assert_eq!(error_offset(reader.next_chunk().err().unwrap()), 8);
+    }
+}
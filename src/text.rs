@@ -0,0 +1,365 @@
+use crate::chunk::Chunk;
+use crate::chunk_type::ChunkType;
+use anyhow::{anyhow, Result};
+use std::str::FromStr;
+
+/// International textual data (`iTXt`): a Latin-1 keyword plus UTF-8 text,
+/// optionally tagged with a language and a translated keyword.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ITxt {
+    _keyword: String,
+    _compressed: bool,
+    _compression_method: u8,
+    _language_tag: String,
+    _translated_keyword: String,
+    _text: Vec<u8>,
+}
+
+impl ITxt {
+    pub const CHUNK_TYPE: &'static str = "iTXt";
+
+    pub fn new(keyword: &str, text: &str) -> Result<Self> {
+        validate_keyword(keyword)?;
+        Ok(ITxt {
+            _keyword: keyword.to_string(),
+            _compressed: false,
+            _compression_method: 0,
+            _language_tag: String::new(),
+            _translated_keyword: String::new(),
+            _text: text.as_bytes().to_vec(),
+        })
+    }
+    /// Fails if the tag is not alphanumeric words separated by `-`, or the
+    /// translated keyword holds a NUL, which would end the field early.
+    pub fn with_language(mut self, language_tag: &str, translated_keyword: &str) -> Result<Self> {
+        validate_language_tag(language_tag)?;
+        if translated_keyword.contains('\0') {
+            return Err(anyhow!(
+                "Invalid Translated Keyword {:?} : Contains NUL",
+                translated_keyword
+            ));
+        }
+        self._language_tag = language_tag.to_string();
+        self._translated_keyword = translated_keyword.to_string();
+        Ok(self)
+    }
+    pub fn keyword(&self) -> &str {
+        &self._keyword
+    }
+    pub fn language_tag(&self) -> &str {
+        &self._language_tag
+    }
+    pub fn translated_keyword(&self) -> &str {
+        &self._translated_keyword
+    }
+    /// True if the text field is deflated. Only parsed chunks can be
+    /// compressed; [`ITxt::new`] always stores plain text.
+    pub fn is_compressed(&self) -> bool {
+        self._compressed
+    }
+    pub fn compression_method(&self) -> u8 {
+        self._compression_method
+    }
+    /// Raw text field as stored in the chunk (still deflated when compressed).
+    pub fn raw_text(&self) -> &[u8] {
+        self._text.as_ref()
+    }
+    /// Text decoded with strict UTF-8 validation.
+    pub fn text(&self) -> Result<String> {
+        self.check_uncompressed()?;
+        String::from_utf8(self._text.clone()).map_err(|e| {
+            anyhow!(
+                "Invalid iTXt Text For Keyword {} : {}",
+                self._keyword,
+                e.utf8_error()
+            )
+        })
+    }
+    /// Text decoded with invalid UTF-8 sequences replaced by U+FFFD.
+    pub fn text_lossy(&self) -> Result<String> {
+        self.check_uncompressed()?;
+        Ok(String::from_utf8_lossy(&self._text).into_owned())
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = latin1_encode(&self._keyword);
+        data.push(0);
+        data.push(self._compressed as u8);
+        data.push(self._compression_method);
+        data.extend_from_slice(self._language_tag.as_bytes());
+        data.push(0);
+        data.extend_from_slice(self._translated_keyword.as_bytes());
+        data.push(0);
+        data.extend_from_slice(&self._text);
+        Chunk::new(ChunkType::from_str(ITxt::CHUNK_TYPE).unwrap(), data)
+    }
+    fn check_uncompressed(&self) -> Result<()> {
+        if self._compressed {
+            Err(anyhow!(
+                "Invalid iTXt Text For Keyword {} : Compressed Text Is Not Supported",
+                self._keyword
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl TryFrom<&Chunk> for ITxt {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type().to_string() != ITxt::CHUNK_TYPE {
+            return Err(anyhow!(
+                "Invalid iTXt Chunk : Found Chunk Type {}",
+                chunk.chunk_type()
+            ));
+        }
+        let data = chunk.data();
+        let (keyword, rest) = split_null(data)
+            .ok_or_else(|| anyhow!("Invalid iTXt Chunk : Missing Keyword Terminator"))?;
+        let keyword = latin1_decode(keyword);
+        validate_keyword(&keyword)?;
+        if rest.len() < 2 {
+            return Err(anyhow!("Invalid iTXt Chunk : Missing Compression Fields"));
+        }
+        let compressed = match rest[0] {
+            0 => false,
+            1 => true,
+            v => return Err(anyhow!("Invalid iTXt Chunk : Compression Flag {}", v)),
+        };
+        let compression_method = rest[1];
+        if compression_method != 0 {
+            return Err(anyhow!(
+                "Invalid iTXt Chunk : Unknown Compression Method {}",
+                compression_method
+            ));
+        }
+        let (language_tag, rest) = split_null(&rest[2..])
+            .ok_or_else(|| anyhow!("Invalid iTXt Chunk : Missing Language Tag Terminator"))?;
+        let (translated_keyword, text) = split_null(rest)
+            .ok_or_else(|| anyhow!("Invalid iTXt Chunk : Missing Translated Keyword Terminator"))?;
+        let language_tag = String::from_utf8(language_tag.to_vec())
+            .map_err(|_| anyhow!("Invalid iTXt Chunk : Language Tag Is Not ASCII"))?;
+        validate_language_tag(&language_tag)?;
+        let translated_keyword = String::from_utf8(translated_keyword.to_vec())
+            .map_err(|_| anyhow!("Invalid iTXt Chunk : Translated Keyword Is Not UTF-8"))?;
+
+        Ok(ITxt {
+            _keyword: keyword,
+            _compressed: compressed,
+            _compression_method: compression_method,
+            _language_tag: language_tag,
+            _translated_keyword: translated_keyword,
+            _text: text.to_vec(),
+        })
+    }
+}
+
+/// Latin-1 textual data (`tEXt`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Text {
+    _keyword: String,
+    _text: String,
+}
+
+impl Text {
+    pub const CHUNK_TYPE: &'static str = "tEXt";
+
+    /// Fails if the keyword is invalid or the text has characters outside
+    /// Latin-1, which tEXt cannot store.
+    pub fn new(keyword: &str, text: &str) -> Result<Self> {
+        validate_keyword(keyword)?;
+        if let Some(c) = text.chars().find(|c| *c as u32 > 0xFF) {
+            return Err(anyhow!(
+                "Invalid tEXt Text For Keyword {} : U+{:04X} Is Not Latin-1",
+                keyword,
+                c as u32
+            ));
+        }
+        Ok(Text {
+            _keyword: keyword.to_string(),
+            _text: text.to_string(),
+        })
+    }
+    pub fn keyword(&self) -> &str {
+        &self._keyword
+    }
+    pub fn text(&self) -> &str {
+        &self._text
+    }
+    pub fn to_chunk(&self) -> Chunk {
+        let mut data = latin1_encode(&self._keyword);
+        data.push(0);
+        data.extend(latin1_encode(&self._text));
+        Chunk::new(ChunkType::from_str(Text::CHUNK_TYPE).unwrap(), data)
+    }
+}
+
+impl TryFrom<&Chunk> for Text {
+    type Error = anyhow::Error;
+
+    fn try_from(chunk: &Chunk) -> Result<Self, Self::Error> {
+        if chunk.chunk_type().to_string() != Text::CHUNK_TYPE {
+            return Err(anyhow!(
+                "Invalid tEXt Chunk : Found Chunk Type {}",
+                chunk.chunk_type()
+            ));
+        }
+        let (keyword, text) = split_null(chunk.data())
+            .ok_or_else(|| anyhow!("Invalid tEXt Chunk : Missing Keyword Terminator"))?;
+        let keyword = latin1_decode(keyword);
+        validate_keyword(&keyword)?;
+        Ok(Text {
+            _keyword: keyword,
+            _text: latin1_decode(text),
+        })
+    }
+}
+
+/// Checks a tEXt/zTXt/iTXt keyword: 1-79 printable Latin-1 characters,
+/// no leading, trailing or consecutive spaces.
+pub fn validate_keyword(keyword: &str) -> Result<()> {
+    let count = keyword.chars().count();
+    if count == 0 || count > 79 {
+        return Err(anyhow!(
+            "Invalid Keyword {} : Length Must Be 1 To 79",
+            keyword
+        ));
+    }
+    if keyword
+        .chars()
+        .any(|c| !matches!(c as u32, 32..=126 | 161..=255))
+    {
+        return Err(anyhow!(
+            "Invalid Keyword {} : Non Printable Latin-1 Character",
+            keyword
+        ));
+    }
+    if keyword.starts_with(' ') || keyword.ends_with(' ') || keyword.contains("  ") {
+        return Err(anyhow!("Invalid Keyword {} : Misplaced Space", keyword));
+    }
+    Ok(())
+}
+
+fn validate_language_tag(tag: &str) -> Result<()> {
+    if tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        Ok(())
+    } else {
+        Err(anyhow!(
+            "Invalid Language Tag {} : Unexpected Character",
+            tag
+        ))
+    }
+}
+
+pub(crate) fn split_null(data: &[u8]) -> Option<(&[u8], &[u8])> {
+    let pos = data.iter().position(|b| *b == 0)?;
+    Some((&data[..pos], &data[pos + 1..]))
+}
+
+pub(crate) fn latin1_decode(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| *b as char).collect()
+}
+
+/// Callers must only pass Latin-1 text, e.g. a keyword that passed
+/// [`validate_keyword`]; other characters would be truncated.
+pub(crate) fn latin1_encode(s: &str) -> Vec<u8> {
+    s.chars().map(|c| c as u8).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_itxt_round_trip() {
+        let itxt = ITxt::new("Title", "Grüße")
+            .unwrap()
+            .with_language("de-DE", "Titel")
+            .unwrap();
+        let chunk = itxt.to_chunk();
+        assert_eq!(chunk.chunk_type().to_string(), "iTXt");
+
+        let parsed = ITxt::try_from(&chunk).unwrap();
+        assert_eq!(parsed, itxt);
+        assert_eq!(parsed.text().unwrap(), "Grüße");
+        assert_eq!(parsed.language_tag(), "de-DE");
+        assert_eq!(parsed.translated_keyword(), "Titel");
+    }
+
+    #[test]
+    fn test_itxt_invalid_language() {
+        let itxt = ITxt::new("Title", "x").unwrap();
+        assert!(itxt.clone().with_language("de DE", "Titel").is_err());
+        let err = itxt.with_language("de", "Ti\0tel").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid Translated Keyword \"Ti\\0tel\" : Contains NUL"
+        );
+    }
+
+    #[test]
+    fn test_itxt_invalid_utf8_text() {
+        let mut data = b"Comment\0\0\0\0\0".to_vec();
+        data.extend_from_slice(&[b'o', b'k', 0xFF]);
+        let chunk = Chunk::new(ChunkType::from_str("iTXt").unwrap(), data);
+
+        let itxt = ITxt::try_from(&chunk).unwrap();
+        assert!(itxt.text().is_err());
+        assert_eq!(itxt.text_lossy().unwrap(), "ok\u{FFFD}");
+    }
+
+    #[test]
+    fn test_itxt_compressed_text() {
+        let data = b"Comment\0\x01\0en\0\0\x78\x9c".to_vec();
+        let chunk = Chunk::new(ChunkType::from_str("iTXt").unwrap(), data);
+
+        let itxt = ITxt::try_from(&chunk).unwrap();
+        assert!(itxt.is_compressed());
+        assert_eq!(itxt.compression_method(), 0);
+        assert_eq!(ITxt::try_from(&itxt.to_chunk()).unwrap(), itxt);
+        assert_eq!(itxt.raw_text(), &[0x78, 0x9c]);
+        assert!(itxt.text().is_err());
+    }
+
+    #[test]
+    fn test_itxt_missing_terminator() {
+        let chunk = Chunk::new(ChunkType::from_str("iTXt").unwrap(), b"Comment".to_vec());
+        assert!(ITxt::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_itxt_wrong_chunk_type() {
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"a\0b".to_vec());
+        assert!(ITxt::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_text_round_trip() {
+        let text = Text::new("Creation Time", "café").unwrap();
+        let chunk = text.to_chunk();
+        assert_eq!(chunk.data(), b"Creation Time\0caf\xE9");
+        assert_eq!(Text::try_from(&chunk).unwrap(), text);
+        assert_eq!(text.keyword(), "Creation Time");
+        assert_eq!(text.text(), "café");
+
+        let err = Text::new("Price", "5 €").err().unwrap();
+        assert_eq!(
+            err.to_string(),
+            "Invalid tEXt Text For Keyword Price : U+20AC Is Not Latin-1"
+        );
+
+        let chunk = Chunk::new(ChunkType::from_str("tEXt").unwrap(), b"NoNull".to_vec());
+        assert!(Text::try_from(&chunk).is_err());
+    }
+
+    #[test]
+    fn test_validate_keyword() {
+        assert!(validate_keyword("Author").is_ok());
+        assert!(validate_keyword("").is_err());
+        assert!(validate_keyword(" Author").is_err());
+        assert!(validate_keyword("Two  Spaces").is_err());
+        assert!(validate_keyword(&"k".repeat(80)).is_err());
+        assert!(validate_keyword("Tab\tKey").is_err());
+    }
+}
@@ -0,0 +1,242 @@
+use crate::ancillary::Time;
+use crate::png::Png;
+use crate::text::{ITxt, Text};
+
+/// One temporal metadata value found in a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimelineEntry {
+    /// Caller supplied label of the file, usually its path.
+    pub source: String,
+    /// Where the value came from, e.g. `tIME` or `XMP xmp:CreateDate`.
+    pub origin: String,
+    /// `YYYY-MM-DDTHH:MM:SS` prefix of the value when it could be normalized.
+    pub timestamp: Option<String>,
+    pub raw: String,
+}
+
+const XMP_KEYWORD: &str = "XML:com.adobe.xmp";
+const XMP_DATE_FIELDS: [&str; 4] = [
+    "xmp:CreateDate",
+    "xmp:ModifyDate",
+    "xmp:MetadataDate",
+    "photoshop:DateCreated",
+];
+
+/// Collects tIME, "Creation Time" text, EXIF date strings and XMP dates from
+/// one image.
+pub fn entries(png: &Png, source: &str) -> Vec<TimelineEntry> {
+    let mut found = Vec::new();
+    let mut push = |origin: String, raw: String| {
+        found.push(TimelineEntry {
+            source: source.to_string(),
+            origin,
+            timestamp: normalize(&raw),
+            raw,
+        })
+    };
+    for chunk in png.chunks() {
+        match chunk.chunk_type().to_string().as_str() {
+            "tIME" => {
+                if let Ok(time) = Time::try_from(chunk) {
+                    push("tIME".to_string(), time.to_string());
+                }
+            }
+            "tEXt" => {
+                if let Ok(text) = Text::try_from(chunk) {
+                    if text.keyword() == "Creation Time" {
+                        push("tEXt Creation Time".to_string(), text.text().to_string());
+                    }
+                }
+            }
+            "iTXt" => {
+                let itxt = match ITxt::try_from(chunk) {
+                    Ok(itxt) => itxt,
+                    Err(_) => continue,
+                };
+                let text = match itxt.text_lossy() {
+                    Ok(text) => text,
+                    Err(_) => continue,
+                };
+                if itxt.keyword() == "Creation Time" {
+                    push("iTXt Creation Time".to_string(), text);
+                } else if itxt.keyword() == XMP_KEYWORD {
+                    for field in XMP_DATE_FIELDS {
+                        for value in xmp_values(&text, field) {
+                            push(format!("XMP {}", field), value);
+                        }
+                    }
+                }
+            }
+            "eXIf" => {
+                for value in exif_dates(chunk.data()) {
+                    push("eXIf DateTime".to_string(), value);
+                }
+            }
+            _ => {}
+        }
+    }
+    found
+}
+
+/// Merges the entries of several files into one list sorted by normalized
+/// timestamp; values that could not be normalized come last.
+pub fn timeline<'a, I>(files: I) -> Vec<TimelineEntry>
+where
+    I: IntoIterator<Item = (&'a str, &'a Png)>,
+{
+    let mut all: Vec<TimelineEntry> = files
+        .into_iter()
+        .flat_map(|(source, png)| entries(png, source))
+        .collect();
+    all.sort_by(|a, b| match (&a.timestamp, &b.timestamp) {
+        (Some(x), Some(y)) => x.cmp(y),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => a.raw.cmp(&b.raw),
+    });
+    all
+}
+
+/// Accepts ISO 8601 (`2022-02-12T13:05:59`, `2022-02-12 13:05:59`) and EXIF
+/// (`2022:02:12 13:05:59`) layouts.
+fn normalize(raw: &str) -> Option<String> {
+    let b = raw.trim().as_bytes();
+    if b.len() < 19 {
+        return None;
+    }
+    let digits = [0, 1, 2, 3, 5, 6, 8, 9, 11, 12, 14, 15, 17, 18];
+    if !digits.iter().all(|i| b[*i].is_ascii_digit())
+        || !matches!((b[4], b[7]), (b'-', b'-') | (b':', b':'))
+        || !matches!(b[10], b'T' | b' ')
+        || b[13] != b':'
+        || b[16] != b':'
+    {
+        return None;
+    }
+    let s = String::from_utf8_lossy(&b[..19]);
+    Some(format!(
+        "{}-{}-{}T{}",
+        &s[0..4],
+        &s[5..7],
+        &s[8..10],
+        &s[11..19]
+    ))
+}
+
+fn exif_dates(data: &[u8]) -> Vec<String> {
+    data.windows(19)
+        .filter_map(|w| std::str::from_utf8(w).ok())
+        .filter(|w| w.as_bytes()[4] == b':' && normalize(w).is_some())
+        .map(|w| w.to_string())
+        .collect()
+}
+
+fn xmp_values(xmp: &str, field: &str) -> Vec<String> {
+    let mut values = Vec::new();
+    for (open, close) in [
+        (format!("{}=\"", field), "\"".to_string()),
+        (format!("<{}>", field), format!("</{}>", field)),
+    ] {
+        let mut rest = xmp;
+        while let Some(start) = rest.find(&open) {
+            rest = &rest[start + open.len()..];
+            if let Some(end) = rest.find(&close) {
+                values.push(rest[..end].trim().to_string());
+                rest = &rest[end..];
+            }
+        }
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use std::str::FromStr;
+
+    fn png_with(chunks: Vec<Chunk>) -> Png {
+        Png::from_chunks(chunks)
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(
+            normalize("2022:02:12 13:05:59").as_deref(),
+            Some("2022-02-12T13:05:59")
+        );
+        assert_eq!(
+            normalize("2022-02-12T13:05:59+01:00").as_deref(),
+            Some("2022-02-12T13:05:59")
+        );
+        assert_eq!(normalize("Sat, 12 Feb 2022 13:05:59 GMT"), None);
+    }
+
+    #[test]
+    fn test_entries() {
+        let xmp = "<x:xmpmeta><rdf:Description xmp:CreateDate=\"2021-01-02T03:04:05\">\
+                   <xmp:ModifyDate>2021-06-07T08:09:10</xmp:ModifyDate></rdf:Description></x:xmpmeta>";
+        let mut exif = b"MM\0*junk".to_vec();
+        exif.extend(b"2020:05:06 07:08:09\0");
+        let png = png_with(vec![
+            Time {
+                year: 2022,
+                month: 2,
+                day: 12,
+                hour: 13,
+                minute: 5,
+                second: 59,
+            }
+            .to_chunk(),
+            Text::new("Creation Time", "Sat, 12 Feb 2022")
+                .unwrap()
+                .to_chunk(),
+            Text::new("Author", "nobody").unwrap().to_chunk(),
+            ITxt::new(XMP_KEYWORD, xmp).unwrap().to_chunk(),
+            Chunk::new(ChunkType::from_str("eXIf").unwrap(), exif),
+        ]);
+
+        let found = entries(&png, "a.png");
+        let origins: Vec<&str> = found.iter().map(|e| e.origin.as_str()).collect();
+        assert_eq!(
+            origins,
+            vec![
+                "tIME",
+                "tEXt Creation Time",
+                "XMP xmp:CreateDate",
+                "XMP xmp:ModifyDate",
+                "eXIf DateTime"
+            ]
+        );
+        assert_eq!(found[1].timestamp, None);
+        assert_eq!(found[4].timestamp.as_deref(), Some("2020-05-06T07:08:09"));
+    }
+
+    #[test]
+    fn test_timeline_sorted_across_files() {
+        let older = png_with(vec![Text::new("Creation Time", "2019-01-01 00:00:00")
+            .unwrap()
+            .to_chunk()]);
+        let newer = png_with(vec![
+            Text::new("Creation Time", "unknown").unwrap().to_chunk(),
+            Text::new("Creation Time", "2023-01-01T00:00:00")
+                .unwrap()
+                .to_chunk(),
+        ]);
+
+        let all = timeline([("new.png", &newer), ("old.png", &older)]);
+        let sources: Vec<(&str, &str)> = all
+            .iter()
+            .map(|e| (e.source.as_str(), e.raw.as_str()))
+            .collect();
+        assert_eq!(
+            sources,
+            vec![
+                ("old.png", "2019-01-01 00:00:00"),
+                ("new.png", "2023-01-01T00:00:00"),
+                ("new.png", "unknown")
+            ]
+        );
+    }
+}
@@ -0,0 +1,87 @@
+use std::time::Duration;
+
+/// How [`size`] and [`duration`] render numbers. Output never depends on the
+/// locale: `.` is the decimal separator and there are no digit groupings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Units {
+    /// Rounded, with binary prefixes: `4.7 KiB`, `1.50s`.
+    #[default]
+    Human,
+    /// Exact integers, for `--bytes` and scripts: `4803 B`, `1500000us`.
+    Exact,
+}
+
+const SIZE_UNITS: [&str; 5] = ["KiB", "MiB", "GiB", "TiB", "PiB"];
+
+/// Formats a byte count.
+pub fn size(bytes: u64, units: Units) -> String {
+    if units == Units::Exact || bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut value = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < SIZE_UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, SIZE_UNITS[unit])
+}
+
+/// Formats a file offset as hex followed by decimal, e.g. `0x000012c3 (4803)`,
+/// so it can be fed to a hex editor or to `dd skip=` alike.
+pub fn offset(offset: usize) -> String {
+    format!("0x{:08x} ({})", offset, offset)
+}
+
+/// Formats an elapsed time.
+pub fn duration(elapsed: Duration, units: Units) -> String {
+    let micros = elapsed.as_micros();
+    if units == Units::Exact {
+        return format!("{}us", micros);
+    }
+    let secs = elapsed.as_secs();
+    if micros < 1_000 {
+        format!("{}us", micros)
+    } else if micros < 1_000_000 {
+        format!("{:.1}ms", micros as f64 / 1_000.0)
+    } else if secs < 60 {
+        format!("{:.2}s", elapsed.as_secs_f64())
+    } else if secs < 3600 {
+        format!("{}m{:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h{:02}m", secs / 3600, secs % 3600 / 60)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size(0, Units::Human), "0 B");
+        assert_eq!(size(1023, Units::Human), "1023 B");
+        assert_eq!(size(4803, Units::Human), "4.7 KiB");
+        assert_eq!(size(3 << 20, Units::Human), "3.0 MiB");
+        assert_eq!(size(u64::MAX, Units::Human), "16384.0 PiB");
+        assert_eq!(size(4803, Units::Exact), "4803 B");
+    }
+
+    #[test]
+    fn test_offset() {
+        assert_eq!(offset(4803), "0x000012c3 (4803)");
+    }
+
+    #[test]
+    fn test_duration() {
+        assert_eq!(duration(Duration::from_micros(250), Units::Human), "250us");
+        assert_eq!(duration(Duration::from_micros(2500), Units::Human), "2.5ms");
+        assert_eq!(duration(Duration::from_millis(1500), Units::Human), "1.50s");
+        assert_eq!(duration(Duration::from_secs(125), Units::Human), "2m05s");
+        assert_eq!(duration(Duration::from_secs(7260), Units::Human), "2h01m");
+        assert_eq!(
+            duration(Duration::from_millis(1500), Units::Exact),
+            "1500000us"
+        );
+    }
+}
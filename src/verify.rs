@@ -0,0 +1,341 @@
+use crate::cancel::CancellationToken;
+use crate::checksum::{Checksum, PNG_CRC};
+use crate::codec::read_u32;
+use crate::events::{Event, Listener};
+use crate::png::Png;
+use crate::vfs::Vfs;
+use anyhow::{anyhow, Result};
+use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
+
+/// Outcome of [`quick_check`]: a cheap structural test meant to run right
+/// after writing a file, catching torn writes without parsing chunk data.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FileCheck {
+    Ok,
+    BadSignature,
+    /// Chunk framing runs past the end of the file at `offset`.
+    Truncated {
+        offset: usize,
+    },
+    /// Framing is intact but the file ends before an IEND chunk.
+    MissingIend,
+    /// The final (IEND) chunk's CRC does not match.
+    BadFinalCrc {
+        offset: usize,
+    },
+    /// Bytes follow IEND starting at `offset`.
+    TrailingData {
+        offset: usize,
+    },
+}
+
+impl FileCheck {
+    pub fn is_ok(&self) -> bool {
+        *self == FileCheck::Ok
+    }
+}
+
+impl Display for FileCheck {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            FileCheck::Ok => write!(f, "ok"),
+            FileCheck::BadSignature => write!(f, "not a PNG signature"),
+            FileCheck::Truncated { offset } => write!(f, "truncated at offset {}", offset),
+            FileCheck::MissingIend => write!(f, "no IEND chunk"),
+            FileCheck::BadFinalCrc { offset } => {
+                write!(f, "wrong IEND CRC at offset {}", offset)
+            }
+            FileCheck::TrailingData { offset } => {
+                write!(f, "trailing data at offset {}", offset)
+            }
+        }
+    }
+}
+
+/// Stored and recomputed CRC of one chunk, found by [`full_check`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkCrc {
+    pub offset: usize,
+    pub chunk_type: String,
+    pub length: u32,
+    pub stored: u32,
+    pub computed: u32,
+}
+
+impl ChunkCrc {
+    pub fn is_ok(&self) -> bool {
+        self.stored == self.computed
+    }
+}
+
+/// Outcome of [`full_check`]: the CRC of every chunk that could be framed,
+/// and the structural result. `framing` is never
+/// [`FileCheck::BadFinalCrc`]; a bad IEND CRC shows up in `chunks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FullCheck {
+    pub framing: FileCheck,
+    pub chunks: Vec<ChunkCrc>,
+}
+
+impl FullCheck {
+    pub fn is_ok(&self) -> bool {
+        self.framing.is_ok() && self.chunks.iter().all(ChunkCrc::is_ok)
+    }
+}
+
+/// Like [`quick_check`], but recomputes the CRC of every chunk and keeps
+/// going past mismatches, so all of them can be reported.
+pub fn full_check(value: &[u8]) -> FullCheck {
+    let mut chunks = Vec::new();
+    if !value.starts_with(&Png::STANDARD_HEADER) {
+        return FullCheck {
+            framing: FileCheck::BadSignature,
+            chunks,
+        };
+    }
+    let mut pos = 8;
+    let framing = loop {
+        if pos == value.len() {
+            break FileCheck::MissingIend;
+        }
+        let length = match read_u32(value, pos) {
+            Ok(length) if pos + 12 <= value.len() => length,
+            _ => break FileCheck::Truncated { offset: pos },
+        };
+        let end = pos + 12 + length as usize;
+        let Ok(stored) = read_u32(value, end - 4) else {
+            break FileCheck::Truncated { offset: pos };
+        };
+        let chunk_type: [u8; 4] = value[pos + 4..pos + 8].try_into().unwrap();
+        chunks.push(ChunkCrc {
+            offset: pos,
+            chunk_type: String::from_utf8_lossy(&chunk_type).into_owned(),
+            length,
+            stored,
+            computed: PNG_CRC.checksum(&chunk_type, &value[pos + 8..end - 4]),
+        });
+        if &chunk_type == b"IEND" {
+            if end != value.len() {
+                break FileCheck::TrailingData { offset: end };
+            }
+            break FileCheck::Ok;
+        }
+        pos = end;
+    };
+    FullCheck { framing, chunks }
+}
+
+/// Checks the signature, walks chunk lengths to IEND and verifies the CRC of
+/// the final chunk. Intermediate CRCs are not computed.
+pub fn quick_check(value: &[u8]) -> FileCheck {
+    if !value.starts_with(&Png::STANDARD_HEADER) {
+        return FileCheck::BadSignature;
+    }
+    let mut pos = 8;
+    loop {
+        if pos == value.len() {
+            return FileCheck::MissingIend;
+        }
+        let length = match read_u32(value, pos) {
+            Ok(length) if pos + 12 <= value.len() => length as usize,
+            _ => return FileCheck::Truncated { offset: pos },
+        };
+        let end = pos + 12 + length;
+        let Ok(stored) = read_u32(value, end - 4) else {
+            return FileCheck::Truncated { offset: pos };
+        };
+        if &value[pos + 4..pos + 8] == b"IEND" {
+            let computed = PNG_CRC.checksum(b"IEND", &value[pos + 8..end - 4]);
+            if stored != computed {
+                return FileCheck::BadFinalCrc { offset: pos };
+            }
+            if end != value.len() {
+                return FileCheck::TrailingData { offset: end };
+            }
+            return FileCheck::Ok;
+        }
+        pos = end;
+    }
+}
+
+pub fn verify_file(vfs: &dyn Vfs, path: &Path) -> Result<FileCheck> {
+    Ok(quick_check(&vfs.read(path)?))
+}
+
+/// Runs [`verify_file`] on each of `paths` in turn, reporting every result
+/// and the number of files done so far to `listener`. Stops at the first
+/// file that cannot be read. Once `token` is cancelled it stops and fails
+/// with [`Cancelled`](crate::cancel::Cancelled).
+pub fn verify_files(
+    vfs: &dyn Vfs,
+    paths: &[PathBuf],
+    token: &CancellationToken,
+    listener: &mut dyn Listener,
+) -> Result<Vec<FileCheck>> {
+    let mut checks = Vec::new();
+    for (i, path) in paths.iter().enumerate() {
+        token.check()?;
+        let check = verify_file(vfs, path)?;
+        listener.on_event(&Event::FileChecked {
+            path: path.clone(),
+            check: check.clone(),
+        });
+        listener.on_event(&Event::Progress {
+            done: i + 1,
+            total: paths.len(),
+        });
+        checks.push(check);
+    }
+    Ok(checks)
+}
+
+/// Moves a file that failed [`verify_file`] into `dir`, creating it if
+/// needed, and returns the new path.
+pub fn quarantine(vfs: &dyn Vfs, path: &Path, dir: &Path) -> Result<PathBuf> {
+    let name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid Quarantine Path {} : No File Name", path.display()))?;
+    vfs.create_dir_all(dir)?;
+    let target = dir.join(name);
+    if vfs.exists(&target) {
+        return Err(anyhow!(
+            "Invalid Quarantine Path {} : Already Exists",
+            target.display()
+        ));
+    }
+    vfs.rename(path, &target)?;
+    Ok(target)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::chunk::Chunk;
+    use crate::chunk_type::ChunkType;
+    use crate::vfs::MemFs;
+    use std::str::FromStr;
+
+    fn minimal_png() -> Vec<u8> {
+        Png::from_chunks(vec![
+            Chunk::new(ChunkType::from_str("IHDR").unwrap(), vec![0; 13]),
+            Chunk::new(ChunkType::from_str("IDAT").unwrap(), vec![1, 2, 3]),
+            Chunk::new(ChunkType::from_str("IEND").unwrap(), Vec::new()),
+        ])
+        .as_bytes()
+    }
+
+    #[test]
+    fn test_quick_check_ok() {
+        assert!(quick_check(&minimal_png()).is_ok());
+    }
+
+    #[test]
+    fn test_quick_check_failures() {
+        let png = minimal_png();
+        let iend = png.len() - 12;
+
+        assert_eq!(quick_check(b"GIF89a"), FileCheck::BadSignature);
+        assert_eq!(
+            quick_check(&png[..png.len() - 2]),
+            FileCheck::Truncated { offset: iend }
+        );
+        assert_eq!(quick_check(&png[..iend]), FileCheck::MissingIend);
+
+        let mut bad = png.clone();
+        *bad.last_mut().unwrap() ^= 1;
+        assert_eq!(quick_check(&bad), FileCheck::BadFinalCrc { offset: iend });
+
+        let mut trailing = png.clone();
+        trailing.push(0);
+        assert_eq!(
+            quick_check(&trailing),
+            FileCheck::TrailingData { offset: png.len() }
+        );
+    }
+
+    #[test]
+    fn test_verify_files_reports_events() {
+        let vfs = MemFs::new();
+        let png = minimal_png();
+        let paths = vec![PathBuf::from("a.png"), PathBuf::from("b.png")];
+        vfs.write(&paths[0], &png).unwrap();
+        vfs.write(&paths[1], &png[..8]).unwrap();
+
+        let mut events = Vec::new();
+        let token = CancellationToken::new();
+        let checks = verify_files(&vfs, &paths, &token, &mut |e: &Event| {
+            events.push(e.clone())
+        })
+        .unwrap();
+        assert_eq!(checks, vec![FileCheck::Ok, FileCheck::MissingIend]);
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[2],
+            Event::FileChecked {
+                path: PathBuf::from("b.png"),
+                check: FileCheck::MissingIend
+            }
+        );
+        assert_eq!(events[3], Event::Progress { done: 2, total: 2 });
+
+        let missing = vec![PathBuf::from("missing.png")];
+        assert!(verify_files(&vfs, &missing, &token, &mut |_: &Event| {}).is_err());
+
+        token.cancel();
+        let err = verify_files(&vfs, &paths, &token, &mut |_: &Event| {})
+            .err()
+            .unwrap();
+        assert!(err.is::<crate::cancel::Cancelled>());
+    }
+
+    #[test]
+    fn test_full_check() {
+        let png = minimal_png();
+        let check = full_check(&png);
+        assert!(check.is_ok());
+        assert_eq!(check.chunks.len(), 3);
+        assert_eq!(check.chunks[1].chunk_type, "IDAT");
+        assert_eq!(check.chunks[1].offset, 33);
+
+        let mut bad = png.clone();
+        bad[33 + 8] ^= 1;
+        *bad.last_mut().unwrap() ^= 1;
+        let check = full_check(&bad);
+        assert_eq!(check.framing, FileCheck::Ok);
+        assert!(!check.is_ok());
+        let failed: Vec<&str> = check
+            .chunks
+            .iter()
+            .filter(|c| !c.is_ok())
+            .map(|c| c.chunk_type.as_str())
+            .collect();
+        assert_eq!(failed, vec!["IDAT", "IEND"]);
+
+        let check = full_check(&png[..png.len() - 12]);
+        assert_eq!(check.framing, FileCheck::MissingIend);
+        assert_eq!(check.chunks.len(), 2);
+        assert_eq!(full_check(b"GIF89a").framing, FileCheck::BadSignature);
+        assert_eq!(
+            FileCheck::Truncated { offset: 8 }.to_string(),
+            "truncated at offset 8"
+        );
+    }
+
+    #[test]
+    fn test_verify_and_quarantine() {
+        let vfs = MemFs::new();
+        let path = Path::new("out/torn.png");
+        let png = minimal_png();
+        vfs.write(path, &png[..png.len() - 5]).unwrap();
+
+        assert!(!verify_file(&vfs, path).unwrap().is_ok());
+        let moved = quarantine(&vfs, path, Path::new("quarantine")).unwrap();
+        assert_eq!(moved, Path::new("quarantine/torn.png"));
+        assert!(!vfs.exists(path));
+        assert!(vfs.exists(&moved));
+
+        vfs.write(path, b"again").unwrap();
+        assert!(quarantine(&vfs, path, Path::new("quarantine")).is_err());
+    }
+}
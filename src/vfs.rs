@@ -0,0 +1,330 @@
+use anyhow::{anyhow, Result};
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io::Write;
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+
+/// File system operations used by the crate, so it can run against archives,
+/// test fixtures or sandboxes instead of the real disk.
+pub trait Vfs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>>;
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()>;
+    /// Replaces `path` with `data` such that an interrupted write leaves
+    /// either the old contents or the new ones, never a mix.
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> Result<()>;
+    fn rename(&self, from: &Path, to: &Path) -> Result<()>;
+    fn remove_file(&self, path: &Path) -> Result<()>;
+    fn create_dir_all(&self, path: &Path) -> Result<()>;
+    fn exists(&self, path: &Path) -> bool;
+    /// The canonical form of an existing `path`, so that two spellings of
+    /// the same file compare equal.
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf>;
+}
+
+/// The real file system, through `std::fs`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct StdFs;
+
+impl Vfs for StdFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        Ok(fs::read(path)?)
+    }
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        Ok(fs::write(path, data)?)
+    }
+    /// Writes `data` to a temporary file next to `path`, syncs it and
+    /// renames it over `path`.
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> Result<()> {
+        let name = path
+            .file_name()
+            .ok_or_else(|| anyhow!("Invalid Save Path {} : No File Name", path.display()))?;
+        let tmp = path.with_file_name(format!(
+            ".{}.{}.tmp",
+            name.to_string_lossy(),
+            std::process::id()
+        ));
+        let written = fs::File::create(&tmp)
+            .and_then(|mut file| {
+                file.write_all(data)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&tmp, path));
+        if written.is_err() {
+            let _ = fs::remove_file(&tmp);
+        }
+        Ok(written?)
+    }
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        Ok(fs::rename(from, to)?)
+    }
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        Ok(fs::remove_file(path)?)
+    }
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        Ok(fs::create_dir_all(path)?)
+    }
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        Ok(fs::canonicalize(path)?)
+    }
+}
+
+/// An in-memory file system. Directories are tracked only so that `exists`
+/// answers consistently; writing a file does not require its parent.
+#[derive(Debug, Default)]
+pub struct MemFs {
+    _files: Mutex<HashMap<PathBuf, Vec<u8>>>,
+    _dirs: Mutex<HashSet<PathBuf>>,
+}
+
+impl MemFs {
+    pub fn new() -> Self {
+        MemFs::default()
+    }
+    /// Paths of all files currently stored, sorted.
+    pub fn files(&self) -> Vec<PathBuf> {
+        let mut files: Vec<PathBuf> = self._files.lock().unwrap().keys().cloned().collect();
+        files.sort();
+        files
+    }
+}
+
+impl Vfs for MemFs {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self._files
+            .lock()
+            .unwrap()
+            .get(path)
+            .cloned()
+            .ok_or_else(|| anyhow!("Invalid Path {} : No Such File", path.display()))
+    }
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self._files
+            .lock()
+            .unwrap()
+            .insert(path.to_path_buf(), data.to_vec());
+        Ok(())
+    }
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.write(path, data)
+    }
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let mut files = self._files.lock().unwrap();
+        let data = files
+            .remove(from)
+            .ok_or_else(|| anyhow!("Invalid Path {} : No Such File", from.display()))?;
+        files.insert(to.to_path_buf(), data);
+        Ok(())
+    }
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self._files
+            .lock()
+            .unwrap()
+            .remove(path)
+            .map(|_| ())
+            .ok_or_else(|| anyhow!("Invalid Path {} : No Such File", path.display()))
+    }
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        let mut dirs = self._dirs.lock().unwrap();
+        for dir in path.ancestors() {
+            dirs.insert(dir.to_path_buf());
+        }
+        Ok(())
+    }
+    fn exists(&self, path: &Path) -> bool {
+        self._files.lock().unwrap().contains_key(path) || self._dirs.lock().unwrap().contains(path)
+    }
+    /// Drops `.` components and resolves `..` lexically; there are no links
+    /// to follow.
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        let mut normal = PathBuf::new();
+        for component in path.components() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir if normal.file_name().is_some() => {
+                    normal.pop();
+                }
+                component => normal.push(component),
+            }
+        }
+        if self.exists(&normal) {
+            Ok(normal)
+        } else {
+            Err(anyhow!("Invalid Path {} : No Such File", path.display()))
+        }
+    }
+}
+
+/// Whether a [`ModeFs`] lets writes through.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OpenMode {
+    #[default]
+    ReadWrite,
+    /// Every write, rename, removal and directory creation fails, so the
+    /// underlying files provably cannot be altered.
+    ReadOnly,
+}
+
+/// Wraps another [`Vfs`] and enforces an [`OpenMode`]. Reads always pass
+/// through; `StdFs` opens files for reading only.
+#[derive(Debug, Default)]
+pub struct ModeFs<V: Vfs> {
+    _inner: V,
+    _mode: OpenMode,
+}
+
+impl<V: Vfs> ModeFs<V> {
+    pub fn new(inner: V, mode: OpenMode) -> Self {
+        ModeFs {
+            _inner: inner,
+            _mode: mode,
+        }
+    }
+    pub fn mode(&self) -> OpenMode {
+        self._mode
+    }
+    pub fn into_inner(self) -> V {
+        self._inner
+    }
+    fn check(&self, operation: &str, path: &Path) -> Result<()> {
+        if self._mode == OpenMode::ReadOnly {
+            Err(anyhow!(
+                "Invalid {} {} : Read-Only Mode",
+                operation,
+                path.display()
+            ))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl<V: Vfs> Vfs for ModeFs<V> {
+    fn read(&self, path: &Path) -> Result<Vec<u8>> {
+        self._inner.read(path)
+    }
+    fn write(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.check("Write", path)?;
+        self._inner.write(path, data)
+    }
+    fn write_atomic(&self, path: &Path, data: &[u8]) -> Result<()> {
+        self.check("Write", path)?;
+        self._inner.write_atomic(path, data)
+    }
+    fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.check("Rename", from)?;
+        self._inner.rename(from, to)
+    }
+    fn remove_file(&self, path: &Path) -> Result<()> {
+        self.check("Remove", path)?;
+        self._inner.remove_file(path)
+    }
+    fn create_dir_all(&self, path: &Path) -> Result<()> {
+        self.check("Create Dir", path)?;
+        self._inner.create_dir_all(path)
+    }
+    fn exists(&self, path: &Path) -> bool {
+        self._inner.exists(path)
+    }
+    fn canonicalize(&self, path: &Path) -> Result<PathBuf> {
+        self._inner.canonicalize(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mem_fs_read_write() {
+        let vfs = MemFs::new();
+        let path = Path::new("a/b.png");
+        assert!(vfs.read(path).is_err());
+
+        vfs.write(path, b"data").unwrap();
+        assert!(vfs.exists(path));
+        assert_eq!(vfs.read(path).unwrap(), b"data");
+    }
+
+    #[test]
+    fn test_mem_fs_rename_and_remove() {
+        let vfs = MemFs::new();
+        vfs.write(Path::new("a.png"), b"data").unwrap();
+        vfs.rename(Path::new("a.png"), Path::new("b.png")).unwrap();
+        assert!(!vfs.exists(Path::new("a.png")));
+        assert_eq!(vfs.files(), vec![PathBuf::from("b.png")]);
+
+        vfs.remove_file(Path::new("b.png")).unwrap();
+        assert!(vfs.files().is_empty());
+        assert!(vfs.remove_file(Path::new("b.png")).is_err());
+        assert!(vfs.rename(Path::new("b.png"), Path::new("c.png")).is_err());
+    }
+
+    #[test]
+    fn test_mem_fs_dirs() {
+        let vfs = MemFs::new();
+        vfs.create_dir_all(Path::new("x/y/z")).unwrap();
+        assert!(vfs.exists(Path::new("x/y")));
+        assert!(!vfs.exists(Path::new("x/q")));
+    }
+
+    #[test]
+    fn test_mem_fs_canonicalize() {
+        let vfs = MemFs::new();
+        vfs.write(Path::new("x/a.png"), b"data").unwrap();
+        assert_eq!(
+            vfs.canonicalize(Path::new("./x/../x/./a.png")).unwrap(),
+            PathBuf::from("x/a.png")
+        );
+        assert!(vfs.canonicalize(Path::new("x/b.png")).is_err());
+    }
+
+    #[test]
+    fn test_read_only_mode() {
+        let inner = MemFs::new();
+        inner.write(Path::new("evidence.png"), b"data").unwrap();
+        let vfs = ModeFs::new(inner, OpenMode::ReadOnly);
+        let path = Path::new("evidence.png");
+
+        assert_eq!(vfs.read(path).unwrap(), b"data");
+        assert!(vfs.write(path, b"changed").is_err());
+        assert!(vfs.write_atomic(path, b"changed").is_err());
+        assert!(vfs.rename(path, Path::new("moved.png")).is_err());
+        assert!(vfs.remove_file(path).is_err());
+        assert!(vfs.create_dir_all(Path::new("out")).is_err());
+        assert!(crate::verify::quarantine(&vfs, path, Path::new("q")).is_err());
+
+        let inner = vfs.into_inner();
+        assert_eq!(inner.files(), vec![PathBuf::from("evidence.png")]);
+        assert_eq!(inner.read(path).unwrap(), b"data");
+
+        let vfs = ModeFs::new(inner, OpenMode::default());
+        assert!(vfs.write(path, b"changed").is_ok());
+    }
+
+    #[test]
+    fn test_std_fs() {
+        let dir = std::env::temp_dir().join(format!("pngme_vfs_{}", std::process::id()));
+        let vfs = StdFs;
+        vfs.create_dir_all(&dir).unwrap();
+        let a = dir.join("a.bin");
+        let b = dir.join("b.bin");
+        vfs.write(&a, b"data").unwrap();
+        vfs.rename(&a, &b).unwrap();
+        assert!(!vfs.exists(&a));
+        assert_eq!(vfs.read(&b).unwrap(), b"data");
+        assert_eq!(
+            vfs.canonicalize(&dir.join(".").join("b.bin")).unwrap(),
+            fs::canonicalize(&b).unwrap()
+        );
+        vfs.write_atomic(&b, b"new data").unwrap();
+        assert_eq!(vfs.read(&b).unwrap(), b"new data");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        vfs.remove_file(&b).unwrap();
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}